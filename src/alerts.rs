@@ -0,0 +1,319 @@
+//! Multi-stage alerts from `[[alerts]]` rules in the config. Each rule
+//! escalates through warn (notification), crit (critical alert and the
+//! crit command) and emergency (the emergency command, once crit has
+//! lasted long enough). Warn and crit are reached by the reading itself
+//! or by how fast it's rising, whichever is further along. Each level
+//! can also ring the terminal bell or flash the screen, set in
+//! `[signals]`. In a dry run the commands are logged instead of run
+//!
+//! `[[leaks]]` rules skip the stages: a leak or a low reservoir is an
+//! emergency as soon as it's seen, whether alerts are silenced or not
+
+use crate::{
+    alert,
+    config::{AlertConfig, LeakConfig, LeakKind, Signal, SignalConfig},
+    dryrun, notify,
+};
+use std::{
+    collections::VecDeque,
+    process::{Command, Stdio},
+    time::{Duration, Instant},
+};
+
+/// A level is only left once the reading has dropped this far below its
+/// threshold, so a sensor hovering around it doesn't spam alerts
+const HYSTERESIS: f64 = 1.0;
+
+/// The same for rates, in degrees per minute
+const RATE_HYSTERESIS: f64 = 0.5;
+
+/// Rates are worked out over this much history
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// No rate until the history spans this long, so the first couple of
+/// samples (or one noisy step) can't read as a steep climb
+const RATE_MIN_SPAN: Duration = Duration::from_secs(20);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Level {
+    Normal,
+    Warn,
+    Crit,
+    Emergency,
+}
+
+/// Run a rule's command without waiting for it, or log it in a dry run
+fn run(command: &[String], dry_run: Option<&mut dryrun::Log>) {
+    let Some((program, args)) = command.split_first() else {
+        return;
+    };
+
+    if let Some(log) = dry_run {
+        log.record(&format!("run {}", command.join(" ")));
+        return;
+    }
+
+    let mut cmd = Command::new(program);
+    cmd.args(args);
+
+    cmd.stdin(Stdio::null());
+    cmd.stdout(Stdio::null());
+    cmd.stderr(Stdio::null());
+
+    match cmd.spawn() {
+        Ok(mut child) => {
+            std::thread::spawn(move || child.wait());
+        }
+        Err(e) => notify(&format!("failed to spawn {program}: {e}"), true),
+    }
+}
+
+#[derive(Debug, Clone)]
+struct State {
+    level: Level,
+    crit_since: Option<Instant>,
+
+    /// Readings over the last `RATE_WINDOW`, oldest first
+    samples: VecDeque<(Instant, f64)>,
+}
+
+impl State {
+    /// Record `val` and return how fast the reading has been rising, in
+    /// degrees per minute, once there's enough history
+    fn rate(&mut self, val: f64) -> Option<f64> {
+        let now = Instant::now();
+        self.samples.push_back((now, val));
+        while self
+            .samples
+            .front()
+            .is_some_and(|(t, _)| now.duration_since(*t) > RATE_WINDOW)
+        {
+            self.samples.pop_front();
+        }
+
+        let (then, first) = *self.samples.front()?;
+        let span = now.duration_since(then);
+        if span < RATE_MIN_SPAN {
+            return None;
+        }
+
+        Some((val - first) / span.as_secs_f64() * 60.0)
+    }
+}
+
+/// Where each rule currently is
+pub struct Escalations {
+    states: Vec<State>,
+
+    /// How many times a rule went up a level, silenced or not
+    pub fired: usize,
+}
+
+impl Escalations {
+    /// Whether any rule is at warn or above
+    pub fn active(&self) -> bool {
+        self.states.iter().any(|s| s.level >= Level::Warn)
+    }
+
+    pub fn new(rules: &[AlertConfig]) -> Self {
+        let state = State {
+            level: Level::Normal,
+            crit_since: None,
+            samples: VecDeque::new(),
+        };
+        Self {
+            states: vec![state; rules.len()],
+            fired: 0,
+        }
+    }
+
+    /// Move each rule to the level its sensor's reading calls for and
+    /// act on the ones that went up. When `silenced` is set the commands
+    /// still run but nothing is shown or spoken. Returns the terminal
+    /// signals of the levels that were reached, for the caller to give
+    pub fn update(
+        &mut self,
+        rules: &[AlertConfig],
+        readings: &[(String, f64)],
+        silenced: bool,
+        tts: &[String],
+        signals: &SignalConfig,
+        mut dry_run: Option<&mut dryrun::Log>,
+    ) -> Vec<Signal> {
+        let mut ret = Vec::new();
+
+        for (rule, state) in rules.iter().zip(&mut self.states) {
+            let Some(val) = readings
+                .iter()
+                .find(|(l, _)| *l == rule.sensor)
+                .map(|(_, v)| *v)
+            else {
+                continue;
+            };
+
+            // the level the reading is at, only counting a level as left
+            // once it's clear of the threshold by the hysteresis
+            let at = |threshold: Option<f64>, current: bool| {
+                threshold.is_some_and(|t| {
+                    val >= t || (current && val > t - HYSTERESIS)
+                })
+            };
+
+            let rate = state.rate(val);
+            let rising = |threshold: Option<f64>, current: bool| {
+                threshold.zip(rate).is_some_and(|(t, r)| {
+                    r >= t || (current && r > t - RATE_HYSTERESIS)
+                })
+            };
+
+            let crit = state.level >= Level::Crit;
+            let warn = state.level >= Level::Warn;
+            let mut level =
+                if at(rule.crit, crit) || rising(rule.crit_rate, crit) {
+                    Level::Crit
+                } else if at(rule.warn, warn) || rising(rule.warn_rate, warn) {
+                    Level::Warn
+                } else {
+                    Level::Normal
+                };
+
+            if level == Level::Crit {
+                let since = *state.crit_since.get_or_insert_with(Instant::now);
+                let persisted = rule.emergency_minutes.is_some_and(|m| {
+                    since.elapsed() >= Duration::from_secs(m * 60)
+                });
+                if persisted {
+                    level = Level::Emergency;
+                }
+            } else {
+                state.crit_since = None;
+            }
+
+            if level > state.level {
+                self.fired += 1;
+                // say how fast it's climbing when that's what set it off
+                let threshold = match level {
+                    Level::Warn => rule.warn,
+                    _ => rule.crit,
+                };
+                let reading = match rate {
+                    Some(r) if !at(threshold, false) => {
+                        format!("{val:.1}, rising {r:.1}/min")
+                    }
+                    _ => format!("{val:.1}"),
+                };
+                let message = match level {
+                    Level::Warn => format!("{} is at {reading}", rule.sensor),
+                    Level::Crit => {
+                        format!("CRITICAL: {} is at {reading}", rule.sensor)
+                    }
+                    _ => format!(
+                        "EMERGENCY: {} has been critical for {} minutes",
+                        rule.sensor,
+                        rule.emergency_minutes.unwrap_or(0)
+                    ),
+                };
+
+                // jumping straight past a level still runs its command
+                if state.level < Level::Crit && level >= Level::Crit {
+                    run(&rule.crit_command, dry_run.as_deref_mut());
+                }
+                if level == Level::Emergency {
+                    run(&rule.emergency_command, dry_run.as_deref_mut());
+                }
+
+                if !silenced {
+                    if level == Level::Warn {
+                        notify(&message, false);
+                    } else {
+                        alert(&message, tts);
+                    }
+
+                    ret.push(match level {
+                        Level::Warn => signals.warn,
+                        Level::Crit => signals.crit,
+                        _ => signals.emergency,
+                    });
+                }
+            }
+
+            state.level = level;
+        }
+
+        ret
+    }
+}
+
+/// Which `[[leaks]]` rules have gone off
+pub struct Leaks {
+    tripped: Vec<bool>,
+}
+
+impl Leaks {
+    pub fn new(rules: &[LeakConfig]) -> Self {
+        Self {
+            tripped: vec![false; rules.len()],
+        }
+    }
+
+    pub fn is_tripped(&self, rule: usize) -> bool {
+        self.tripped.get(rule).copied().unwrap_or(false)
+    }
+
+    /// Whether any rule has gone off
+    pub fn active(&self) -> bool {
+        self.tripped.iter().any(|t| *t)
+    }
+
+    /// Trip each rule whose reading calls for it, given in `values` in
+    /// rule order (None when its sensor wasn't found, which leaves the
+    /// rule as it was). Rules that just tripped alert and run their
+    /// command straight away. A level only clears once it's back above
+    /// its `min` by the hysteresis. Returns the messages of the rules
+    /// that tripped, for the caller to log and signal
+    pub fn update(
+        &mut self,
+        rules: &[LeakConfig],
+        values: &[Option<f64>],
+        tts: &[String],
+        mut dry_run: Option<&mut dryrun::Log>,
+    ) -> Vec<String> {
+        let mut ret = Vec::new();
+
+        for ((rule, tripped), val) in
+            rules.iter().zip(&mut self.tripped).zip(values)
+        {
+            let Some(val) = *val else {
+                continue;
+            };
+
+            let now = match rule.kind {
+                LeakKind::Leak => val >= 0.5,
+                LeakKind::Level => rule.min.is_some_and(|m| {
+                    val < m || (*tripped && val < m + HYSTERESIS)
+                }),
+            };
+
+            if now && !*tripped {
+                let message = match rule.kind {
+                    LeakKind::Leak => {
+                        format!("EMERGENCY: {} detected a leak", rule.label)
+                    }
+                    LeakKind::Level => {
+                        format!(
+                            "EMERGENCY: {} is down to {val:.0}%",
+                            rule.label
+                        )
+                    }
+                };
+                run(&rule.command, dry_run.as_deref_mut());
+                alert(&message, tts);
+                ret.push(message);
+            }
+
+            *tripped = now;
+        }
+
+        ret
+    }
+}
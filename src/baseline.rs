@@ -0,0 +1,84 @@
+//! Idle baseline captured shortly after startup, so the temps table can
+//! show how far each sensor is from its usual idle reading
+
+use std::{
+    collections::BTreeMap,
+    fs::read_to_string,
+    time::{Duration, Instant},
+};
+
+/// The machine counts as idle while the 1 minute load average is below
+/// this
+const IDLE_LOAD: f64 = 1.0;
+
+fn load_avg() -> Option<f64> {
+    read_to_string("/proc/loadavg")
+        .ok()?
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()
+}
+
+pub struct Baseline {
+    duration: Duration,
+
+    /// Start of the current idle stretch, and the running sum and count
+    /// of each sensor during it
+    idle_since: Option<Instant>,
+    sums: BTreeMap<String, (f64, usize)>,
+
+    captured: Option<BTreeMap<String, f64>>,
+}
+
+impl Baseline {
+    pub fn new(minutes: u64) -> Self {
+        Self {
+            duration: Duration::from_secs(minutes * 60),
+            idle_since: None,
+            sums: BTreeMap::new(),
+            captured: None,
+        }
+    }
+
+    /// Feed one tick's readings. Returns true on the tick the baseline
+    /// is captured, which happens once the machine has been idle for the
+    /// whole duration
+    pub fn update(&mut self, readings: &[(String, f64)]) -> bool {
+        if self.captured.is_some() {
+            return false;
+        }
+
+        // without a load average we can't tell, so assume idle rather
+        // than never capturing
+        if load_avg().is_some_and(|l| l >= IDLE_LOAD) {
+            self.idle_since = None;
+            self.sums.clear();
+            return false;
+        }
+
+        let since = *self.idle_since.get_or_insert_with(Instant::now);
+        for (label, value) in readings {
+            let (sum, n) = self.sums.entry(label.clone()).or_default();
+            *sum += value;
+            *n += 1;
+        }
+
+        if since.elapsed() < self.duration {
+            return false;
+        }
+
+        let averages = std::mem::take(&mut self.sums)
+            .into_iter()
+            .map(|(label, (sum, n))| (label, sum / n as f64))
+            .collect();
+        self.captured = Some(averages);
+        true
+    }
+
+    /// Difference between `value` and the baseline of `label`, once
+    /// there is one
+    pub fn delta(&self, label: &str, value: f64) -> Option<f64> {
+        Some(value - self.captured.as_ref()?.get(label)?)
+    }
+}
@@ -0,0 +1,150 @@
+//! Laptop batteries and AC adapters from /sys/class/power_supply.
+//! Batteries of wireless mice and keyboards (scope `Device`) are left
+//! out, only the ones powering the machine count
+
+use crate::provider::{Reading, SensorProvider};
+use anyhow::{bail, Result};
+use std::{
+    fs::{read_dir, read_to_string},
+    path::{Path, PathBuf},
+};
+
+const POWER_SUPPLY_ROOT: &str = "/sys/class/power_supply";
+
+/// Charge of all batteries together and where the power comes from
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Charge {
+    /// Charge in percent, of the combined capacity when there are
+    /// several batteries
+    pub percent: f64,
+    pub charging: bool,
+    /// Whether an AC adapter is plugged in
+    pub ac_online: bool,
+}
+
+pub struct Battery {
+    /// `BAT0`, `BAT1`... with their directories
+    batteries: Vec<(String, PathBuf)>,
+}
+
+fn read(path: &Path, file: &str) -> Option<String> {
+    read_to_string(path.join(file))
+        .ok()
+        .map(|v| v.trim().to_string())
+}
+
+fn read_f64(path: &Path, file: &str) -> Option<f64> {
+    read(path, file)?.parse().ok()
+}
+
+/// Supplies of the given `type` (`Battery`, `Mains`) that power the
+/// system, sorted by name
+fn supplies(kind: &str) -> Vec<(String, PathBuf)> {
+    let mut ret: Vec<(String, PathBuf)> = read_dir(POWER_SUPPLY_ROOT)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|e| {
+            let path = e.path();
+            if read(&path, "type")? != kind
+                || read(&path, "scope").is_some_and(|s| s == "Device")
+            {
+                return None;
+            }
+            Some((e.file_name().into_string().ok()?, path))
+        })
+        .collect();
+
+    ret.sort();
+    ret
+}
+
+pub fn init() -> Result<Battery> {
+    let batteries = supplies("Battery");
+    if batteries.is_empty() {
+        bail!("no battery in {POWER_SUPPLY_ROOT}");
+    }
+
+    Ok(Battery { batteries })
+}
+
+/// Power going in or out of a battery in watts, from `power_now` or
+/// else current times voltage
+fn watts(path: &Path) -> Option<f64> {
+    let uw = read_f64(path, "power_now").or_else(|| {
+        Some(
+            read_f64(path, "current_now")? * read_f64(path, "voltage_now")?
+                / 1_000_000.0,
+        )
+    })?;
+    Some(uw.abs() / 1_000_000.0)
+}
+
+/// Charge of the batteries, None without any
+pub fn charge() -> Option<Charge> {
+    let batteries = supplies("Battery");
+    if batteries.is_empty() {
+        return None;
+    }
+
+    // weighted by capacity where the driver reports energy, so a small
+    // second battery doesn't count as much as the main one
+    let (mut now, mut full) = (0.0, 0.0);
+    let mut percents = Vec::new();
+    let mut charging = false;
+    for (_, path) in &batteries {
+        match (read_f64(path, "energy_now"), read_f64(path, "energy_full")) {
+            (Some(n), Some(f)) => {
+                now += n;
+                full += f;
+            }
+            _ => percents.extend(read_f64(path, "capacity")),
+        }
+        charging |= read(path, "status").is_some_and(|s| s == "Charging");
+    }
+
+    let percent = if full > 0.0 {
+        now / full * 100.0
+    } else if !percents.is_empty() {
+        percents.iter().sum::<f64>() / percents.len() as f64
+    } else {
+        return None;
+    };
+
+    let ac_online = supplies("Mains")
+        .iter()
+        .any(|(_, path)| read(path, "online").is_some_and(|o| o == "1"));
+
+    Some(Charge {
+        percent: percent.clamp(0.0, 100.0),
+        charging,
+        ac_online,
+    })
+}
+
+impl SensorProvider for Battery {
+    /// Charge or discharge rate and temperature (when the driver has
+    /// one) of every battery as `powerN` and `tempN` of a `battery`
+    /// chip, numbered from 1 and labelled with the battery's name
+    fn poll(&mut self) -> Vec<Reading> {
+        let mut ret = Vec::new();
+
+        for (i, (name, path)) in self.batteries.iter().enumerate() {
+            // tenths of a degree
+            let temp = read_f64(path, "temp").map(|t| ("temp", t / 10.0));
+            let power = watts(path).map(|w| ("power", w));
+
+            for (kind, value) in [temp, power].into_iter().flatten() {
+                ret.push(Reading {
+                    chip: "battery".to_string(),
+                    serial: None,
+                    feature: format!("{kind}{}", i + 1),
+                    label: Some(name.clone()),
+                    value,
+                });
+            }
+        }
+
+        ret
+    }
+}
@@ -0,0 +1,77 @@
+//! Copying text to the system clipboard. The usual clipboard tools are
+//! tried first, and when none of them is installed (or there's no
+//! display, over SSH) the text is sent with an OSC 52 escape, which most
+//! terminals pass on to the clipboard of the machine they run on
+
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+/// Clipboard tools and their arguments, in the order they're tried
+const TOOLS: [(&str, &[&str]); 4] = [
+    ("wl-copy", &[]),
+    ("xclip", &["-selection", "clipboard"]),
+    ("xsel", &["--clipboard", "--input"]),
+    ("pbcopy", &[]),
+];
+
+const BASE64: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+
+    for chunk in data.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+
+    out
+}
+
+/// Pipe `text` into `program`, returning whether it took it
+fn pipe(program: &str, args: &[&str], text: &str) -> bool {
+    let Ok(mut child) = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+    else {
+        return false;
+    };
+
+    let written = child
+        .stdin
+        .take()
+        .is_some_and(|mut stdin| stdin.write_all(text.as_bytes()).is_ok());
+
+    child.wait().is_ok_and(|s| s.success()) && written
+}
+
+/// Copy `text` to the clipboard and return what it went through
+pub fn copy(text: &str) -> &'static str {
+    for (program, args) in TOOLS {
+        if pipe(program, args, text) {
+            return program;
+        }
+    }
+
+    let mut stdout = std::io::stdout();
+    let _ = write!(stdout, "\x1b]52;c;{}\x07", base64(text.as_bytes()));
+    let _ = stdout.flush();
+    "the terminal"
+}
@@ -0,0 +1,2353 @@
+//! Configuration, stored as TOML. The system-wide file
+//! (`/etc/sensors-mon/config.toml`) is read first and the user's file
+//! (`~/.config/sensors-mon/config.toml`) is layered on top of it. Every
+//! key is optional and a missing file is the same as an empty one.
+//!
+//! Keys set in a later file replace the earlier value, except for the
+//! `[chart]`, `[layout]`, `[scale]`, `[hardware]`, `[drives]`,
+//! `[processes]` and `[signals]` tables which are merged key by key.
+//! Arrays (including `[[loops]]`, `[[sensors]]`, `[[fans]]`,
+//! `[[voltages]]`, `[[commands]]`, `[[network]]`, `[[views]]`,
+//! `[[fused]]`, `[[alerts]]`, `[[leaks]]` and `[[export]]`) and
+//! `[serial]` are replaced whole
+
+use crate::{model::Fusion, provider::Reading};
+use anyhow::{anyhow, Context, Result};
+use ratatui::style::Color;
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    fs::{copy, create_dir_all, read_to_string, write},
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+use toml_edit::{DocumentMut, Item, Value};
+
+/// More tiles than this don't fit across a normal terminal
+pub const MAX_PINNED: usize = 6;
+
+/// Which series are drawn on the chart. Readings are collected for all
+/// of them either way
+#[derive(Debug, Clone)]
+pub struct ChartConfig {
+    pub cpu: bool,
+    pub coolant: bool,
+    pub gpu: bool,
+
+    /// The ambient role, such as a USB thermometer, so the coolant line
+    /// can be read against the room
+    pub ambient: bool,
+
+    /// Pressure stall percentages from /proc/pressure. These share the
+    /// temperature axis, which then starts at zero
+    pub psi_cpu: bool,
+    pub psi_io: bool,
+    pub psi_memory: bool,
+
+    /// Drive temperatures, one line per drive
+    pub drives: bool,
+
+    /// CPU package power, as a line scaled from zero watts at the
+    /// bottom of the plot to the highest power in the window (rounded
+    /// up) at the top. The legend gives it in watts
+    pub cpu_power: bool,
+
+    /// Battery charge or discharge rate, on the same watt scale as the
+    /// CPU power line
+    pub battery: bool,
+
+    /// Draw a guide line at each series' current value
+    pub guides: bool,
+
+    /// Draw faint gridlines at minute boundaries and at the y labels
+    pub grid: bool,
+
+    /// Ticks averaged together when drawing the lines, so small steps
+    /// in a reading show up as a ramp rather than a jump. 1 draws the
+    /// readings as they are
+    pub smooth: u64,
+
+    /// Labels on the y axis, including the top and bottom ones
+    pub y_labels: u64,
+
+    /// Decimal places of the y labels. A tight auto-scaled span of a few
+    /// degrees needs one to tell the labels apart
+    pub y_precision: u64,
+
+    /// Draw the chart with Braille dots. When unset this is detected
+    /// from the terminal, falling back to plain dots on the Linux console
+    /// and non-UTF-8 locales where Braille shows up as garbage
+    pub braille: Option<bool>,
+
+    /// Labels of the series whose line is colored by its value, blue at
+    /// `gradient_min` through red at the sensor's `[[alerts]]` crit (or
+    /// `gradient_max` without one), instead of in the series' own color
+    pub gradient: Vec<String>,
+    pub gradient_min: f64,
+    pub gradient_max: f64,
+}
+
+/// Where one of the dashboard's fixed sensors is read from
+#[derive(Debug, Clone)]
+pub struct RoleConfig {
+    /// Chip name pattern. The first chip matching it with the feature is
+    /// used
+    pub chip: glob::Pattern,
+
+    /// Feature name (`temp1`) or the label the driver gives it (`Tctl`)
+    pub feature: String,
+    pub label: String,
+
+    /// Chart color, for the sensors that are charted
+    pub color: Color,
+
+    /// Reads per tick, of which the median is used. 1 reads once
+    pub samples: usize,
+}
+
+impl RoleConfig {
+    fn new(chip: &str, feature: &str, label: &str, color: Color) -> Self {
+        Self {
+            // the defaults are all valid patterns
+            chip: glob::Pattern::new(chip).unwrap(),
+            feature: feature.to_string(),
+            label: label.to_string(),
+            color,
+            samples: 1,
+        }
+    }
+}
+
+/// The sensors the dashboard is built around. The defaults match the
+/// machine this was written for
+#[derive(Debug, Clone)]
+pub struct HardwareConfig {
+    /// CPU control temperature, which is also what throttling is judged
+    /// by
+    pub cpu: RoleConfig,
+    /// CPU die temperature, shown in the table
+    pub ccd: RoleConfig,
+    /// The coolant probes. Both are used for the cooling level and the
+    /// first one is charted
+    pub coolant1: RoleConfig,
+    pub coolant2: RoleConfig,
+    /// Room air temperature, the reference for the delta-T gauge
+    pub ambient: RoleConfig,
+    /// GPU label and color. The chip is the GPU's hwmon chip, only read
+    /// when NVML isn't available
+    pub gpu: RoleConfig,
+    /// Where the GPU readings come from
+    pub gpu_provider: GpuProvider,
+    /// How the coolant probes are combined for the cooling level and
+    /// the delta-T gauge. The max means a single probe that reads low
+    /// can't mask a real problem
+    pub coolant_fusion: Fusion,
+}
+
+pub const ROLES: [&str; 6] =
+    ["cpu", "ccd", "coolant1", "coolant2", "ambient", "gpu"];
+
+impl HardwareConfig {
+    pub fn role(&self, name: &str) -> Option<&RoleConfig> {
+        Some(match name {
+            "cpu" => &self.cpu,
+            "ccd" => &self.ccd,
+            "coolant1" => &self.coolant1,
+            "coolant2" => &self.coolant2,
+            "ambient" => &self.ambient,
+            "gpu" => &self.gpu,
+            _ => return None,
+        })
+    }
+
+    fn role_mut(&mut self, name: &str) -> Option<&mut RoleConfig> {
+        Some(match name {
+            "cpu" => &mut self.cpu,
+            "ccd" => &mut self.ccd,
+            "coolant1" => &mut self.coolant1,
+            "coolant2" => &mut self.coolant2,
+            "ambient" => &mut self.ambient,
+            "gpu" => &mut self.gpu,
+            _ => return None,
+        })
+    }
+}
+
+/// Source of the GPU gauges, `provider` in `[hardware.gpu]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GpuProvider {
+    /// NVML, then the hwmon chip, then an Intel GPU
+    Auto,
+    Nvml,
+    /// The hwmon chip matching `hardware.gpu.chip`
+    Hwmon,
+    /// An Intel Arc or integrated GPU through the i915 or xe driver
+    Intel,
+}
+
+impl GpuProvider {
+    pub fn name(self) -> &'static str {
+        match self {
+            GpuProvider::Auto => "auto",
+            GpuProvider::Nvml => "nvml",
+            GpuProvider::Hwmon => "hwmon",
+            GpuProvider::Intel => "intel",
+        }
+    }
+
+    fn parse(s: &str) -> Result<Self> {
+        Ok(match s {
+            "auto" => GpuProvider::Auto,
+            "nvml" => GpuProvider::Nvml,
+            "hwmon" => GpuProvider::Hwmon,
+            "intel" => GpuProvider::Intel,
+            _ => anyhow::bail!(
+                "unknown GPU provider `{s}`, expected auto, nvml, hwmon or intel"
+            ),
+        })
+    }
+
+    /// Whether this provider may read from `other`
+    pub fn allows(self, other: GpuProvider) -> bool {
+        self == GpuProvider::Auto || self == other
+    }
+}
+
+fn parse_fusion(s: &str) -> Result<Fusion> {
+    Ok(match s {
+        "min" => Fusion::Min,
+        "max" => Fusion::Max,
+        "avg" => Fusion::Avg,
+        _ => anyhow::bail!("unknown mode `{s}`, expected min, max or avg"),
+    })
+}
+
+fn parse_color(s: &str) -> Result<Color> {
+    s.parse().map_err(|_| {
+        anyhow!(
+            "invalid color `{s}`, expected a name like \"red\" or \"#rrggbb\""
+        )
+    })
+}
+
+/// Names of the series the chart can show, as used in `[[views]]`
+pub const CHART_SERIES: [&str; 11] = [
+    "cpu",
+    "coolant",
+    "ambient",
+    "gpu",
+    "psi_cpu",
+    "psi_io",
+    "psi_memory",
+    "drives",
+    "cpu_power",
+    "battery",
+    "network",
+];
+
+/// A named set of chart series that can be switched to with a key
+#[derive(Debug, Clone)]
+pub struct ViewConfig {
+    pub name: String,
+    pub series: Vec<String>,
+}
+
+/// A named group of sensors in one coolant loop, summarized together
+#[derive(Debug, Clone)]
+pub struct LoopConfig {
+    pub name: String,
+    pub sensors: Vec<String>,
+}
+
+/// Sizes of the dashboard's bottom panel, or a custom arrangement of
+/// the panes
+#[derive(Debug, Clone)]
+pub struct LayoutConfig {
+    /// Height of the bottom panel in rows. When unset it grows to fit
+    /// the gauges or the temps table, whichever is taller
+    pub bottom_height: Option<u16>,
+
+    /// Width of the temps table in columns
+    pub table_width: u16,
+
+    /// Custom dashboard layout. When set, the two sizes above aren't
+    /// used
+    pub tree: Option<LayoutNode>,
+}
+
+/// A piece of the dashboard that can be placed in a layout tree
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pane {
+    Chart,
+    Table,
+    Gauges,
+    /// The `pinned` sensors
+    Tiles,
+    Fans,
+    /// Utilization and clocks
+    Cpu,
+    /// Flow rate and pump metrics
+    Loop,
+    /// Voltage rails
+    Voltages,
+    /// Busiest processes
+    Processes,
+}
+
+impl Pane {
+    pub fn name(self) -> &'static str {
+        match self {
+            Pane::Chart => "chart",
+            Pane::Table => "table",
+            Pane::Gauges => "gauges",
+            Pane::Tiles => "tiles",
+            Pane::Fans => "fans",
+            Pane::Cpu => "cpu",
+            Pane::Loop => "loop",
+            Pane::Voltages => "voltages",
+            Pane::Processes => "processes",
+        }
+    }
+
+    fn parse(s: &str) -> Result<Self> {
+        Ok(match s {
+            "chart" => Pane::Chart,
+            "table" => Pane::Table,
+            "gauges" => Pane::Gauges,
+            "tiles" => Pane::Tiles,
+            "fans" => Pane::Fans,
+            "cpu" => Pane::Cpu,
+            "loop" => Pane::Loop,
+            "voltages" => Pane::Voltages,
+            "processes" => Pane::Processes,
+            _ => anyhow::bail!(
+                "unknown pane `{s}`, expected chart, table, gauges, tiles, \
+                 fans, cpu, loop, voltages or processes"
+            ),
+        })
+    }
+}
+
+/// How much of its parent a layout node takes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Size {
+    /// Share of whatever the fixed sizes leave over. This is the default
+    Fill,
+    /// Rows or columns
+    Length(u16),
+    Percent(u16),
+}
+
+#[derive(Debug, Clone)]
+pub enum LayoutNode {
+    Pane(Pane, Size),
+    Split {
+        /// Children stacked top to bottom rather than left to right
+        vertical: bool,
+        size: Size,
+        children: Vec<LayoutNode>,
+    },
+}
+
+impl LayoutNode {
+    pub fn size(&self) -> Size {
+        match self {
+            LayoutNode::Pane(_, size) | LayoutNode::Split { size, .. } => *size,
+        }
+    }
+
+    /// Parse `{ pane = "chart", size = 14 }` or `{ split = "vertical",
+    /// children = [...] }`. Sizes are a number of cells, a percentage
+    /// like "30%", or "fill"
+    fn parse(item: &Item) -> Result<Self> {
+        let size = match item.get("size") {
+            None => Size::Fill,
+            Some(v) => match (v.as_integer(), v.as_str()) {
+                (Some(n), _) => Size::Length(
+                    u16::try_from(n)
+                        .map_err(|_| anyhow!("`size` is too big"))?,
+                ),
+                (_, Some("fill")) => Size::Fill,
+                (_, Some(s)) if s.ends_with('%') => Size::Percent(
+                    s[..s.len() - 1]
+                        .parse()
+                        .with_context(|| format!("invalid size `{s}`"))?,
+                ),
+                _ => anyhow::bail!(
+                    "`size` must be a number, a percentage or \"fill\""
+                ),
+            },
+        };
+
+        if let Some(pane) = get_str(item, "pane")? {
+            return Ok(LayoutNode::Pane(Pane::parse(&pane)?, size));
+        }
+
+        let vertical = match get_str(item, "split")?.as_deref() {
+            Some("vertical") => true,
+            Some("horizontal") => false,
+            Some(s) => anyhow::bail!(
+                "unknown split `{s}`, expected vertical or horizontal"
+            ),
+            None => {
+                anyhow::bail!("every layout node needs a `pane` or `split`")
+            }
+        };
+
+        // an inline array, or [[...children]] tables
+        let children: Vec<Item> = match item.get("children") {
+            Some(c) if c.is_array() => c
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(|v| Item::Value(v.clone()))
+                .collect(),
+            Some(c) if c.is_array_of_tables() => c
+                .as_array_of_tables()
+                .into_iter()
+                .flatten()
+                .map(|t| Item::Table(t.clone()))
+                .collect(),
+            _ => anyhow::bail!("a split needs a `children` array"),
+        };
+        let children = children
+            .iter()
+            .map(LayoutNode::parse)
+            .collect::<Result<Vec<_>>>()?;
+        if children.is_empty() {
+            anyhow::bail!("a split needs at least one child");
+        }
+
+        Ok(LayoutNode::Split {
+            vertical,
+            size,
+            children,
+        })
+    }
+
+    /// The node as a TOML inline table
+    fn to_toml(&self) -> String {
+        let size = match self.size() {
+            Size::Fill => String::new(),
+            Size::Length(n) => format!(", size = {n}"),
+            Size::Percent(p) => format!(", size = \"{p}%\""),
+        };
+
+        match self {
+            LayoutNode::Pane(pane, _) => {
+                format!("{{ pane = {}{size} }}", toml_str(pane.name()))
+            }
+            LayoutNode::Split {
+                vertical, children, ..
+            } => {
+                let split = if *vertical { "vertical" } else { "horizontal" };
+                let children: Vec<String> =
+                    children.iter().map(LayoutNode::to_toml).collect();
+                format!(
+                    "{{ split = {}{size}, children = [{}] }}",
+                    toml_str(split),
+                    children.join(", ")
+                )
+            }
+        }
+    }
+}
+
+/// The sensors a `[[sensors]]`, `[[fans]]`, `[[voltages]]` or `[[leaks]]`
+/// rule picks, by glob patterns on the chip and feature names, so a rule
+/// keeps working when a chip's bus address changes between boots
+#[derive(Debug, Clone)]
+pub struct Selector {
+    pub chip: glob::Pattern,
+    pub feature: glob::Pattern,
+
+    /// Serial number of the USB device the chip belongs to. Tells apart
+    /// identical devices, whose names only differ by bus address
+    pub serial: Option<String>,
+}
+
+impl Selector {
+    /// Read the `chip`, `feature` and `serial` keys of the rule `name`.
+    /// `chip` can be left out when there's a `serial`, `feature` when
+    /// there's a `default_feature`
+    fn parse(
+        item: &Item,
+        name: &str,
+        default_feature: Option<&str>,
+    ) -> Result<Self> {
+        let serial = get_str(item, "serial")?;
+        let chip = match get_str(item, "chip")? {
+            Some(chip) => chip,
+            None if serial.is_some() => "*".to_string(),
+            None => anyhow::bail!("{name} needs a `chip` or `serial`"),
+        };
+        let feature = match get_str(item, "feature")? {
+            Some(feature) => feature,
+            None => default_feature
+                .ok_or_else(|| anyhow!("{name} needs a `feature`"))?
+                .to_string(),
+        };
+
+        let pattern = |p: &str| {
+            glob::Pattern::new(p)
+                .with_context(|| format!("invalid pattern `{p}` in {name}"))
+        };
+
+        Ok(Self {
+            chip: pattern(&chip)?,
+            feature: pattern(&feature)?,
+            serial,
+        })
+    }
+
+    /// The feature pattern matches either the feature name or its label
+    pub fn matches(&self, reading: &Reading) -> bool {
+        self.chip.matches(&reading.chip)
+            && (self.feature.matches(&reading.feature)
+                || reading
+                    .label
+                    .as_deref()
+                    .is_some_and(|l| self.feature.matches(l)))
+            && self
+                .serial
+                .as_deref()
+                .is_none_or(|s| Some(s) == reading.serial.as_deref())
+    }
+
+    /// The `chip`, `feature` and `serial` lines of a rule's table
+    fn to_toml(&self, out: &mut String) {
+        let _ = writeln!(out, "chip = {}", toml_str(self.chip.as_str()));
+        let _ = writeln!(out, "feature = {}", toml_str(self.feature.as_str()));
+        if let Some(serial) = &self.serial {
+            let _ = writeln!(out, "serial = {}", toml_str(serial));
+        }
+    }
+}
+
+/// Extra temperature sensors
+#[derive(Debug, Clone)]
+pub struct SensorConfig {
+    /// Table label. When the patterns match more than one feature, the
+    /// feature's own label is appended to tell them apart. When unset,
+    /// each match is shown by its own label (`Tctl`, `SYSTIN`), or by
+    /// its feature name if the driver doesn't give one
+    pub label: Option<String>,
+    pub select: Selector,
+
+    /// Also plot the sensor on the chart, in this color
+    pub chart: Option<Color>,
+
+    /// Reads per tick, of which the median is used. 1 reads once
+    pub samples: usize,
+}
+
+impl SensorConfig {
+    /// The rule as a `[[sensors]]` table
+    pub fn to_toml(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "[[sensors]]");
+        if let Some(label) = &self.label {
+            let _ = writeln!(out, "label = {}", toml_str(label));
+        }
+        self.select.to_toml(&mut out);
+        if let Some(color) = self.chart {
+            let _ = writeln!(out, "chart = true");
+            let _ = writeln!(out, "color = {}", toml_str(&color.to_string()));
+        }
+        if self.samples > 1 {
+            let _ = writeln!(out, "samples = {}", self.samples);
+        }
+        out
+    }
+}
+
+/// Rotational speed a fan gauge is drawn against when the `[[fans]]`
+/// rule doesn't give one
+pub const DEFAULT_FAN_MAX_RPM: u16 = 3000;
+
+/// A fan header picked like `[[sensors]]`, and the label it's shown
+/// under in the fans panel
+#[derive(Debug, Clone)]
+pub struct FanConfig {
+    /// Panel label. When the patterns match more than one fan, the
+    /// fan's own label is appended to tell them apart
+    pub label: String,
+    pub select: Selector,
+
+    /// Speed the gauge is full at
+    pub max_rpm: u16,
+
+    /// Measured or datasheet (RPM, dB(A)) points for the noise
+    /// estimate, sorted by RPM. Empty for a typical fan
+    pub noise: Vec<(f64, f64)>,
+}
+
+impl FanConfig {
+    /// The rule as a `[[fans]]` table
+    pub fn to_toml(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "[[fans]]");
+        let _ = writeln!(out, "label = {}", toml_str(&self.label));
+        self.select.to_toml(&mut out);
+        let _ = writeln!(out, "max_rpm = {}", self.max_rpm);
+        if !self.noise.is_empty() {
+            let points: Vec<String> = self
+                .noise
+                .iter()
+                .map(|(rpm, db)| format!("[{rpm:?}, {db:?}]"))
+                .collect();
+            let _ = writeln!(out, "noise = [{}]", points.join(", "));
+        }
+        out
+    }
+}
+
+/// A command whose output is read as a sensor, for anything no chip
+/// reports (an Arduino probe, a router over SSH)
+#[derive(Debug, Clone)]
+pub struct CommandConfig {
+    /// Table label
+    pub label: String,
+
+    /// Program and its arguments, run without a shell. The first number
+    /// it prints is the reading
+    pub command: Vec<String>,
+
+    /// Milliseconds between runs, every tick when unset
+    pub interval: Option<u64>,
+
+    /// Factor the number is multiplied by
+    pub scale: f64,
+
+    /// Also plot it on the chart, in this color
+    pub chart: Option<Color>,
+}
+
+/// A network interface whose throughput gets a gauge
+#[derive(Debug, Clone)]
+pub struct NetworkConfig {
+    /// Name as in /proc/net/dev (`enp5s0`)
+    pub interface: String,
+
+    /// Gauge label, the interface name when not set
+    pub label: String,
+
+    /// Rate in MB/s the gauge is full at
+    pub max: f64,
+
+    /// Also plot receive and transmit on the chart, in these colors
+    pub chart: Option<(Color, Color)>,
+}
+
+/// A voltage rail picked like `[[sensors]]`, with the range it should
+/// stay in
+#[derive(Debug, Clone)]
+pub struct VoltageConfig {
+    /// Table label. When the patterns match more than one rail, the
+    /// rail's own label is appended to tell them apart
+    pub label: String,
+    pub select: Selector,
+
+    /// Factor for rails the chip reads through a divider, which the
+    /// driver reports unscaled (a 12V rail read as about 1V)
+    pub scale: f64,
+    /// Readings outside this range, in volts after scaling, are out of
+    /// spec
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+/// Several sensors that measure the same thing combined into one, so a
+/// single miscalibrated probe can't trip or mask an alert on it
+#[derive(Debug, Clone)]
+pub struct FusedConfig {
+    pub label: String,
+    /// Labels of the sensors combined, missing ones are left out
+    pub sensors: Vec<String>,
+    pub mode: Fusion,
+}
+
+/// Staged alert thresholds for one sensor. Every stage is optional
+#[derive(Debug, Clone)]
+pub struct AlertConfig {
+    /// Label of the sensor to watch
+    pub sensor: String,
+
+    /// Send a notification at this reading
+    pub warn: Option<f64>,
+
+    /// Send a critical alert and run `crit_command` at this reading
+    pub crit: Option<f64>,
+    pub crit_command: Vec<String>,
+
+    /// The same two stages for how fast the reading rises, in degrees
+    /// per minute. A coolant loop climbing quickly means the pump
+    /// stopped long before the temperature itself is alarming
+    pub warn_rate: Option<f64>,
+    pub crit_rate: Option<f64>,
+
+    /// Run `emergency_command` once the reading has been at `crit` for
+    /// this many minutes
+    pub emergency_minutes: Option<u64>,
+    pub emergency_command: Vec<String>,
+}
+
+/// What a `[[leaks]]` sensor reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LeakKind {
+    /// A leak detector, reading 1 (or anything from 0.5) when wet
+    Leak,
+    /// Reservoir fill level in percent
+    Level,
+}
+
+impl LeakKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            LeakKind::Leak => "leak",
+            LeakKind::Level => "level",
+        }
+    }
+
+    fn parse(s: &str) -> Result<Self> {
+        Ok(match s {
+            "leak" => LeakKind::Leak,
+            "level" => LeakKind::Level,
+            _ => {
+                anyhow::bail!("unknown leak kind `{s}`, expected leak or level")
+            }
+        })
+    }
+}
+
+/// A leak detector or coolant fill level sensor, picked like
+/// `[[voltages]]`. Either going off is an emergency straight away, there's
+/// no warn stage and silencing alerts doesn't hold it back
+#[derive(Debug, Clone)]
+pub struct LeakConfig {
+    pub label: String,
+    pub select: Selector,
+    pub kind: LeakKind,
+
+    /// Fill level, in percent, below which the reservoir is low. Only
+    /// for `level` sensors
+    pub min: Option<f64>,
+
+    /// Run as soon as the leak is detected or the level drops below
+    /// `min`, say to shut the machine down
+    pub command: Vec<String>,
+}
+
+/// What the terminal does when an alert reaches a level, on top of the
+/// notification
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Signal {
+    None,
+    /// Ring the terminal bell
+    Bell,
+    /// Invert the screen's colors briefly
+    Flash,
+}
+
+impl Signal {
+    pub fn name(self) -> &'static str {
+        match self {
+            Signal::None => "none",
+            Signal::Bell => "bell",
+            Signal::Flash => "flash",
+        }
+    }
+
+    fn parse(s: &str) -> Result<Self> {
+        Ok(match s {
+            "none" => Signal::None,
+            "bell" => Signal::Bell,
+            "flash" => Signal::Flash,
+            _ => anyhow::bail!(
+                "unknown signal `{s}`, expected none, bell or flash"
+            ),
+        })
+    }
+}
+
+/// Terminal signal for each `[[alerts]]` level
+#[derive(Debug, Clone)]
+pub struct SignalConfig {
+    pub warn: Signal,
+    pub crit: Signal,
+    pub emergency: Signal,
+}
+
+/// NVMe and SATA drive temperatures, shown as rows in the temps table
+#[derive(Debug, Clone)]
+pub struct DrivesConfig {
+    pub enabled: bool,
+
+    /// Ask `smartctl` about drives without an hwmon device (SATA drives
+    /// when the `drivetemp` module isn't loaded). Needs root
+    pub smart: bool,
+}
+
+/// The panel of the busiest processes by CPU and GPU use
+#[derive(Debug, Clone)]
+pub struct ProcessesConfig {
+    pub enabled: bool,
+
+    /// Processes listed
+    pub count: usize,
+}
+
+/// Serial port of a companion desk display and the sensors sent to it
+#[derive(Debug, Clone)]
+pub struct SerialConfig {
+    pub device: PathBuf,
+    pub sensors: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportKind {
+    Csv,
+    Influx,
+    Mqtt,
+    Prometheus,
+}
+
+impl ExportKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            ExportKind::Csv => "csv",
+            ExportKind::Influx => "influx",
+            ExportKind::Mqtt => "mqtt",
+            ExportKind::Prometheus => "prometheus",
+        }
+    }
+
+    fn parse(s: &str) -> Result<Self> {
+        Ok(match s {
+            "csv" => ExportKind::Csv,
+            "influx" => ExportKind::Influx,
+            "mqtt" => ExportKind::Mqtt,
+            "prometheus" => ExportKind::Prometheus,
+            _ => anyhow::bail!(
+                "unknown export kind `{s}`, expected csv, influx, mqtt or prometheus"
+            ),
+        })
+    }
+}
+
+/// Somewhere samples are exported to
+#[derive(Debug, Clone)]
+pub struct ExportConfig {
+    pub kind: ExportKind,
+
+    /// File path for csv, write URL for influx, pushgateway URL for
+    /// prometheus, or `host[:port]` of the broker for mqtt
+    pub target: String,
+
+    /// MQTT topic to publish to
+    pub topic: String,
+
+    /// Samples sent per write
+    pub batch: usize,
+}
+
+/// Where a setting's value came from, when it isn't the default
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Origin {
+    File(PathBuf),
+    CommandLine,
+}
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Time between samples in milliseconds
+    pub interval: u64,
+
+    /// Span of the chart in seconds
+    pub window: u64,
+
+    /// Show session min/max in the chart legend
+    pub legend_min_max: bool,
+
+    /// Count driver-reserved VRAM as used in the GPU memory gauge, which
+    /// matches what nvidia-smi reports
+    pub vram_reserved: bool,
+
+    /// Log cooling level changes and alert commands to
+    /// `dryrun::default_path()` instead of carrying them out
+    pub dry_run: bool,
+
+    /// Print a summary of the session (min/avg/max of every sensor,
+    /// alerts, energy) to stdout on quit
+    pub exit_summary: bool,
+
+    /// Electricity price per kWh, used for the cost ticker in the status
+    /// bar. The ticker is hidden when this isn't set
+    pub rate: Option<f64>,
+
+    /// How long alerts stay silenced after pressing the silence key
+    pub silence_minutes: u64,
+
+    pub chart: ChartConfig,
+
+    pub layout: LayoutConfig,
+
+    pub hardware: HardwareConfig,
+
+    /// Labels of sensors shown as big number tiles above the chart
+    pub pinned: Vec<String>,
+
+    /// Command and arguments used to read critical alerts out loud, with
+    /// `{message}` replaced by the alert text. Empty to disable
+    pub tts_command: Vec<String>,
+
+    /// CSV file every sample is appended to, for `sensors-mon compare`.
+    /// Nothing is recorded when this isn't set
+    pub history: Option<PathBuf>,
+
+    /// Path of the control socket scripts can send commands to. The
+    /// socket isn't created when this isn't set
+    pub socket: Option<PathBuf>,
+
+    /// Coolant loops for dual-loop builds. Each gets a summary row in
+    /// the temps table
+    pub loops: Vec<LoopConfig>,
+
+    /// Per-sensor factors readings are multiplied by, for drivers that
+    /// report in the wrong unit. Sensors without one are scaled
+    /// automatically when their readings are implausible
+    pub scale: BTreeMap<String, f64>,
+
+    /// Companion display to mirror readings to every tick
+    pub serial: Option<SerialConfig>,
+
+    /// Exporters every sample is sent to
+    pub exports: Vec<ExportConfig>,
+
+    /// Extra sensors shown in the temps table
+    pub sensors: Vec<SensorConfig>,
+
+    /// Fans shown in the fans panel. Every fan is shown, by its own
+    /// label, when there are none
+    pub fans: Vec<FanConfig>,
+
+    /// Rails shown in the voltages panel. Every voltage is shown, by its
+    /// own label, when there are none
+    pub voltages: Vec<VoltageConfig>,
+
+    /// Commands read as sensors, each shown in the table by its label
+    pub commands: Vec<CommandConfig>,
+
+    /// Interfaces with a throughput gauge
+    pub network: Vec<NetworkConfig>,
+
+    /// Chart views cycled through with the view key. The `[chart]`
+    /// settings are used when no view is selected
+    pub views: Vec<ViewConfig>,
+
+    /// Sensors combined from others, shown in the temps table and
+    /// usable by alerts like any other
+    pub fused: Vec<FusedConfig>,
+
+    /// Per-sensor staged alerts
+    pub alerts: Vec<AlertConfig>,
+
+    /// Leak detectors and coolant level sensors, shown in the loop panel
+    pub leaks: Vec<LeakConfig>,
+
+    pub signals: SignalConfig,
+
+    pub drives: DrivesConfig,
+
+    pub processes: ProcessesConfig,
+
+    /// Minutes of idle after startup averaged into a baseline, shown as
+    /// a delta column in the temps table. No column when this isn't set
+    pub baseline_minutes: Option<u64>,
+
+    /// Files the config was read from, in the order they were layered.
+    /// Empty when only the defaults are in use
+    pub sources: Vec<PathBuf>,
+
+    /// The layer that last set each key, by dotted path (`chart.cpu`,
+    /// `sensors[0].chip`). Keys that aren't in here have their default
+    pub origins: BTreeMap<String, Origin>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            interval: 3000,
+            window: 5 * 60,
+            legend_min_max: false,
+            vram_reserved: false,
+            dry_run: false,
+            exit_summary: false,
+            rate: None,
+            silence_minutes: 30,
+            chart: ChartConfig {
+                cpu: true,
+                coolant: true,
+                gpu: true,
+                ambient: false,
+                psi_cpu: false,
+                psi_io: false,
+                psi_memory: false,
+                drives: false,
+                cpu_power: false,
+                battery: false,
+                guides: true,
+                grid: false,
+                smooth: 1,
+                y_labels: 6,
+                y_precision: 0,
+                braille: None,
+                gradient: Vec::new(),
+                gradient_min: 30.0,
+                gradient_max: 90.0,
+            },
+            layout: LayoutConfig {
+                bottom_height: None,
+                table_width: 40,
+                tree: None,
+            },
+            hardware: HardwareConfig {
+                cpu: RoleConfig::new(
+                    "k10temp-pci-00c3",
+                    "temp1",
+                    "7800 X3D CTL",
+                    Color::Red,
+                ),
+                ccd: RoleConfig::new(
+                    "k10temp-pci-00c3",
+                    "temp3",
+                    "7800 X3D CCD",
+                    Color::Magenta,
+                ),
+                // the quadro's bus address changes between boots
+                coolant1: RoleConfig::new(
+                    "quadro-hid-*",
+                    "temp1",
+                    "Coolant 1",
+                    Color::Blue,
+                ),
+                coolant2: RoleConfig::new(
+                    "quadro-hid-*",
+                    "temp2",
+                    "Coolant 2",
+                    Color::Cyan,
+                ),
+                ambient: RoleConfig::new(
+                    "quadro-hid-*",
+                    "temp4",
+                    "Ambient",
+                    Color::Gray,
+                ),
+                gpu: RoleConfig::new(
+                    "nvidia*",
+                    "temp1",
+                    "RTX 4070",
+                    Color::Green,
+                ),
+                gpu_provider: GpuProvider::Auto,
+                coolant_fusion: Fusion::Max,
+            },
+            pinned: Vec::new(),
+            tts_command: Vec::new(),
+            history: None,
+            socket: None,
+            loops: Vec::new(),
+            scale: BTreeMap::new(),
+            serial: None,
+            exports: Vec::new(),
+            sensors: Vec::new(),
+            fans: Vec::new(),
+            voltages: Vec::new(),
+            commands: Vec::new(),
+            network: Vec::new(),
+            views: Vec::new(),
+            fused: Vec::new(),
+            alerts: Vec::new(),
+            leaks: Vec::new(),
+            signals: SignalConfig {
+                warn: Signal::None,
+                crit: Signal::None,
+                emergency: Signal::None,
+            },
+            drives: DrivesConfig {
+                enabled: true,
+                smart: false,
+            },
+            processes: ProcessesConfig {
+                enabled: false,
+                count: 5,
+            },
+            baseline_minutes: None,
+            sources: Vec::new(),
+            origins: BTreeMap::new(),
+        }
+    }
+}
+
+/// Machine-wide config, for things like sensor maps an admin ships with
+/// the machine
+pub const SYSTEM_PATH: &str = "/etc/sensors-mon/config.toml";
+
+/// `$XDG_CONFIG_HOME/sensors-mon/config.toml`, falling back to
+/// `~/.config` when XDG_CONFIG_HOME isn't set
+pub fn default_path() -> PathBuf {
+    let mut path = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) => PathBuf::from(dir),
+        None => {
+            let mut home =
+                PathBuf::from(std::env::var_os("HOME").unwrap_or_default());
+            home.push(".config");
+            home
+        }
+    };
+
+    path.push("sensors-mon");
+    path.push("config.toml");
+    path
+}
+
+fn get_bool(item: &Item, key: &str) -> Result<Option<bool>> {
+    match item.get(key) {
+        None => Ok(None),
+        Some(v) => v
+            .as_bool()
+            .map(Some)
+            .ok_or_else(|| anyhow!("`{key}` must be true or false")),
+    }
+}
+
+fn get_u64(item: &Item, key: &str) -> Result<Option<u64>> {
+    match item.get(key) {
+        None => Ok(None),
+        Some(v) => v
+            .as_integer()
+            .and_then(|i| u64::try_from(i).ok())
+            .map(Some)
+            .ok_or_else(|| anyhow!("`{key}` must be a positive integer")),
+    }
+}
+
+fn get_u16(item: &Item, key: &str) -> Result<Option<u16>> {
+    match get_u64(item, key)? {
+        None => Ok(None),
+        Some(v) => u16::try_from(v)
+            .map(Some)
+            .map_err(|_| anyhow!("`{key}` is too large")),
+    }
+}
+
+fn get_f64(item: &Item, key: &str) -> Result<Option<f64>> {
+    match item.get(key) {
+        None => Ok(None),
+        Some(v) => v
+            .as_float()
+            .or_else(|| v.as_integer().map(|i| i as f64))
+            .map(Some)
+            .ok_or_else(|| anyhow!("`{key}` must be a number")),
+    }
+}
+
+/// Reads per tick for the median filter, capped so a typo can't stall
+/// every tick
+fn get_samples(item: &Item) -> Result<Option<usize>> {
+    const MAX_SAMPLES: u64 = 9;
+
+    match get_u64(item, "samples")? {
+        Some(v @ 1..=MAX_SAMPLES) => Ok(Some(v as usize)),
+        Some(_) => {
+            anyhow::bail!("`samples` must be between 1 and {MAX_SAMPLES}")
+        }
+        None => Ok(None),
+    }
+}
+
+fn get_str(item: &Item, key: &str) -> Result<Option<String>> {
+    match item.get(key) {
+        None => Ok(None),
+        Some(v) => v
+            .as_str()
+            .map(|s| Some(s.to_string()))
+            .ok_or_else(|| anyhow!("`{key}` must be a string")),
+    }
+}
+
+fn get_str_array(item: &Item, key: &str) -> Result<Option<Vec<String>>> {
+    let err = || anyhow!("`{key}` must be an array of strings");
+
+    match item.get(key) {
+        None => Ok(None),
+        Some(v) => v
+            .as_array()
+            .ok_or_else(err)?
+            .iter()
+            .map(|v| v.as_str().map(str::to_string).ok_or_else(err))
+            .collect::<Result<Vec<_>>>()
+            .map(Some),
+    }
+}
+
+/// An array of `[x, y]` number pairs
+fn get_points(item: &Item, key: &str) -> Result<Option<Vec<(f64, f64)>>> {
+    let err = || anyhow!("`{key}` must be an array of [number, number] pairs");
+    let number = |v: &Value| {
+        v.as_float()
+            .or_else(|| v.as_integer().map(|i| i as f64))
+            .ok_or_else(err)
+    };
+
+    match item.get(key) {
+        None => Ok(None),
+        Some(v) => v
+            .as_array()
+            .ok_or_else(err)?
+            .iter()
+            .map(|p| match p.as_array() {
+                Some(p) if p.len() == 2 => Ok((
+                    number(p.get(0).ok_or_else(err)?)?,
+                    number(p.get(1).ok_or_else(err)?)?,
+                )),
+                _ => Err(err()),
+            })
+            .collect::<Result<Vec<_>>>()
+            .map(Some),
+    }
+}
+
+/// Dotted paths of every value in `item`, with arrays of tables indexed
+/// like `sensors[0].chip`. Inline tables count as one value
+fn key_paths(item: &Item, prefix: &str, out: &mut Vec<String>) {
+    match item {
+        Item::Table(t) => {
+            for (k, v) in t.iter() {
+                key_paths(v, &format!("{prefix}{k}."), out);
+            }
+        }
+        Item::ArrayOfTables(a) => {
+            let prefix = prefix.trim_end_matches('.');
+            for (i, t) in a.iter().enumerate() {
+                for (k, v) in t.iter() {
+                    key_paths(v, &format!("{prefix}[{i}].{k}."), out);
+                }
+            }
+        }
+        Item::Value(_) => {
+            out.push(prefix.trim_end_matches('.').to_string());
+        }
+        Item::None => {}
+    }
+}
+
+fn doc_paths(text: &str) -> Result<Vec<String>> {
+    let doc: DocumentMut = text.parse()?;
+    let mut out = Vec::new();
+    key_paths(doc.as_item(), "", &mut out);
+    Ok(out)
+}
+
+/// What [`Config::migrate`] did to a file
+#[derive(Debug)]
+pub struct Migration {
+    /// Where the original was copied to
+    pub backup: PathBuf,
+
+    /// Keys of the original that aren't in the rewritten file because
+    /// nothing reads them (any more)
+    pub dropped: Vec<String>,
+}
+
+/// Quote a string for writing as a TOML basic string
+pub fn toml_str(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+impl Config {
+    /// Load the configs at `paths` on top of the defaults, each one
+    /// overriding the ones before it. Files that don't exist are skipped
+    pub fn load(paths: &[&Path]) -> Result<Self> {
+        let mut config = Self::default();
+
+        for path in paths {
+            let text = match read_to_string(path) {
+                Ok(t) => t,
+                Err(e) if e.kind() == ErrorKind::NotFound => continue,
+                Err(e) => {
+                    return Err(e)
+                        .with_context(|| format!("reading {}", path.display()))
+                }
+            };
+
+            config
+                .merge(&text)
+                .with_context(|| format!("in {}", path.display()))?;
+            config.sources.push(path.to_path_buf());
+            for key in doc_paths(&text)? {
+                config.origins.insert(key, Origin::File(path.to_path_buf()));
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// Apply every key set in `text` on top of the current values
+    fn merge(&mut self, text: &str) -> Result<()> {
+        let doc: DocumentMut = text.parse()?;
+        let root = doc.as_item();
+        let config = self;
+
+        if let Some(v) = get_u64(root, "interval")? {
+            if v == 0 {
+                anyhow::bail!("`interval` must be greater than zero");
+            }
+            config.interval = v;
+        }
+        if let Some(v) = get_u64(root, "window")? {
+            config.window = v;
+        }
+        if let Some(v) = get_bool(root, "legend_min_max")? {
+            config.legend_min_max = v;
+        }
+        if let Some(v) = get_bool(root, "vram_reserved")? {
+            config.vram_reserved = v;
+        }
+        if let Some(v) = get_bool(root, "dry_run")? {
+            config.dry_run = v;
+        }
+        if let Some(v) = get_bool(root, "exit_summary")? {
+            config.exit_summary = v;
+        }
+        if let Some(v) = get_f64(root, "rate")? {
+            config.rate = Some(v);
+        }
+        if let Some(v) = get_u64(root, "silence_minutes")? {
+            config.silence_minutes = v;
+        }
+
+        if let Some(v) = get_str_array(root, "pinned")? {
+            if v.len() > MAX_PINNED {
+                anyhow::bail!("at most {MAX_PINNED} sensors can be pinned");
+            }
+            config.pinned = v;
+        }
+        if let Some(v) = get_str_array(root, "tts_command")? {
+            config.tts_command = v;
+        }
+        if let Some(v) = get_str(root, "history")? {
+            config.history = Some(PathBuf::from(v));
+        }
+        if let Some(v) = get_str(root, "socket")? {
+            config.socket = Some(PathBuf::from(v));
+        }
+        if let Some(v) = get_u64(root, "baseline_minutes")? {
+            config.baseline_minutes = Some(v);
+        }
+
+        if let Some(hardware) = root.get("hardware") {
+            for name in ROLES {
+                let Some(item) = hardware.get(name) else {
+                    continue;
+                };
+                let role = config.hardware.role_mut(name).unwrap();
+                let ctx = || format!("in hardware.{name}");
+
+                if let Some(v) = get_str(item, "chip").with_context(ctx)? {
+                    role.chip = glob::Pattern::new(&v)
+                        .with_context(|| format!("invalid pattern `{v}`"))
+                        .with_context(ctx)?;
+                }
+                if let Some(v) = get_str(item, "feature").with_context(ctx)? {
+                    role.feature = v;
+                }
+                if let Some(v) = get_str(item, "label").with_context(ctx)? {
+                    role.label = v;
+                }
+                if let Some(v) = get_str(item, "color").with_context(ctx)? {
+                    role.color = parse_color(&v).with_context(ctx)?;
+                }
+                if let Some(v) = get_samples(item).with_context(ctx)? {
+                    role.samples = v;
+                }
+            }
+
+            if let Some(v) = get_str(hardware, "coolant_fusion")? {
+                config.hardware.coolant_fusion =
+                    parse_fusion(&v).context("in hardware.coolant_fusion")?;
+            }
+
+            if let Some(item) = hardware.get("gpu") {
+                if let Some(v) =
+                    get_str(item, "provider").context("in hardware.gpu")?
+                {
+                    config.hardware.gpu_provider =
+                        GpuProvider::parse(&v).context("in hardware.gpu")?;
+                }
+            }
+        }
+
+        if let Some(layout) = root.get("layout") {
+            if let Some(v) = get_u16(layout, "bottom_height")? {
+                config.layout.bottom_height = Some(v);
+            }
+            if let Some(v) = get_u16(layout, "table_width")? {
+                config.layout.table_width = v;
+            }
+            if let Some(tree) = layout.get("tree") {
+                config.layout.tree =
+                    Some(LayoutNode::parse(tree).context("in layout.tree")?);
+            }
+        }
+
+        if let Some(drives) = root.get("drives") {
+            if let Some(v) = get_bool(drives, "enabled")? {
+                config.drives.enabled = v;
+            }
+            if let Some(v) = get_bool(drives, "smart")? {
+                config.drives.smart = v;
+            }
+        }
+
+        if let Some(processes) = root.get("processes") {
+            if let Some(v) = get_bool(processes, "enabled")? {
+                config.processes.enabled = v;
+            }
+            match get_u64(processes, "count")? {
+                Some(0) => anyhow::bail!("`processes.count` must be above 0"),
+                Some(v) => config.processes.count = v as usize,
+                None => {}
+            }
+        }
+
+        if let Some(signals) = root.get("signals") {
+            for (key, signal) in [
+                ("warn", &mut config.signals.warn),
+                ("crit", &mut config.signals.crit),
+                ("emergency", &mut config.signals.emergency),
+            ] {
+                if let Some(v) = get_str(signals, key)? {
+                    *signal = Signal::parse(&v)
+                        .with_context(|| format!("in signals.{key}"))?;
+                }
+            }
+        }
+
+        if let Some(serial) = root.get("serial") {
+            let device = get_str(serial, "device")?
+                .ok_or_else(|| anyhow!("`serial` needs a `device`"))?;
+            let sensors = get_str_array(serial, "sensors")?
+                .ok_or_else(|| anyhow!("`serial` needs `sensors`"))?;
+
+            config.serial = Some(SerialConfig {
+                device: PathBuf::from(device),
+                sensors,
+            });
+        }
+
+        if let Some(loops) = root.get("loops") {
+            let loops = loops
+                .as_array_of_tables()
+                .ok_or_else(|| anyhow!("`loops` must be an array of tables"))?;
+
+            let mut parsed = Vec::new();
+            for table in loops {
+                let item = Item::Table(table.clone());
+                let name = get_str(&item, "name")?
+                    .ok_or_else(|| anyhow!("every loop needs a `name`"))?;
+                let sensors =
+                    get_str_array(&item, "sensors")?.with_context(|| {
+                        format!("loop `{name}` has no `sensors`")
+                    })?;
+
+                parsed.push(LoopConfig { name, sensors });
+            }
+            config.loops = parsed;
+        }
+
+        if let Some(sensors) = root.get("sensors") {
+            let sensors = sensors.as_array_of_tables().ok_or_else(|| {
+                anyhow!("`sensors` must be an array of tables")
+            })?;
+
+            let mut parsed = Vec::new();
+            for (i, table) in sensors.iter().enumerate() {
+                let item = Item::Table(table.clone());
+                let label = get_str(&item, "label")?;
+                let name = match &label {
+                    Some(l) => format!("sensor `{l}`"),
+                    None => format!("sensor {}", i + 1),
+                };
+                let select = Selector::parse(&item, &name, Some("temp*"))?;
+                let color = get_str(&item, "color")?
+                    .map(|c| parse_color(&c))
+                    .transpose()?;
+                let chart = get_bool(&item, "chart")?
+                    .unwrap_or(false)
+                    .then(|| color.unwrap_or(Color::Yellow));
+
+                parsed.push(SensorConfig {
+                    select,
+                    chart,
+                    samples: get_samples(&item)
+                        .with_context(|| format!("in {name}"))?
+                        .unwrap_or(1),
+                    label,
+                });
+            }
+            config.sensors = parsed;
+        }
+
+        if let Some(fans) = root.get("fans") {
+            let fans = fans
+                .as_array_of_tables()
+                .ok_or_else(|| anyhow!("`fans` must be an array of tables"))?;
+
+            let mut parsed = Vec::new();
+            for table in fans {
+                let item = Item::Table(table.clone());
+                let label = get_str(&item, "label")?
+                    .ok_or_else(|| anyhow!("every fan needs a `label`"))?;
+                let select = Selector::parse(
+                    &item,
+                    &format!("fan `{label}`"),
+                    Some("fan*"),
+                )?;
+                let max_rpm =
+                    get_u16(&item, "max_rpm")?.unwrap_or(DEFAULT_FAN_MAX_RPM);
+                if max_rpm == 0 {
+                    anyhow::bail!("`max_rpm` of fan `{label}` must be above 0");
+                }
+                let noise = get_points(&item, "noise")?.unwrap_or_default();
+                if noise.first().is_some_and(|(rpm, _)| *rpm <= 0.0)
+                    || noise.windows(2).any(|w| w[0].0 >= w[1].0)
+                {
+                    anyhow::bail!(
+                        "`noise` of fan `{label}` must be sorted by RPM, \
+                         starting above 0"
+                    );
+                }
+
+                parsed.push(FanConfig {
+                    select,
+                    max_rpm,
+                    noise,
+                    label,
+                });
+            }
+            config.fans = parsed;
+        }
+
+        if let Some(voltages) = root.get("voltages") {
+            let voltages = voltages.as_array_of_tables().ok_or_else(|| {
+                anyhow!("`voltages` must be an array of tables")
+            })?;
+
+            let mut parsed = Vec::new();
+            for table in voltages {
+                let item = Item::Table(table.clone());
+                let label = get_str(&item, "label")?
+                    .ok_or_else(|| anyhow!("every voltage needs a `label`"))?;
+                let select = Selector::parse(
+                    &item,
+                    &format!("voltage `{label}`"),
+                    Some("in*"),
+                )?;
+                let scale = get_f64(&item, "scale")?.unwrap_or(1.0);
+                if scale <= 0.0 {
+                    anyhow::bail!(
+                        "`scale` of voltage `{label}` must be above 0"
+                    );
+                }
+                let min = get_f64(&item, "min")?;
+                let max = get_f64(&item, "max")?;
+                if let (Some(min), Some(max)) = (min, max) {
+                    if min > max {
+                        anyhow::bail!(
+                            "`min` of voltage `{label}` is above its `max`"
+                        );
+                    }
+                }
+
+                parsed.push(VoltageConfig {
+                    select,
+                    scale,
+                    min,
+                    max,
+                    label,
+                });
+            }
+            config.voltages = parsed;
+        }
+
+        if let Some(commands) = root.get("commands") {
+            let commands = commands.as_array_of_tables().ok_or_else(|| {
+                anyhow!("`commands` must be an array of tables")
+            })?;
+
+            let mut parsed = Vec::new();
+            for table in commands {
+                let item = Item::Table(table.clone());
+                let label = get_str(&item, "label")?
+                    .ok_or_else(|| anyhow!("every command needs a `label`"))?;
+                let command = get_str_array(&item, "command")?
+                    .filter(|c| !c.is_empty())
+                    .ok_or_else(|| {
+                        anyhow!("command `{label}` needs a `command`")
+                    })?;
+                let interval = get_u64(&item, "interval")?;
+                if interval == Some(0) {
+                    anyhow::bail!(
+                        "`interval` of command `{label}` must be greater than \
+                         zero"
+                    );
+                }
+                let scale = get_f64(&item, "scale")?.unwrap_or(1.0);
+                let color = get_str(&item, "color")?
+                    .map(|c| parse_color(&c))
+                    .transpose()?;
+                let chart = get_bool(&item, "chart")?
+                    .unwrap_or(false)
+                    .then(|| color.unwrap_or(Color::Yellow));
+
+                parsed.push(CommandConfig {
+                    label,
+                    command,
+                    interval,
+                    scale,
+                    chart,
+                });
+            }
+            config.commands = parsed;
+        }
+
+        if let Some(network) = root.get("network") {
+            let network = network.as_array_of_tables().ok_or_else(|| {
+                anyhow!("`network` must be an array of tables")
+            })?;
+
+            let mut parsed = Vec::new();
+            for table in network {
+                let item = Item::Table(table.clone());
+                let interface =
+                    get_str(&item, "interface")?.ok_or_else(|| {
+                        anyhow!("every network entry needs an `interface`")
+                    })?;
+                let max = get_f64(&item, "max")?.unwrap_or(125.0);
+                if max <= 0.0 {
+                    anyhow::bail!(
+                        "`max` of interface `{interface}` must be above 0"
+                    );
+                }
+                let color = |key: &str, default: Color| -> Result<Color> {
+                    Ok(get_str(&item, key)?
+                        .map(|c| parse_color(&c))
+                        .transpose()?
+                        .unwrap_or(default))
+                };
+                let chart = if get_bool(&item, "chart")?.unwrap_or(false) {
+                    Some((
+                        color("color", Color::Cyan)?,
+                        color("tx_color", Color::Magenta)?,
+                    ))
+                } else {
+                    None
+                };
+
+                parsed.push(NetworkConfig {
+                    label: get_str(&item, "label")?
+                        .unwrap_or_else(|| interface.clone()),
+                    interface,
+                    max,
+                    chart,
+                });
+            }
+            config.network = parsed;
+        }
+
+        if let Some(views) = root.get("views") {
+            let views = views
+                .as_array_of_tables()
+                .ok_or_else(|| anyhow!("`views` must be an array of tables"))?;
+
+            let mut parsed = Vec::new();
+            for table in views {
+                let item = Item::Table(table.clone());
+                let name = get_str(&item, "name")?
+                    .ok_or_else(|| anyhow!("every view needs a `name`"))?;
+                let series =
+                    get_str_array(&item, "series")?.with_context(|| {
+                        format!("view `{name}` has no `series`")
+                    })?;
+
+                if let Some(s) =
+                    series.iter().find(|s| !CHART_SERIES.contains(&s.as_str()))
+                {
+                    anyhow::bail!(
+                        "unknown series `{s}` in view `{name}`, expected one of {}",
+                        CHART_SERIES.join(", ")
+                    );
+                }
+
+                parsed.push(ViewConfig { name, series });
+            }
+            config.views = parsed;
+        }
+
+        if let Some(fused) = root.get("fused") {
+            let fused = fused
+                .as_array_of_tables()
+                .ok_or_else(|| anyhow!("`fused` must be an array of tables"))?;
+
+            let mut parsed = Vec::new();
+            for table in fused {
+                let item = Item::Table(table.clone());
+                let label = get_str(&item, "label")?.ok_or_else(|| {
+                    anyhow!("every fused sensor needs a `label`")
+                })?;
+                let sensors = get_str_array(&item, "sensors")?
+                    .filter(|s| !s.is_empty())
+                    .ok_or_else(|| {
+                        anyhow!("fused sensor `{label}` needs `sensors`")
+                    })?;
+                let mode = get_str(&item, "mode")?
+                    .map(|m| parse_fusion(&m))
+                    .transpose()
+                    .with_context(|| format!("in fused sensor `{label}`"))?
+                    .unwrap_or(Fusion::Max);
+
+                parsed.push(FusedConfig {
+                    label,
+                    sensors,
+                    mode,
+                });
+            }
+            config.fused = parsed;
+        }
+
+        if let Some(alerts) = root.get("alerts") {
+            let alerts = alerts.as_array_of_tables().ok_or_else(|| {
+                anyhow!("`alerts` must be an array of tables")
+            })?;
+
+            let mut parsed = Vec::new();
+            for table in alerts {
+                let item = Item::Table(table.clone());
+                let sensor = get_str(&item, "sensor")?
+                    .ok_or_else(|| anyhow!("every alert needs a `sensor`"))?;
+
+                let alert = AlertConfig {
+                    warn: get_f64(&item, "warn")?,
+                    crit: get_f64(&item, "crit")?,
+                    crit_command: get_str_array(&item, "crit_command")?
+                        .unwrap_or_default(),
+                    warn_rate: get_f64(&item, "warn_rate")?,
+                    crit_rate: get_f64(&item, "crit_rate")?,
+                    emergency_minutes: get_u64(&item, "emergency_minutes")?,
+                    emergency_command: get_str_array(
+                        &item,
+                        "emergency_command",
+                    )?
+                    .unwrap_or_default(),
+                    sensor,
+                };
+
+                if alert.emergency_minutes.is_some()
+                    && alert.crit.is_none()
+                    && alert.crit_rate.is_none()
+                {
+                    anyhow::bail!(
+                        "alert for `{}` has `emergency_minutes` but no `crit` \
+                         or `crit_rate`",
+                        alert.sensor
+                    );
+                }
+
+                parsed.push(alert);
+            }
+            config.alerts = parsed;
+        }
+
+        if let Some(leaks) = root.get("leaks") {
+            let leaks = leaks
+                .as_array_of_tables()
+                .ok_or_else(|| anyhow!("`leaks` must be an array of tables"))?;
+
+            let mut parsed = Vec::new();
+            for table in leaks {
+                let item = Item::Table(table.clone());
+                let label = get_str(&item, "label")?
+                    .ok_or_else(|| anyhow!("every leak needs a `label`"))?;
+                let select =
+                    Selector::parse(&item, &format!("leak `{label}`"), None)?;
+                let kind = get_str(&item, "kind")?
+                    .map(|k| LeakKind::parse(&k))
+                    .transpose()?
+                    .unwrap_or(LeakKind::Leak);
+                let min = get_f64(&item, "min")?;
+                match (kind, min) {
+                    (LeakKind::Level, None) => {
+                        anyhow::bail!("level sensor `{label}` needs a `min`")
+                    }
+                    (LeakKind::Leak, Some(_)) => anyhow::bail!(
+                        "`min` of `{label}` is only for `kind = \"level\"`"
+                    ),
+                    _ => {}
+                }
+
+                parsed.push(LeakConfig {
+                    select,
+                    kind,
+                    min,
+                    command: get_str_array(&item, "command")?
+                        .unwrap_or_default(),
+                    label,
+                });
+            }
+            config.leaks = parsed;
+        }
+
+        if let Some(exports) = root.get("export") {
+            let exports = exports.as_array_of_tables().ok_or_else(|| {
+                anyhow!("`export` must be an array of tables")
+            })?;
+
+            let mut parsed = Vec::new();
+            for table in exports {
+                let item = Item::Table(table.clone());
+                let kind = get_str(&item, "kind")?
+                    .ok_or_else(|| anyhow!("every export needs a `kind`"))?;
+                let kind = ExportKind::parse(&kind)?;
+                let target = get_str(&item, "target")?.with_context(|| {
+                    format!("{} export has no `target`", kind.name())
+                })?;
+                let topic = get_str(&item, "topic")?
+                    .unwrap_or_else(|| "sensors-mon".to_string());
+                let batch = get_u64(&item, "batch")?.unwrap_or(1).max(1);
+
+                parsed.push(ExportConfig {
+                    kind,
+                    target,
+                    topic,
+                    batch: batch as usize,
+                });
+            }
+            config.exports = parsed;
+        }
+
+        if let Some(scale) = root.get("scale") {
+            let labels = scale
+                .as_table_like()
+                .ok_or_else(|| anyhow!("`scale` must be a table"))?
+                .iter()
+                .map(|(label, _)| label.to_string());
+
+            for label in labels {
+                if let Some(factor) = get_f64(scale, &label)? {
+                    config.scale.insert(label, factor);
+                }
+            }
+        }
+
+        if let Some(chart) = root.get("chart") {
+            if let Some(v) = get_bool(chart, "cpu")? {
+                config.chart.cpu = v;
+            }
+            if let Some(v) = get_bool(chart, "coolant")? {
+                config.chart.coolant = v;
+            }
+            if let Some(v) = get_bool(chart, "gpu")? {
+                config.chart.gpu = v;
+            }
+            if let Some(v) = get_bool(chart, "ambient")? {
+                config.chart.ambient = v;
+            }
+            if let Some(v) = get_bool(chart, "guides")? {
+                config.chart.guides = v;
+            }
+            if let Some(v) = get_bool(chart, "grid")? {
+                config.chart.grid = v;
+            }
+            if let Some(v) = get_bool(chart, "psi_cpu")? {
+                config.chart.psi_cpu = v;
+            }
+            if let Some(v) = get_bool(chart, "psi_io")? {
+                config.chart.psi_io = v;
+            }
+            if let Some(v) = get_bool(chart, "psi_memory")? {
+                config.chart.psi_memory = v;
+            }
+            if let Some(v) = get_bool(chart, "drives")? {
+                config.chart.drives = v;
+            }
+            if let Some(v) = get_bool(chart, "cpu_power")? {
+                config.chart.cpu_power = v;
+            }
+            if let Some(v) = get_bool(chart, "battery")? {
+                config.chart.battery = v;
+            }
+            if let Some(v) = get_u64(chart, "smooth")? {
+                if v == 0 {
+                    anyhow::bail!("`chart.smooth` must be at least 1");
+                }
+                config.chart.smooth = v;
+            }
+            if let Some(v) = get_u64(chart, "y_labels")? {
+                if v < 2 {
+                    anyhow::bail!("`chart.y_labels` must be at least 2");
+                }
+                config.chart.y_labels = v;
+            }
+            if let Some(v) = get_u64(chart, "y_precision")? {
+                if v > 3 {
+                    anyhow::bail!("`chart.y_precision` can be at most 3");
+                }
+                config.chart.y_precision = v;
+            }
+            if let Some(v) = get_bool(chart, "braille")? {
+                config.chart.braille = Some(v);
+            }
+            if let Some(v) = get_str_array(chart, "gradient")? {
+                config.chart.gradient = v;
+            }
+            if let Some(v) = get_f64(chart, "gradient_min")? {
+                config.chart.gradient_min = v;
+            }
+            if let Some(v) = get_f64(chart, "gradient_max")? {
+                config.chart.gradient_max = v;
+            }
+            if config.chart.gradient_min >= config.chart.gradient_max {
+                anyhow::bail!(
+                    "`chart.gradient_min` must be below `chart.gradient_max`"
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    fn to_toml(&self) -> String {
+        let mut out = String::new();
+
+        // writing to a String can't fail
+        let _ = writeln!(out, "interval = {}", self.interval);
+        let _ = writeln!(out, "window = {}", self.window);
+        let _ = writeln!(out, "legend_min_max = {}", self.legend_min_max);
+        let _ = writeln!(out, "vram_reserved = {}", self.vram_reserved);
+        let _ = writeln!(out, "dry_run = {}", self.dry_run);
+        let _ = writeln!(out, "exit_summary = {}", self.exit_summary);
+        if let Some(rate) = self.rate {
+            let _ = writeln!(out, "rate = {rate:?}");
+        }
+        let _ = writeln!(out, "silence_minutes = {}", self.silence_minutes);
+        if !self.pinned.is_empty() {
+            let pinned: Vec<String> =
+                self.pinned.iter().map(|p| toml_str(p)).collect();
+            let _ = writeln!(out, "pinned = [{}]", pinned.join(", "));
+        }
+        if !self.tts_command.is_empty() {
+            let cmd: Vec<String> =
+                self.tts_command.iter().map(|a| toml_str(a)).collect();
+            let _ = writeln!(out, "tts_command = [{}]", cmd.join(", "));
+        }
+        if let Some(history) = &self.history {
+            let path = toml_str(&history.to_string_lossy());
+            let _ = writeln!(out, "history = {path}");
+        }
+        if let Some(socket) = &self.socket {
+            let path = toml_str(&socket.to_string_lossy());
+            let _ = writeln!(out, "socket = {path}");
+        }
+        if let Some(minutes) = self.baseline_minutes {
+            let _ = writeln!(out, "baseline_minutes = {minutes}");
+        }
+
+        let _ = writeln!(out);
+        let _ = writeln!(out, "[chart]");
+        let _ = writeln!(out, "cpu = {}", self.chart.cpu);
+        let _ = writeln!(out, "coolant = {}", self.chart.coolant);
+        let _ = writeln!(out, "gpu = {}", self.chart.gpu);
+        let _ = writeln!(out, "ambient = {}", self.chart.ambient);
+        let _ = writeln!(out, "psi_cpu = {}", self.chart.psi_cpu);
+        let _ = writeln!(out, "psi_io = {}", self.chart.psi_io);
+        let _ = writeln!(out, "psi_memory = {}", self.chart.psi_memory);
+        let _ = writeln!(out, "drives = {}", self.chart.drives);
+        let _ = writeln!(out, "cpu_power = {}", self.chart.cpu_power);
+        let _ = writeln!(out, "battery = {}", self.chart.battery);
+        let _ = writeln!(out, "guides = {}", self.chart.guides);
+        let _ = writeln!(out, "grid = {}", self.chart.grid);
+        let _ = writeln!(out, "smooth = {}", self.chart.smooth);
+        let _ = writeln!(out, "y_labels = {}", self.chart.y_labels);
+        let _ = writeln!(out, "y_precision = {}", self.chart.y_precision);
+        if let Some(braille) = self.chart.braille {
+            let _ = writeln!(out, "braille = {braille}");
+        }
+        if !self.chart.gradient.is_empty() {
+            let labels: Vec<String> =
+                self.chart.gradient.iter().map(|l| toml_str(l)).collect();
+            let _ = writeln!(out, "gradient = [{}]", labels.join(", "));
+        }
+        let _ = writeln!(out, "gradient_min = {:?}", self.chart.gradient_min);
+        let _ = writeln!(out, "gradient_max = {:?}", self.chart.gradient_max);
+
+        let _ = writeln!(out);
+        let _ = writeln!(out, "[layout]");
+        if let Some(height) = self.layout.bottom_height {
+            let _ = writeln!(out, "bottom_height = {height}");
+        }
+        let _ = writeln!(out, "table_width = {}", self.layout.table_width);
+        if let Some(tree) = &self.layout.tree {
+            let _ = writeln!(out, "tree = {}", tree.to_toml());
+        }
+
+        let _ = writeln!(out);
+        let _ = writeln!(out, "[drives]");
+        let _ = writeln!(out, "enabled = {}", self.drives.enabled);
+        let _ = writeln!(out, "smart = {}", self.drives.smart);
+
+        let _ = writeln!(out);
+        let _ = writeln!(out, "[processes]");
+        let _ = writeln!(out, "enabled = {}", self.processes.enabled);
+        let _ = writeln!(out, "count = {}", self.processes.count);
+
+        let _ = writeln!(out);
+        let _ = writeln!(out, "[signals]");
+        let _ = writeln!(out, "warn = {}", toml_str(self.signals.warn.name()));
+        let _ = writeln!(out, "crit = {}", toml_str(self.signals.crit.name()));
+        let _ = writeln!(
+            out,
+            "emergency = {}",
+            toml_str(self.signals.emergency.name())
+        );
+
+        let _ = writeln!(out);
+        let _ = writeln!(out, "[hardware]");
+        let _ = writeln!(
+            out,
+            "coolant_fusion = {}",
+            toml_str(self.hardware.coolant_fusion.name())
+        );
+
+        for name in ROLES {
+            let role = self.hardware.role(name).unwrap();
+
+            let _ = writeln!(out);
+            let _ = writeln!(out, "[hardware.{name}]");
+            let _ = writeln!(out, "chip = {}", toml_str(role.chip.as_str()));
+            let _ = writeln!(out, "feature = {}", toml_str(&role.feature));
+            let _ = writeln!(out, "label = {}", toml_str(&role.label));
+            let _ =
+                writeln!(out, "color = {}", toml_str(&role.color.to_string()));
+            if role.samples > 1 {
+                let _ = writeln!(out, "samples = {}", role.samples);
+            }
+            if name == "gpu" {
+                let _ = writeln!(
+                    out,
+                    "provider = {}",
+                    toml_str(self.hardware.gpu_provider.name())
+                );
+            }
+        }
+
+        if !self.scale.is_empty() {
+            let _ = writeln!(out);
+            let _ = writeln!(out, "[scale]");
+            for (label, factor) in &self.scale {
+                let _ = writeln!(out, "{} = {factor:?}", toml_str(label));
+            }
+        }
+
+        if let Some(serial) = &self.serial {
+            let sensors: Vec<String> =
+                serial.sensors.iter().map(|s| toml_str(s)).collect();
+
+            let _ = writeln!(out);
+            let _ = writeln!(out, "[serial]");
+            let _ = writeln!(
+                out,
+                "device = {}",
+                toml_str(&serial.device.to_string_lossy())
+            );
+            let _ = writeln!(out, "sensors = [{}]", sensors.join(", "));
+        }
+
+        for l in &self.loops {
+            let sensors: Vec<String> =
+                l.sensors.iter().map(|s| toml_str(s)).collect();
+
+            let _ = writeln!(out);
+            let _ = writeln!(out, "[[loops]]");
+            let _ = writeln!(out, "name = {}", toml_str(&l.name));
+            let _ = writeln!(out, "sensors = [{}]", sensors.join(", "));
+        }
+
+        for s in &self.sensors {
+            let _ = writeln!(out);
+            out.push_str(&s.to_toml());
+        }
+
+        for f in &self.fans {
+            let _ = writeln!(out);
+            out.push_str(&f.to_toml());
+        }
+
+        for v in &self.voltages {
+            let _ = writeln!(out);
+            let _ = writeln!(out, "[[voltages]]");
+            let _ = writeln!(out, "label = {}", toml_str(&v.label));
+            v.select.to_toml(&mut out);
+            if v.scale != 1.0 {
+                let _ = writeln!(out, "scale = {:?}", v.scale);
+            }
+            if let Some(min) = v.min {
+                let _ = writeln!(out, "min = {min:?}");
+            }
+            if let Some(max) = v.max {
+                let _ = writeln!(out, "max = {max:?}");
+            }
+        }
+
+        for c in &self.commands {
+            let command: Vec<String> =
+                c.command.iter().map(|a| toml_str(a)).collect();
+
+            let _ = writeln!(out);
+            let _ = writeln!(out, "[[commands]]");
+            let _ = writeln!(out, "label = {}", toml_str(&c.label));
+            let _ = writeln!(out, "command = [{}]", command.join(", "));
+            if let Some(interval) = c.interval {
+                let _ = writeln!(out, "interval = {interval}");
+            }
+            if c.scale != 1.0 {
+                let _ = writeln!(out, "scale = {:?}", c.scale);
+            }
+            if let Some(color) = c.chart {
+                let _ = writeln!(out, "chart = true");
+                let _ =
+                    writeln!(out, "color = {}", toml_str(&color.to_string()));
+            }
+        }
+
+        for n in &self.network {
+            let _ = writeln!(out);
+            let _ = writeln!(out, "[[network]]");
+            let _ = writeln!(out, "interface = {}", toml_str(&n.interface));
+            if n.label != n.interface {
+                let _ = writeln!(out, "label = {}", toml_str(&n.label));
+            }
+            let _ = writeln!(out, "max = {:?}", n.max);
+            if let Some((rx, tx)) = n.chart {
+                let _ = writeln!(out, "chart = true");
+                let _ = writeln!(out, "color = {}", toml_str(&rx.to_string()));
+                let _ =
+                    writeln!(out, "tx_color = {}", toml_str(&tx.to_string()));
+            }
+        }
+
+        for v in &self.views {
+            let series: Vec<String> =
+                v.series.iter().map(|s| toml_str(s)).collect();
+
+            let _ = writeln!(out);
+            let _ = writeln!(out, "[[views]]");
+            let _ = writeln!(out, "name = {}", toml_str(&v.name));
+            let _ = writeln!(out, "series = [{}]", series.join(", "));
+        }
+
+        for f in &self.fused {
+            let sensors: Vec<String> =
+                f.sensors.iter().map(|s| toml_str(s)).collect();
+
+            let _ = writeln!(out);
+            let _ = writeln!(out, "[[fused]]");
+            let _ = writeln!(out, "label = {}", toml_str(&f.label));
+            let _ = writeln!(out, "sensors = [{}]", sensors.join(", "));
+            let _ = writeln!(out, "mode = {}", toml_str(f.mode.name()));
+        }
+
+        for a in &self.alerts {
+            let cmd = |c: &[String]| -> String {
+                let c: Vec<String> = c.iter().map(|a| toml_str(a)).collect();
+                format!("[{}]", c.join(", "))
+            };
+
+            let _ = writeln!(out);
+            let _ = writeln!(out, "[[alerts]]");
+            let _ = writeln!(out, "sensor = {}", toml_str(&a.sensor));
+            if let Some(warn) = a.warn {
+                let _ = writeln!(out, "warn = {warn:?}");
+            }
+            if let Some(crit) = a.crit {
+                let _ = writeln!(out, "crit = {crit:?}");
+            }
+            if !a.crit_command.is_empty() {
+                let _ =
+                    writeln!(out, "crit_command = {}", cmd(&a.crit_command));
+            }
+            if let Some(rate) = a.warn_rate {
+                let _ = writeln!(out, "warn_rate = {rate:?}");
+            }
+            if let Some(rate) = a.crit_rate {
+                let _ = writeln!(out, "crit_rate = {rate:?}");
+            }
+            if let Some(minutes) = a.emergency_minutes {
+                let _ = writeln!(out, "emergency_minutes = {minutes}");
+            }
+            if !a.emergency_command.is_empty() {
+                let _ = writeln!(
+                    out,
+                    "emergency_command = {}",
+                    cmd(&a.emergency_command)
+                );
+            }
+        }
+
+        for l in &self.leaks {
+            let command: Vec<String> =
+                l.command.iter().map(|a| toml_str(a)).collect();
+
+            let _ = writeln!(out);
+            let _ = writeln!(out, "[[leaks]]");
+            let _ = writeln!(out, "label = {}", toml_str(&l.label));
+            l.select.to_toml(&mut out);
+            let _ = writeln!(out, "kind = {}", toml_str(l.kind.name()));
+            if let Some(min) = l.min {
+                let _ = writeln!(out, "min = {min:?}");
+            }
+            if !l.command.is_empty() {
+                let _ = writeln!(out, "command = [{}]", command.join(", "));
+            }
+        }
+
+        for e in &self.exports {
+            let _ = writeln!(out);
+            let _ = writeln!(out, "[[export]]");
+            let _ = writeln!(out, "kind = {}", toml_str(e.kind.name()));
+            let _ = writeln!(out, "target = {}", toml_str(&e.target));
+            if e.kind == ExportKind::Mqtt {
+                let _ = writeln!(out, "topic = {}", toml_str(&e.topic));
+            }
+            let _ = writeln!(out, "batch = {}", e.batch);
+        }
+
+        out
+    }
+
+    /// Write the current value of each of `keys` (dotted, like
+    /// `chart.cpu`) to `path`, creating the file and its directory if
+    /// needed. It becomes a source if it wasn't one already. The file is
+    /// edited in place, so its comments, formatting and every other key
+    /// are kept as they were
+    pub fn save(&mut self, path: &Path, keys: &[&str]) -> Result<()> {
+        let mut doc: DocumentMut = match read_to_string(path) {
+            Ok(text) => text
+                .parse()
+                .with_context(|| format!("in {}", path.display()))?,
+            Err(e) if e.kind() == ErrorKind::NotFound => DocumentMut::new(),
+            Err(e) => {
+                return Err(e)
+                    .with_context(|| format!("reading {}", path.display()))
+            }
+        };
+        // the values as they'd be written, so they're formatted the same
+        // way as in a migrated file
+        let current: DocumentMut = self.to_toml().parse()?;
+
+        for key in keys {
+            let parts: Vec<&str> = key.split('.').collect();
+            let Some((name, tables)) = parts.split_last() else {
+                continue;
+            };
+            let mut value = tables
+                .iter()
+                .try_fold(current.as_item(), |item, t| item.get(t))
+                .and_then(|item| item.get(name))
+                .and_then(Item::as_value)
+                .ok_or_else(|| anyhow!("no `{key}` setting"))?
+                .clone();
+            value.decor_mut().clear();
+
+            let mut item = doc.as_item_mut();
+            for t in tables {
+                let table = { item }
+                    .as_table_like_mut()
+                    .ok_or_else(|| anyhow!("`{t}` isn't a table"))?;
+                if table.get(t).is_none() {
+                    table.insert(t, toml_edit::table());
+                }
+                item = table.get_mut(t).expect("inserted above");
+            }
+            let table = item
+                .as_table_like_mut()
+                .ok_or_else(|| anyhow!("`{key}` isn't in a table"))?;
+
+            // a comment after the old value stays with the new one
+            match table.get_mut(name).and_then(Item::as_value_mut) {
+                Some(old) => {
+                    let decor = old.decor().clone();
+                    *old = value;
+                    *old.decor_mut() = decor;
+                }
+                None => {
+                    table.insert(name, Item::Value(value));
+                }
+            }
+        }
+
+        if let Some(dir) = path.parent() {
+            create_dir_all(dir)?;
+        }
+        write(path, doc.to_string())
+            .with_context(|| format!("writing {}", path.display()))?;
+        if !self.sources.iter().any(|p| p == path) {
+            self.sources.push(path.to_path_buf());
+        }
+        for key in keys {
+            self.origins
+                .insert(key.to_string(), Origin::File(path.to_path_buf()));
+        }
+        Ok(())
+    }
+
+    /// Rewrite the config at `path` in the current format, after copying
+    /// it to `<path>.bak`. Only that file is read, so nothing from the
+    /// system-wide config ends up in it
+    pub fn migrate(path: &Path) -> Result<Migration> {
+        let text = read_to_string(path)
+            .with_context(|| format!("reading {}", path.display()))?;
+
+        let mut config = Self::default();
+        config
+            .merge(&text)
+            .with_context(|| format!("in {}", path.display()))?;
+        let new = config.to_toml();
+
+        // A key is carried over when it, or a table it's nested in, is
+        // written back. Tables the original spelled out may come back as
+        // inline tables, and the other way around
+        let old = doc_paths(&text)?;
+        let new_paths = doc_paths(&new)?;
+        let dropped = old
+            .into_iter()
+            .filter(|key| {
+                !new_paths.iter().any(|n| {
+                    n == key
+                        || key.starts_with(&format!("{n}."))
+                        || n.starts_with(&format!("{key}."))
+                })
+            })
+            .collect();
+
+        let mut backup = path.as_os_str().to_owned();
+        backup.push(".bak");
+        let backup = PathBuf::from(backup);
+        copy(path, &backup)
+            .with_context(|| format!("writing {}", backup.display()))?;
+        write(path, new)
+            .with_context(|| format!("writing {}", path.display()))?;
+
+        Ok(Migration { backup, dropped })
+    }
+}
@@ -0,0 +1,143 @@
+//! Unix control socket for scripts to talk to a running instance. The
+//! protocol is one command per line, answered with `ok` (optionally
+//! followed by a result) or `error: <reason>`:
+//!
+//! - `annotate <text>` adds a marker to the chart at the current time
+//! - `snapshot [path]` writes an OpenMetrics snapshot of the current
+//!   readings and replies with the path it was written to
+//! - `chart [path]` writes the chart as an SVG file and replies with the
+//!   path it was written to
+
+use anyhow::{bail, Context, Result};
+use std::{
+    fs::{remove_file, symlink_metadata},
+    io::{BufRead, BufReader, ErrorKind, Write},
+    os::unix::{
+        fs::FileTypeExt,
+        net::{UnixListener, UnixStream},
+    },
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// A client that takes longer than this to send its command is dropped,
+/// so a stuck script can't freeze the UI
+const CLIENT_TIMEOUT: Duration = Duration::from_millis(100);
+
+#[derive(Debug)]
+pub enum Request {
+    Annotate(String),
+    Snapshot(Option<PathBuf>),
+    Chart(Option<PathBuf>),
+}
+
+impl Request {
+    fn parse(line: &str) -> std::result::Result<Self, String> {
+        let (cmd, arg) = line.split_once(' ').unwrap_or((line, ""));
+        let arg = arg.trim();
+
+        match cmd {
+            "annotate" if arg.is_empty() => {
+                Err("annotate needs some text".to_string())
+            }
+            "annotate" => Ok(Request::Annotate(arg.to_string())),
+            "snapshot" if arg.is_empty() => Ok(Request::Snapshot(None)),
+            "snapshot" => Ok(Request::Snapshot(Some(PathBuf::from(arg)))),
+            "chart" if arg.is_empty() => Ok(Request::Chart(None)),
+            "chart" => Ok(Request::Chart(Some(PathBuf::from(arg)))),
+            _ => Err(format!("unknown command `{cmd}`")),
+        }
+    }
+}
+
+/// A request waiting for the app to handle it and reply
+pub struct Client {
+    pub request: Request,
+    stream: UnixStream,
+}
+
+impl Client {
+    /// Send the result back, `ok` followed by the message if there is
+    /// one, or the error
+    pub fn reply(mut self, result: Result<Option<String>>) {
+        let _ = match result {
+            Ok(None) => writeln!(self.stream, "ok"),
+            Ok(Some(msg)) => writeln!(self.stream, "ok {msg}"),
+            Err(e) => writeln!(self.stream, "error: {e:#}"),
+        };
+    }
+}
+
+pub struct Control {
+    listener: UnixListener,
+    path: PathBuf,
+}
+
+impl Control {
+    pub fn open(path: &Path) -> Result<Self> {
+        // a socket left behind by an instance that didn't exit cleanly
+        // would make bind fail. Anything else at the path is left alone,
+        // it's more likely a typo in the config than ours
+        match symlink_metadata(path) {
+            Ok(meta) if !meta.file_type().is_socket() => {
+                bail!("{} exists and isn't a socket", path.display())
+            }
+            Ok(_) if UnixStream::connect(path).is_err() => {
+                let _ = remove_file(path);
+            }
+            _ => {}
+        }
+
+        let listener = UnixListener::bind(path)
+            .with_context(|| format!("binding {}", path.display()))?;
+        listener.set_nonblocking(true)?;
+
+        Ok(Self {
+            listener,
+            path: path.to_path_buf(),
+        })
+    }
+
+    /// Accept every client currently waiting. Clients that sent a bad
+    /// command have already been answered and aren't returned
+    pub fn poll(&self) -> Vec<Client> {
+        let mut ret = Vec::new();
+
+        loop {
+            let stream = match self.listener.accept() {
+                Ok((s, _)) => s,
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(_) => break,
+            };
+
+            if let Some(c) = Self::read(stream) {
+                ret.push(c);
+            }
+        }
+
+        ret
+    }
+
+    fn read(stream: UnixStream) -> Option<Client> {
+        stream.set_nonblocking(false).ok()?;
+        stream.set_read_timeout(Some(CLIENT_TIMEOUT)).ok()?;
+
+        let mut line = String::new();
+        BufReader::new(&stream).read_line(&mut line).ok()?;
+
+        let mut stream = stream;
+        match Request::parse(line.trim()) {
+            Ok(request) => Some(Client { request, stream }),
+            Err(e) => {
+                let _ = writeln!(stream, "error: {e}");
+                None
+            }
+        }
+    }
+}
+
+impl Drop for Control {
+    fn drop(&mut self) {
+        let _ = remove_file(&self.path);
+    }
+}
@@ -0,0 +1,78 @@
+//! Per-core and per-CCD CPU temperatures, for spotting one core or
+//! chiplet running away from the others that the package reading hides
+
+use crate::provider::Reading;
+use ratatui::{
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::Line,
+    widgets::{Block, Clear, Paragraph},
+    Frame,
+};
+
+/// Width of the bars, in characters
+const BAR_WIDTH: usize = 20;
+
+/// Temperatures the bars start and end at
+const BAR_MIN: f64 = 30.0;
+const BAR_MAX: f64 = 100.0;
+
+/// Readings of chips matching `chip` labelled like the per-die (`Tccd1`,
+/// k10temp) or per-core (`Core 0`, coretemp) sensors, as (label, value)
+/// in the order the driver lists them
+pub fn temps(readings: &[Reading], chip: &glob::Pattern) -> Vec<(String, f64)> {
+    readings
+        .iter()
+        .filter(|r| r.is_temp() && chip.matches(&r.chip))
+        .filter_map(|r| {
+            let label = r.label.as_deref()?;
+            (label.starts_with("Tccd") || label.starts_with("Core "))
+                .then(|| (label.to_string(), r.value))
+        })
+        .collect()
+}
+
+/// Draw the breakdown at the top middle of `area`, with the hottest
+/// reading highlighted
+pub fn render(temps: &[(String, f64)], frame: &mut Frame, area: Rect) {
+    let rows = temps.len().max(1) as u16;
+    let [area] = Layout::horizontal([Constraint::Length(40)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [area] = Layout::vertical([Constraint::Length(rows + 2)]).areas(area);
+
+    let hottest = temps
+        .iter()
+        .map(|(_, v)| *v)
+        .reduce(f64::max)
+        .unwrap_or(0.0);
+
+    let lines: Vec<Line> = if temps.is_empty() {
+        vec![Line::from("no per-core sensors").dark_gray()]
+    } else {
+        temps
+            .iter()
+            .map(|(label, value)| {
+                let ratio =
+                    ((value - BAR_MIN) / (BAR_MAX - BAR_MIN)).clamp(0.0, 1.0);
+                let bar =
+                    "\u{2588}".repeat((ratio * BAR_WIDTH as f64) as usize);
+                let line = Line::from(format!(
+                    "{label:<8} {value:>5.1} {bar:<BAR_WIDTH$}"
+                ));
+
+                if *value >= hottest {
+                    line.style(Style::new().bold().fg(Color::Red))
+                } else {
+                    line
+                }
+            })
+            .collect()
+    };
+
+    let pane = Paragraph::new(lines)
+        .block(Block::bordered().title(" Cores ").fg(Color::Gray));
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(pane, area);
+}
@@ -0,0 +1,88 @@
+//! CPU utilization from /proc/stat and core clocks from cpufreq, for
+//! lining temperatures up with load
+
+use std::fs::{read_dir, read_to_string};
+
+const STAT: &str = "/proc/stat";
+const CPU_ROOT: &str = "/sys/devices/system/cpu";
+
+/// Utilization since the last read and the clocks right now. Clocks are
+/// zero without cpufreq (in most VMs)
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Load {
+    /// Share of time not idle across all cores, in percent
+    pub utilization: f64,
+    pub avg_mhz: f64,
+    pub max_mhz: f64,
+}
+
+/// Keeps the previous /proc/stat counters, utilization is how they moved
+/// since
+#[derive(Debug, Default)]
+pub struct CpuStat {
+    last: Option<(u64, u64)>,
+}
+
+/// Busy and total jiffies from the aggregate `cpu` line
+fn jiffies() -> Option<(u64, u64)> {
+    let text = read_to_string(STAT).ok()?;
+    let line = text.lines().find(|l| l.starts_with("cpu "))?;
+    let fields: Vec<u64> = line
+        .split_whitespace()
+        .skip(1)
+        .filter_map(|f| f.parse().ok())
+        .collect();
+
+    // user nice system idle iowait irq softirq steal, guest time is
+    // already counted in user
+    let total: u64 = fields.iter().take(8).sum();
+    let idle = fields.get(3)? + fields.get(4).unwrap_or(&0);
+    Some((total - idle, total))
+}
+
+/// Current clock of every core in MHz
+fn clocks() -> Vec<f64> {
+    read_dir(CPU_ROOT)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|e| {
+            let name = e.file_name();
+            let name = name.to_str().unwrap_or("");
+            name.strip_prefix("cpu")
+                .is_some_and(|n| n.parse::<u32>().is_ok())
+        })
+        .filter_map(|e| {
+            let khz = read_to_string(e.path().join("cpufreq/scaling_cur_freq"));
+            khz.ok()?.trim().parse::<f64>().ok()
+        })
+        .map(|khz| khz / 1000.0)
+        .collect()
+}
+
+impl CpuStat {
+    /// Read the counters and clocks. The first call only primes the
+    /// counters and reports no utilization
+    pub fn read(&mut self) -> Load {
+        let mut load = Load::default();
+
+        if let Some((busy, total)) = jiffies() {
+            if let Some((last_busy, last_total)) = self.last {
+                let dt = total.saturating_sub(last_total);
+                if dt > 0 {
+                    let db = busy.saturating_sub(last_busy);
+                    load.utilization = db as f64 / dt as f64 * 100.0;
+                }
+            }
+            self.last = Some((busy, total));
+        }
+
+        let clocks = clocks();
+        if !clocks.is_empty() {
+            load.avg_mhz = clocks.iter().sum::<f64>() / clocks.len() as f64;
+            load.max_mhz = clocks.iter().copied().fold(0.0, f64::max);
+        }
+
+        load
+    }
+}
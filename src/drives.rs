@@ -0,0 +1,160 @@
+//! Drive temperatures. NVMe drives have an hwmon device of their own and
+//! SATA drives get one from the `drivetemp` module. Drives with neither
+//! can optionally be asked through `smartctl`, which is slow and needs
+//! root, so its readings are cached for a while
+
+use crate::provider::{Reading, SensorProvider};
+use std::{
+    fs::{read_dir, read_to_string},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    time::{Duration, Instant},
+};
+
+const BLOCK_ROOT: &str = "/sys/block";
+
+/// Block devices that are never physical drives
+const VIRTUAL_PREFIXES: [&str; 6] = ["loop", "ram", "zram", "dm-", "md", "sr"];
+
+/// How long a `smartctl` reading is reused for
+const SMART_INTERVAL: Duration = Duration::from_secs(60);
+
+struct Drive {
+    /// Block device name, like `nvme0n1` or `sda`
+    dev: String,
+    model: Option<String>,
+
+    /// The `temp1_input` of the drive's hwmon device
+    hwmon: Option<PathBuf>,
+
+    /// Last `smartctl` reading, for drives without hwmon
+    smart: Option<(Instant, Option<f64>)>,
+}
+
+pub struct Drives {
+    drives: Vec<Drive>,
+    smart: bool,
+}
+
+fn read(path: &Path) -> Option<String> {
+    read_to_string(path).ok().map(|v| v.trim().to_string())
+}
+
+/// `temp1_input` of the hwmon device under a drive's device directory.
+/// NVMe puts it at `hwmonN`, drivetemp at `hwmon/hwmonN`
+fn hwmon_temp(device: &Path) -> Option<PathBuf> {
+    [device.to_path_buf(), device.join("hwmon")]
+        .iter()
+        .flat_map(|d| read_dir(d).into_iter().flatten().flatten())
+        .map(|e| e.path())
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with("hwmon"))
+        })
+        .map(|p| p.join("temp1_input"))
+        .find(|p| p.exists())
+}
+
+/// Current temperature from `smartctl -j -A`. `-n standby` leaves
+/// spun-down disks alone rather than waking them for a reading
+fn smartctl_temp(dev: &str) -> Option<f64> {
+    let out = Command::new("smartctl")
+        .args(["-j", "-n", "standby", "-A", &format!("/dev/{dev}")])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    let json = String::from_utf8_lossy(&out.stdout);
+
+    // `"temperature": { "current": 34, ... }`. not worth a JSON parser
+    let rest = &json[json.find("\"temperature\"")?..];
+    let rest = &rest[rest.find("\"current\"")? + "\"current\"".len()..];
+    let num: String = rest
+        .trim_start_matches([':', ' '])
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == '-')
+        .collect();
+
+    num.parse().ok()
+}
+
+/// Every physical drive in /sys/block. `smart` asks `smartctl` for the
+/// ones without an hwmon device
+pub fn init(smart: bool) -> Drives {
+    let mut drives: Vec<Drive> = read_dir(BLOCK_ROOT)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|e| {
+            let dev = e.file_name().into_string().ok()?;
+            if VIRTUAL_PREFIXES.iter().any(|p| dev.starts_with(p)) {
+                return None;
+            }
+
+            let device = e.path().join("device");
+            Some(Drive {
+                model: read(&device.join("model")),
+                hwmon: hwmon_temp(&device),
+                smart: None,
+                dev,
+            })
+        })
+        .collect();
+    drives.sort_by(|a, b| a.dev.cmp(&b.dev));
+
+    Drives { drives, smart }
+}
+
+impl Drive {
+    fn temp(&mut self, smart: bool) -> Option<f64> {
+        if let Some(path) = &self.hwmon {
+            return read(path)?.parse::<f64>().ok().map(|v| v / 1000.0);
+        }
+        if !smart {
+            return None;
+        }
+
+        match self.smart {
+            Some((at, temp)) if at.elapsed() < SMART_INTERVAL => temp,
+            _ => {
+                let temp = smartctl_temp(&self.dev);
+                self.smart = Some((Instant::now(), temp));
+                temp
+            }
+        }
+    }
+
+    fn label(&self) -> String {
+        match &self.model {
+            Some(model) => format!("{} {model}", self.dev),
+            None => self.dev.clone(),
+        }
+    }
+}
+
+impl SensorProvider for Drives {
+    /// Temperature of every drive as `tempN` of a `drive` chip, numbered
+    /// from 1 in device name order and labelled with the device name and
+    /// model
+    fn poll(&mut self) -> Vec<Reading> {
+        let smart = self.smart;
+        let mut ret = Vec::new();
+
+        for (i, drive) in self.drives.iter_mut().enumerate() {
+            let Some(value) = drive.temp(smart) else {
+                continue;
+            };
+
+            ret.push(Reading {
+                chip: "drive".to_string(),
+                serial: None,
+                feature: format!("temp{}", i + 1),
+                label: Some(drive.label()),
+                value,
+            });
+        }
+
+        ret
+    }
+}
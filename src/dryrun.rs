@@ -0,0 +1,49 @@
+//! Dry-run mode, for trying out cooling and alert rules before arming
+//! them. Control actions (cooling level changes and alert commands) are
+//! appended to a log instead of being carried out
+
+use crate::history;
+use anyhow::{Context, Result};
+use std::{
+    fs::{create_dir_all, File, OpenOptions},
+    io::Write as _,
+    path::{Path, PathBuf},
+};
+
+pub struct Log {
+    file: File,
+
+    /// Level the last logged cooling change would have set. The real
+    /// level never changes in a dry run, so without this the same change
+    /// would be logged every tick
+    pub cooling_level: Option<u8>,
+}
+
+/// `$XDG_STATE_HOME/sensors-mon/dry-run.log`
+pub fn default_path() -> PathBuf {
+    crate::state_dir().join("dry-run.log")
+}
+
+impl Log {
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(dir) = path.parent() {
+            create_dir_all(dir)?;
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("opening {}", path.display()))?;
+
+        Ok(Self {
+            file,
+            cooling_level: None,
+        })
+    }
+
+    /// Log that `action` would have been carried out now
+    pub fn record(&mut self, action: &str) {
+        let _ = writeln!(self.file, "{} {action}", history::now());
+    }
+}
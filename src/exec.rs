@@ -0,0 +1,127 @@
+//! Sensors read from `[[commands]]` in the config: a program is run
+//! every tick (or at its own interval) and the first number it prints
+//! is the reading. Each command runs on a thread of its own, so one that
+//! goes over SSH or hangs doesn't hold up the ticks
+
+use crate::{
+    config::CommandConfig,
+    provider::{Reading, SensorProvider},
+};
+use std::{
+    process::{Command, Stdio},
+    sync::mpsc::{channel, Receiver, Sender},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Chip name the commands are reported under
+pub const CHIP: &str = "exec";
+
+/// A value is dropped once it's this many of its command's intervals
+/// old, the command hangs (an SSH host went away) and it'd pass for a
+/// current one
+const STALE_INTERVALS: u32 = 3;
+
+/// But never sooner than this, so a command that takes longer to run
+/// than its interval doesn't flicker in and out
+const STALE_MIN: Duration = Duration::from_secs(10);
+
+pub struct Exec {
+    labels: Vec<String>,
+    rx: Receiver<(usize, Option<f64>)>,
+    /// Last value of each command and when it came in, None when it
+    /// failed or printed no number
+    last: Vec<Option<(f64, Instant)>>,
+    /// How old each command's value can get before it's dropped
+    stale_after: Vec<Duration>,
+}
+
+/// The first number in `text`, so `23.5C` and `temp: 41` both read
+fn first_number(text: &str) -> Option<f64> {
+    text.split(|c: char| !(c.is_ascii_digit() || matches!(c, '.' | '-')))
+        .filter(|s| s.chars().any(|c| c.is_ascii_digit()))
+        .find_map(|s| s.parse().ok())
+}
+
+fn run_once(command: &[String]) -> Option<f64> {
+    let (program, args) = command.split_first()?;
+    let out = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+
+    if !out.status.success() {
+        return None;
+    }
+    first_number(&String::from_utf8_lossy(&out.stdout))
+}
+
+fn run(
+    index: usize,
+    command: CommandConfig,
+    interval: Duration,
+    tx: Sender<(usize, Option<f64>)>,
+) {
+    loop {
+        let value = run_once(&command.command).map(|v| v * command.scale);
+        if tx.send((index, value)).is_err() {
+            return;
+        }
+        thread::sleep(interval);
+    }
+}
+
+/// Start running `commands`, the ones without an interval of their own
+/// every `tick`
+pub fn spawn(commands: &[CommandConfig], tick: Duration) -> Exec {
+    let (tx, rx) = channel();
+
+    let mut stale_after = Vec::new();
+    for (i, command) in commands.iter().enumerate() {
+        let interval = command.interval.map_or(tick, Duration::from_millis);
+        stale_after.push((interval * STALE_INTERVALS).max(STALE_MIN));
+        let command = command.clone();
+        let tx = tx.clone();
+        thread::spawn(move || run(i, command, interval, tx));
+    }
+
+    Exec {
+        labels: commands.iter().map(|c| c.label.clone()).collect(),
+        rx,
+        last: vec![None; commands.len()],
+        stale_after,
+    }
+}
+
+impl SensorProvider for Exec {
+    /// The last value of every command that has a recent one, as `tempN`
+    /// of an `exec` chip numbered from 1 in config order and labelled
+    /// with the command's label
+    fn poll(&mut self) -> Vec<Reading> {
+        while let Ok((i, value)) = self.rx.try_recv() {
+            self.last[i] = value.map(|v| (v, Instant::now()));
+        }
+        for (last, stale_after) in self.last.iter_mut().zip(&self.stale_after) {
+            if last.is_some_and(|(_, at)| at.elapsed() > *stale_after) {
+                *last = None;
+            }
+        }
+
+        self.labels
+            .iter()
+            .zip(&self.last)
+            .enumerate()
+            .filter_map(|(i, (label, last))| {
+                Some(Reading {
+                    chip: CHIP.to_string(),
+                    serial: None,
+                    feature: format!("temp{}", i + 1),
+                    label: Some(label.clone()),
+                    value: last.map(|(v, _)| v)?,
+                })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,285 @@
+//! Exporting samples to other tools. Each exporter runs on its own
+//! thread with a buffer in front of it, so the sampling loop only ever
+//! hands samples over a channel. Samples are written in batches, and
+//! when a write fails (the network dropped, the server restarted) they
+//! stay buffered and the batch is retried with exponential backoff.
+//!
+//! Sinks that talk to the network shell out to `curl` and
+//! `mosquitto_pub` rather than pulling in HTTP and MQTT stacks
+
+use crate::{
+    config::{ExportConfig, ExportKind},
+    history, openmetrics,
+};
+use anyhow::{bail, Context, Result};
+use std::{
+    collections::VecDeque,
+    fmt::Write as _,
+    fs::{create_dir_all, OpenOptions},
+    io::Write as _,
+    path::PathBuf,
+    process::{Command, Stdio},
+    sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender},
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+/// Samples kept while a sink is failing. Past this the oldest are
+/// dropped, which at the default interval is over 8 hours of data
+const MAX_BUFFERED: usize = 10_000;
+
+const RETRY_MIN: Duration = Duration::from_secs(1);
+const RETRY_MAX: Duration = Duration::from_secs(60);
+
+/// Give up on a single network write after this many seconds
+const TIMEOUT_SECS: &str = "10";
+
+/// Every reading taken in one tick
+#[derive(Debug, Clone)]
+pub struct Sample {
+    /// Seconds since the unix epoch
+    pub ts: u64,
+    pub readings: Vec<(String, f64)>,
+
+    /// What each reading measures, in the same order
+    pub kinds: Vec<openmetrics::Kind>,
+}
+
+enum Sink {
+    Csv(PathBuf),
+    Influx(String),
+    Mqtt { host: String, topic: String },
+    Prometheus(String),
+}
+
+/// Run `cmd` with `input` on its stdin, failing if it exits non-zero
+fn pipe(mut cmd: Command, input: &str) -> Result<()> {
+    cmd.stdin(Stdio::piped());
+    cmd.stdout(Stdio::null());
+    cmd.stderr(Stdio::null());
+
+    let program = cmd.get_program().to_string_lossy().into_owned();
+    let mut child = cmd
+        .spawn()
+        .with_context(|| format!("failed to spawn {program}"))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(input.as_bytes())?;
+    }
+
+    let status = child.wait()?;
+    if !status.success() {
+        bail!("{program} exited with {status}");
+    }
+
+    Ok(())
+}
+
+fn curl(url: &str, body: &str) -> Result<()> {
+    let mut cmd = Command::new("curl");
+    cmd.args(["-fsS", "--max-time", TIMEOUT_SECS, "--data-binary", "@-"]);
+    cmd.arg(url);
+    pipe(cmd, body)
+}
+
+/// Escape an InfluxDB line protocol tag value
+fn influx_tag(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace(',', "\\,")
+        .replace('=', "\\=")
+        .replace(' ', "\\ ")
+}
+
+fn json_str(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+impl Sink {
+    fn new(config: &ExportConfig) -> Result<Self> {
+        Ok(match config.kind {
+            ExportKind::Csv => {
+                let path = PathBuf::from(&config.target);
+                if let Some(dir) = path.parent() {
+                    create_dir_all(dir)?;
+                }
+                Sink::Csv(path)
+            }
+            ExportKind::Influx => Sink::Influx(config.target.clone()),
+            ExportKind::Mqtt => Sink::Mqtt {
+                host: config.target.clone(),
+                topic: config.topic.clone(),
+            },
+            ExportKind::Prometheus => Sink::Prometheus(config.target.clone()),
+        })
+    }
+
+    fn write(&self, batch: &[Sample]) -> Result<()> {
+        match self {
+            Sink::Csv(path) => {
+                let rows: String = batch
+                    .iter()
+                    .map(|s| history::rows(s.ts, &s.readings))
+                    .collect();
+
+                // reopened every time so the file can be rotated
+                // underneath us
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .with_context(|| format!("opening {}", path.display()))?
+                    .write_all(rows.as_bytes())?;
+            }
+            Sink::Influx(url) => {
+                let mut body = String::new();
+                for sample in batch {
+                    let ns = sample.ts as u128 * 1_000_000_000;
+                    for (label, value) in &sample.readings {
+                        let _ = writeln!(
+                            body,
+                            "sensors_mon,sensor={} value={value} {ns}",
+                            influx_tag(label)
+                        );
+                    }
+                }
+                curl(url, &body)?;
+            }
+            Sink::Mqtt { host, topic } => {
+                // one JSON message per line, mosquitto_pub -l publishes
+                // each line separately
+                let mut body = String::new();
+                for sample in batch {
+                    let _ = write!(body, "{{\"ts\":{}", sample.ts);
+                    for (label, value) in &sample.readings {
+                        let _ = write!(body, ",{}:{value}", json_str(label));
+                    }
+                    body.push_str("}\n");
+                }
+
+                let (host, port) =
+                    host.split_once(':').unwrap_or((host, "1883"));
+                let mut cmd = Command::new("mosquitto_pub");
+                cmd.args(["-h", host, "-p", port, "-t", topic, "-l"]);
+                pipe(cmd, &body)?;
+            }
+            Sink::Prometheus(url) => {
+                // the pushgateway only keeps the latest value of each
+                // metric, so there's no point sending older samples
+                let Some(sample) = batch.last() else {
+                    return Ok(());
+                };
+
+                let mut snapshot = openmetrics::Snapshot::default();
+                snapshot.readings(&sample.readings, &sample.kinds);
+                curl(url, &snapshot.finish())?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Buffer samples and write them to `sink` in batches of `batch` until
+/// the sending side hangs up
+fn run(sink: Sink, batch: usize, rx: Receiver<Sample>) {
+    let mut buffer: VecDeque<Sample> = VecDeque::new();
+    let mut backoff = RETRY_MIN;
+    let mut retry_at: Option<Instant> = None;
+
+    loop {
+        let timeout = retry_at
+            .map(|t| t.saturating_duration_since(Instant::now()))
+            .unwrap_or(RETRY_MAX);
+
+        match rx.recv_timeout(timeout) {
+            Ok(sample) => {
+                buffer.push_back(sample);
+                if buffer.len() > MAX_BUFFERED {
+                    buffer.pop_front();
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+
+        if retry_at.is_some_and(|t| Instant::now() < t) {
+            continue;
+        }
+
+        while buffer.len() >= batch {
+            match sink.write(&buffer.make_contiguous()[..batch]) {
+                Ok(()) => {
+                    buffer.drain(..batch);
+                    backoff = RETRY_MIN;
+                    retry_at = None;
+                }
+                Err(_) => {
+                    retry_at = Some(Instant::now() + backoff);
+                    backoff = (backoff * 2).min(RETRY_MAX);
+                    break;
+                }
+            }
+        }
+    }
+
+    // shutting down, send what's left (including a partial batch) but
+    // don't hold up quitting by retrying
+    while !buffer.is_empty() {
+        let n = batch.min(buffer.len());
+        if sink.write(&buffer.make_contiguous()[..n]).is_err() {
+            break;
+        }
+        buffer.drain(..n);
+    }
+}
+
+pub struct Exporter {
+    tx: Option<Sender<Sample>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Exporter {
+    pub fn spawn(config: &ExportConfig) -> Result<Self> {
+        let sink = Sink::new(config)?;
+        let batch = config.batch;
+        let (tx, rx) = channel();
+
+        let thread = std::thread::Builder::new()
+            .name(format!("export-{}", config.kind.name()))
+            .spawn(move || run(sink, batch, rx))?;
+
+        Ok(Self {
+            tx: Some(tx),
+            thread: Some(thread),
+        })
+    }
+
+    /// Queue a sample. Never blocks
+    pub fn send(&self, sample: Sample) {
+        if let Some(tx) = &self.tx {
+            let _ = tx.send(sample);
+        }
+    }
+}
+
+impl Drop for Exporter {
+    /// Flush what's buffered before exiting
+    fn drop(&mut self) {
+        self.tx = None;
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
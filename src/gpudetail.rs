@@ -0,0 +1,56 @@
+//! GPU utilization, clocks, fan and memory junction temperature, the
+//! readings that don't fit in the gauges
+
+use crate::provider::NvmlValues;
+use ratatui::{
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Color, Stylize},
+    text::Line,
+    widgets::{Block, Clear, Paragraph},
+    Frame,
+};
+
+/// Rows each GPU takes: its name and four lines of readings
+const ROWS_PER_GPU: u16 = 5;
+
+/// Draw the readings of every GPU at the top right of `area`, as
+/// (label, readings)
+pub fn render(gpus: &[(&str, &NvmlValues)], frame: &mut Frame, area: Rect) {
+    let rows = (gpus.len() as u16 * ROWS_PER_GPU).max(1);
+    let [area] = Layout::horizontal([Constraint::Length(44)])
+        .flex(Flex::End)
+        .areas(area);
+    let [area] = Layout::vertical([Constraint::Length(rows + 2)]).areas(area);
+
+    let mut lines: Vec<Line> = Vec::new();
+    for (label, v) in gpus {
+        let fan = v.fan_pct.map_or("-".to_string(), |p| format!("{p}%"));
+        let mem_temp =
+            v.mem_temp.map_or("-".to_string(), |c| format!("{c:.1}C"));
+
+        lines.push(Line::from(label.to_string()).bold());
+        lines.push(Line::from(format!(
+            "  Utilization {:>4}%   Memory bus {:>4}%",
+            v.gpu_busy, v.mem_busy
+        )));
+        lines.push(Line::from(format!(
+            "  Core {:>5} MHz     Memory {:>5} MHz",
+            v.clock_mhz, v.mem_clock_mhz
+        )));
+        lines.push(Line::from(format!(
+            "  Core {:>6.1}C       Junction {:>7}",
+            v.temp, mem_temp
+        )));
+        lines.push(Line::from(format!("  Fan {fan:>6}")));
+    }
+
+    if lines.is_empty() {
+        lines.push(Line::from("no GPU").dark_gray());
+    }
+
+    let pane = Paragraph::new(lines)
+        .block(Block::bordered().title(" GPU ").fg(Color::Gray));
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(pane, area);
+}
@@ -0,0 +1,61 @@
+//! A one-line summary of the readings written every tick, for status
+//! bars and desktop widgets (conky, i3blocks) that want a line to show
+//! rather than history or JSON to parse. Regular files are replaced
+//! whole so readers never see half a line; FIFOs get a line per tick
+//! while something is reading them
+
+use std::{
+    fs::{rename, OpenOptions},
+    io::Write as _,
+    os::unix::fs::FileTypeExt,
+    path::{Path, PathBuf},
+    sync::mpsc::{channel, Receiver, Sender},
+    thread,
+};
+
+pub struct Heartbeat {
+    tx: Sender<String>,
+}
+
+fn write(path: &Path, line: &str) -> std::io::Result<()> {
+    let fifo = path.metadata().is_ok_and(|m| m.file_type().is_fifo());
+
+    if fifo {
+        // blocks until there's a reader, which is why this runs on its
+        // own thread
+        OpenOptions::new()
+            .write(true)
+            .open(path)?
+            .write_all(line.as_bytes())
+    } else {
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        std::fs::write(&tmp, line)?;
+        rename(&tmp, path)
+    }
+}
+
+/// Write the newest line whenever there is one, skipping any that piled
+/// up while a FIFO had no reader
+fn run(path: PathBuf, rx: Receiver<String>) {
+    while let Ok(mut line) = rx.recv() {
+        while let Ok(newer) = rx.try_recv() {
+            line = newer;
+        }
+        let _ = write(&path, &line);
+    }
+}
+
+impl Heartbeat {
+    /// Start writing to `path`. The thread isn't joined on quit, a FIFO
+    /// nobody is reading would hold it up forever
+    pub fn spawn(path: PathBuf) -> Self {
+        let (tx, rx) = channel();
+        thread::spawn(move || run(path, rx));
+        Self { tx }
+    }
+
+    pub fn send(&self, line: String) {
+        let _ = self.tx.send(line + "\n");
+    }
+}
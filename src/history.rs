@@ -0,0 +1,539 @@
+//! On-disk sample history, stored as `timestamp,sensor,value` CSV rows,
+//! and the `compare`, `histogram` and `fan-curve` reports built from it.
+//! Annotations are stored as `timestamp,#annotation,text` rows and
+//! session notes as `timestamp,#note,text` rows. Fan speeds are stored
+//! like sensors, labelled with `FAN_SUFFIX`
+
+use crate::model::Stats;
+use anyhow::{anyhow, bail, Context, Result};
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    fs::{create_dir_all, read_to_string, File, OpenOptions},
+    io::Write as _,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Sensor column value marking an annotation row
+const ANNOTATION: &str = "#annotation";
+
+/// Sensor column value marking a session note row
+const NOTE: &str = "#note";
+
+/// Appended to a fan's label in the sensor column, so its speed doesn't
+/// clash with a temperature of the same name
+pub const FAN_SUFFIX: &str = " RPM";
+
+/// Appends samples to the history file
+pub struct Recorder {
+    file: File,
+}
+
+impl Recorder {
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(dir) = path.parent() {
+            create_dir_all(dir)?;
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("opening {}", path.display()))?;
+
+        Ok(Self { file })
+    }
+
+    pub fn record(&mut self, readings: &[(String, f64)]) {
+        // a full disk shouldn't take down the UI, the samples just
+        // won't be saved
+        let _ = self.file.write_all(rows(now(), readings).as_bytes());
+    }
+
+    pub fn annotate(&mut self, text: &str) {
+        let _ = writeln!(self.file, "{},{ANNOTATION},{text}", now());
+    }
+
+    pub fn note(&mut self, text: &str) {
+        let _ = writeln!(self.file, "{},{NOTE},{text}", now());
+    }
+}
+
+/// Format one sample as history rows
+pub fn rows(ts: u64, readings: &[(String, f64)]) -> String {
+    let mut rows = String::new();
+    for (label, value) in readings {
+        // labels come from us and the sensor drivers, but commas
+        // would still break the format
+        let label = label.replace(',', ";");
+        let _ = writeln!(rows, "{ts},{label},{value}");
+    }
+    rows
+}
+
+/// Seconds since the unix epoch
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date
+fn days_from_civil(y: i64, m: i64, d: i64) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * (m + if m > 2 { -3 } else { 9 }) + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+/// Year, month and day of a day count since 1970-01-01, the inverse of
+/// `days_from_civil`
+fn civil_from_days(z: i64) -> (i64, i64, i64) {
+    let z = z + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + if m <= 2 { 1 } else { 0 };
+    (y, m, d)
+}
+
+/// Format a unix timestamp as a UTC `YYYY-MM-DD HH:MM` time
+fn fmt_time(ts: u64) -> String {
+    let ts = ts as i64;
+    let (y, m, d) = civil_from_days(ts.div_euclid(86400));
+    let secs = ts.rem_euclid(86400);
+    format!(
+        "{y:04}-{m:02}-{d:02} {:02}:{:02}",
+        secs / 3600,
+        secs % 3600 / 60
+    )
+}
+
+/// Parse a unix timestamp or a UTC `YYYY-MM-DD[THH:MM[:SS]]` time
+fn parse_time(s: &str) -> Result<u64> {
+    if let Ok(ts) = s.parse::<u64>() {
+        return Ok(ts);
+    }
+
+    let err = || {
+        anyhow!("invalid time `{s}`, expected YYYY-MM-DD[THH:MM[:SS]] or a unix timestamp")
+    };
+
+    let (date, time) = s.split_once(['T', ' ']).unwrap_or((s, "00:00"));
+
+    let date: Vec<i64> = date
+        .split('-')
+        .map(|p| p.parse().map_err(|_| err()))
+        .collect::<Result<_>>()?;
+    let time: Vec<i64> = time
+        .split(':')
+        .map(|p| p.parse().map_err(|_| err()))
+        .collect::<Result<_>>()?;
+
+    let [y, mo, d] = date[..] else {
+        return Err(err());
+    };
+    let (h, mi, sec) = match time[..] {
+        [h, mi] => (h, mi, 0),
+        [h, mi, sec] => (h, mi, sec),
+        _ => return Err(err()),
+    };
+
+    let ts = days_from_civil(y, mo, d) * 86400 + h * 3600 + mi * 60 + sec;
+    u64::try_from(ts).map_err(|_| err())
+}
+
+/// Parse a `FROM..TO` range into inclusive unix timestamps
+pub fn parse_range(s: &str) -> Result<(u64, u64)> {
+    let (from, to) = s
+        .split_once("..")
+        .ok_or_else(|| anyhow!("invalid range `{s}`, expected FROM..TO"))?;
+
+    let (from, to) = (parse_time(from)?, parse_time(to)?);
+    if from > to {
+        bail!("range `{s}` ends before it starts");
+    }
+
+    Ok((from, to))
+}
+
+/// Every `(timestamp, sensor, value)` sample in the history file's
+/// text, skipping annotations, notes and rows that don't parse
+fn samples(text: &str) -> impl Iterator<Item = (u64, &str, f64)> {
+    text.lines().filter_map(|line| {
+        let mut parts = line.splitn(3, ',');
+        let (ts, label, value) = (parts.next()?, parts.next()?, parts.next()?);
+        if label == ANNOTATION || label == NOTE {
+            return None;
+        }
+
+        let (ts, value) = (ts.parse::<u64>().ok()?, value.parse::<f64>().ok()?);
+        value.is_finite().then_some((ts, label, value))
+    })
+}
+
+/// A history file read back in, with each sensor's samples in the order
+/// they were recorded
+#[derive(Debug, Default)]
+pub struct History {
+    samples: BTreeMap<String, Vec<(u64, f64)>>,
+
+    /// Session notes as `(timestamp, text)`
+    notes: Vec<(u64, String)>,
+}
+
+impl History {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = read_to_string(path)
+            .with_context(|| format!("reading {}", path.display()))?;
+
+        let mut history = Self::default();
+        for (ts, label, value) in samples(&text) {
+            history
+                .samples
+                .entry(label.to_string())
+                .or_default()
+                .push((ts, value));
+        }
+
+        history.notes = text
+            .lines()
+            .filter_map(|line| {
+                let mut parts = line.splitn(3, ',');
+                let (ts, label, text) =
+                    (parts.next()?, parts.next()?, parts.next()?);
+                (label == NOTE).then_some((ts.parse().ok()?, text.to_string()))
+            })
+            .collect();
+
+        Ok(history)
+    }
+
+    /// Labels of every sensor with samples, sorted
+    pub fn sensors(&self) -> impl Iterator<Item = &str> {
+        self.samples.keys().map(String::as_str)
+    }
+
+    /// `(timestamp, value)` samples of `sensor`
+    pub fn samples(&self, sensor: &str) -> &[(u64, f64)] {
+        self.samples
+            .get(sensor)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Values of `sensor` recorded within `range` (inclusive), or all of
+    /// them without one
+    pub fn values(&self, sensor: &str, range: Option<(u64, u64)>) -> Vec<f64> {
+        self.samples(sensor)
+            .iter()
+            .filter(|(ts, _)| range.is_none_or(|(a, b)| (a..=b).contains(ts)))
+            .map(|(_, v)| *v)
+            .collect()
+    }
+
+    /// Session notes written within `range` (inclusive), or all of them
+    /// without one
+    pub fn notes(
+        &self,
+        range: Option<(u64, u64)>,
+    ) -> impl Iterator<Item = &(u64, String)> {
+        self.notes.iter().filter(move |(ts, _)| {
+            range.is_none_or(|(a, b)| (a..=b).contains(ts))
+        })
+    }
+}
+
+/// Append the session notes in `range` to a report, under `title`
+fn write_notes(
+    out: &mut String,
+    history: &History,
+    title: &str,
+    range: Option<(u64, u64)>,
+) {
+    let mut notes = history.notes(range).peekable();
+    if notes.peek().is_none() {
+        return;
+    }
+
+    let _ = writeln!(out);
+    let _ = writeln!(out, "{title}:");
+    for (ts, text) in notes {
+        let _ = writeln!(out, "  {}  {text}", fmt_time(*ts));
+    }
+}
+
+/// Build a report comparing per-sensor avg/max/p95 between two time
+/// ranges of the history file at `path`
+pub fn compare(path: &Path, a: (u64, u64), b: (u64, u64)) -> Result<String> {
+    let history = History::load(path)?;
+
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "{:<24} {:>17} {:>17} {:>17}",
+        "sensor", "avg A/B/\u{0394}", "max A/B/\u{0394}", "p95 A/B/\u{0394}"
+    );
+
+    let mut any = false;
+    for label in history.sensors() {
+        let va = history.values(label, Some(a));
+        let vb = history.values(label, Some(b));
+        let (Some(sa), Some(sb)) = (Stats::of(va), Stats::of(vb)) else {
+            continue;
+        };
+        any = true;
+
+        let col = |a: f64, b: f64| format!("{a:.1}/{b:.1}/{:+.1}", b - a);
+        let _ = writeln!(
+            out,
+            "{label:<24} {:>17} {:>17} {:>17}",
+            col(sa.avg, sb.avg),
+            col(sa.max, sb.max),
+            col(sa.p95, sb.p95),
+        );
+    }
+
+    if !any {
+        bail!("no sensor has samples in both ranges");
+    }
+
+    write_notes(&mut out, &history, "notes in A", Some(a));
+    write_notes(&mut out, &history, "notes in B", Some(b));
+
+    Ok(out)
+}
+
+/// Build a histogram of `sensor`'s values in the history file at `path`,
+/// over `range` or the whole file. An average hides a sensor that sits
+/// at two plateaus (idle and load), the histogram shows both
+pub fn histogram(
+    path: &Path,
+    sensor: &str,
+    range: Option<(u64, u64)>,
+    bins: usize,
+) -> Result<String> {
+    // widest bar, in characters
+    const BAR_WIDTH: usize = 50;
+
+    let history = History::load(path)?;
+    let values = history.values(sensor, range);
+
+    let min = values.iter().copied().reduce(f64::min);
+    let max = values.iter().copied().reduce(f64::max);
+    let (Some(min), Some(max)) = (min, max) else {
+        bail!("no samples of `{sensor}`");
+    };
+
+    // a sensor that never moved gets a single bin
+    let bins = if max > min { bins.max(1) } else { 1 };
+    let width = (max - min) / bins as f64;
+
+    let mut counts = vec![0usize; bins];
+    for v in &values {
+        let i = if width > 0.0 {
+            ((v - min) / width) as usize
+        } else {
+            0
+        };
+        counts[i.min(bins - 1)] += 1;
+    }
+
+    let avg = values.iter().sum::<f64>() / values.len() as f64;
+    let most = counts.iter().copied().max().unwrap_or(0).max(1);
+
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "{sensor}: {} samples, min {min:.1}, avg {avg:.1}, max {max:.1}",
+        values.len()
+    );
+
+    for (i, count) in counts.iter().enumerate() {
+        let lo = min + width * i as f64;
+        let bar = "\u{2588}".repeat(count * BAR_WIDTH / most);
+        let _ = writeln!(
+            out,
+            "{lo:>7.1} - {:<7.1} {bar:<BAR_WIDTH$} {count}",
+            lo + width
+        );
+    }
+
+    write_notes(&mut out, &history, "notes", range);
+
+    Ok(out)
+}
+
+/// Seconds both the fan and the sensor have to hold still for to count
+/// as a steady state
+const STEADY_SECS: u64 = 120;
+
+/// Widest temperature swing within a steady state
+const STEADY_TEMP: f64 = 1.0;
+
+/// Widest fan speed swing within a steady state, as a fraction of its
+/// average
+const STEADY_RPM: f64 = 0.05;
+
+/// Smallest drop in temperature between two speeds worth spinning the
+/// fan faster for
+const WORTHWHILE: f64 = 0.5;
+
+/// The last value of each second of `samples`
+fn by_second(samples: &[(u64, f64)]) -> BTreeMap<u64, f64> {
+    samples.iter().copied().collect()
+}
+
+/// `(avg RPM, avg temperature, seconds)` of each steady stretch of the
+/// paired `(timestamp, RPM, temperature)` samples. Stretches don't
+/// overlap, so a long one counts once per `STEADY_SECS`
+fn steady_states(paired: &[(u64, f64, f64)]) -> Vec<(f64, f64, u64)> {
+    let mut states = Vec::new();
+
+    let mut i = 0;
+    while i < paired.len() {
+        let start = paired[i].0;
+        let Some(end) = paired[i..]
+            .iter()
+            .position(|(ts, ..)| ts - start >= STEADY_SECS)
+            .map(|n| i + n)
+        else {
+            break;
+        };
+
+        let window = &paired[i..=end];
+        let range = |f: fn(&(u64, f64, f64)) -> f64| {
+            let min = window.iter().map(f).fold(f64::INFINITY, f64::min);
+            let max = window.iter().map(f).fold(f64::NEG_INFINITY, f64::max);
+            (min, max)
+        };
+        let (rpm_lo, rpm_hi) = range(|s| s.1);
+        let (temp_lo, temp_hi) = range(|s| s.2);
+
+        let n = window.len() as f64;
+        let rpm = window.iter().map(|s| s.1).sum::<f64>() / n;
+        let temp = window.iter().map(|s| s.2).sum::<f64>() / n;
+
+        // a gap in the recording isn't a steady state, it's a gap
+        let gapless = window
+            .windows(2)
+            .all(|w| w[1].0 - w[0].0 <= STEADY_SECS / 4);
+
+        if gapless
+            && rpm_hi - rpm_lo <= rpm * STEADY_RPM
+            && temp_hi - temp_lo <= STEADY_TEMP
+        {
+            states.push((rpm, temp, paired[end].0 - start));
+            i = end + 1;
+        } else {
+            i += 1;
+        }
+    }
+
+    states
+}
+
+/// Build a report of the temperature `sensor` settles at for each speed
+/// `fan` has held steady at in the history file at `path`, over `range`
+/// or the whole file, rounded to `step` RPM. Neighbouring speeds are
+/// compared to suggest where the fan curve is worth raising and where
+/// spinning faster stops paying off. Steady states under different loads
+/// are mixed together, so a range of similar use gives the best advice
+pub fn fan_curve(
+    path: &Path,
+    fan: &str,
+    sensor: &str,
+    range: Option<(u64, u64)>,
+    step: f64,
+) -> Result<String> {
+    let history = History::load(path)?;
+
+    let rpm_label = format!("{fan}{FAN_SUFFIX}");
+    let in_range = |ts: &u64| range.is_none_or(|(a, b)| (a..=b).contains(ts));
+    let rpms = by_second(history.samples(&rpm_label));
+    let temps = by_second(history.samples(sensor));
+    if rpms.is_empty() {
+        bail!("no samples of fan `{fan}`");
+    }
+    if temps.is_empty() {
+        bail!("no samples of `{sensor}`");
+    }
+
+    let paired: Vec<(u64, f64, f64)> = rpms
+        .iter()
+        .filter(|(ts, _)| in_range(ts))
+        .filter_map(|(ts, rpm)| Some((*ts, *rpm, *temps.get(ts)?)))
+        .collect();
+
+    // (temperature * seconds, seconds) by rounded speed, to average the
+    // steady states weighted by how long each lasted
+    let mut buckets: BTreeMap<u64, (f64, u64)> = BTreeMap::new();
+    for (rpm, temp, secs) in steady_states(&paired) {
+        let key = ((rpm / step).round() * step) as u64;
+        let bucket = buckets.entry(key).or_default();
+        bucket.0 += temp * secs as f64;
+        bucket.1 += secs;
+    }
+
+    if buckets.is_empty() {
+        bail!(
+            "`{fan}` and `{sensor}` never held steady for {STEADY_SECS}s together"
+        );
+    }
+
+    let steady: Vec<(u64, f64, u64)> = buckets
+        .into_iter()
+        .map(|(rpm, (weighted, secs))| (rpm, weighted / secs as f64, secs))
+        .collect();
+
+    let mut out = String::new();
+    let _ = writeln!(out, "{sensor} at steady {fan} speeds:");
+    let _ = writeln!(out, "{:>8} {:>8} {:>8}", "RPM", "temp", "minutes");
+    for (rpm, temp, secs) in &steady {
+        let _ = writeln!(out, "{rpm:>8} {temp:>7.1}C {:>8}", secs.div_ceil(60));
+    }
+
+    if steady.len() > 1 {
+        let _ = writeln!(out);
+        let _ = writeln!(out, "suggestions:");
+        let (base_rpm, base_temp, _) = steady[0];
+        for pair in steady.windows(2) {
+            let ((lo, lo_temp, _), (hi, hi_temp, _)) = (pair[0], pair[1]);
+            let drop = lo_temp - hi_temp;
+            if drop < WORTHWHILE {
+                let _ = writeln!(
+                    out,
+                    "  going from {lo} to {hi} RPM only changes {sensor} by \
+                     {:+.1}C, the curve could top out at {lo} RPM",
+                    -drop
+                );
+            } else {
+                let _ = write!(
+                    out,
+                    "  at {hi} RPM {sensor} stabilizes ~{drop:.1}C lower than \
+                     at {lo} RPM"
+                );
+                if lo != base_rpm {
+                    let _ = write!(
+                        out,
+                        " ({:.1}C lower than at {base_rpm} RPM)",
+                        base_temp - hi_temp
+                    );
+                }
+                let _ = writeln!(out);
+            }
+        }
+    }
+
+    write_notes(&mut out, &history, "notes", range);
+
+    Ok(out)
+}
@@ -0,0 +1,286 @@
+//! Sensor backend that reads /sys/class/hwmon directly, without going
+//! through libsensors. Chip names are built the same way libsensors
+//! builds them so the chip constants work with either backend
+
+use crate::provider::{device_serial, Reading, SubFeatureReading};
+use anyhow::{bail, Result};
+use std::{
+    fs::{read_dir, read_link, read_to_string},
+    path::{Path, PathBuf},
+};
+
+const HWMON_ROOT: &str = "/sys/class/hwmon";
+
+#[derive(Debug)]
+pub struct Chip {
+    pub name: String,
+    pub path: PathBuf,
+    pub serial: Option<String>,
+}
+
+impl Chip {
+    fn read(&self, file: &str) -> Option<String> {
+        read_to_string(self.path.join(file))
+            .ok()
+            .map(|v| v.trim().to_string())
+    }
+
+    /// Temperature of a `tempN` feature in degrees C
+    pub fn temp(&self, feature: &str) -> Option<f64> {
+        self.read(&format!("{feature}_input"))?
+            .parse::<f64>()
+            .ok()
+            .map(|v| v / 1000.0)
+    }
+
+    /// Power of a `powerN` feature in watts, from the instantaneous
+    /// reading if the driver has one and the average otherwise
+    pub fn power(&self, feature: &str) -> Option<f64> {
+        self.read(&format!("{feature}_input"))
+            .or_else(|| self.read(&format!("{feature}_average")))?
+            .parse::<f64>()
+            .ok()
+            .map(|v| v / 1_000_000.0)
+    }
+
+    /// Speed of a `fanN` feature in RPM
+    pub fn fan(&self, feature: &str) -> Option<f64> {
+        self.read(&format!("{feature}_input"))?.parse::<f64>().ok()
+    }
+
+    /// Voltage of an `inN` feature in volts
+    pub fn voltage(&self, feature: &str) -> Option<f64> {
+        self.read(&format!("{feature}_input"))?
+            .parse::<f64>()
+            .ok()
+            .map(|v| v / 1000.0)
+    }
+
+    pub fn label(&self, feature: &str) -> Option<String> {
+        self.read(&format!("{feature}_label"))
+    }
+
+    /// Every `<feature>_<item>` file with a numeric value, as (feature,
+    /// file name, value) in the units libsensors reports: degrees C,
+    /// volts, amps, watts, joules, RPM and percent
+    pub fn sub_features(&self) -> Vec<(String, String, f64)> {
+        let mut ret: Vec<(String, String, f64)> = read_dir(&self.path)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|e| {
+                let file = e.file_name().into_string().ok()?;
+                let (feature, item) = file.split_once('_')?;
+                if item == "label" {
+                    return None;
+                }
+
+                let kind =
+                    feature.trim_end_matches(|c: char| c.is_ascii_digit());
+                if kind == feature {
+                    return None;
+                }
+                // flags and enums are plain integers whatever the kind
+                let flag = ["alarm", "beep", "enable", "fault", "type", "mode"]
+                    .iter()
+                    .any(|f| item.ends_with(f));
+                let scale = match kind {
+                    _ if flag => 1.0,
+                    "temp" | "in" | "curr" | "humidity" => 1000.0,
+                    "power" | "energy" => 1_000_000.0,
+                    "fan" | "pwm" => 1.0,
+                    _ => return None,
+                };
+
+                let value = self.read(&file)?.parse::<f64>().ok()? / scale;
+                Some((feature.to_string(), file, value))
+            })
+            .collect();
+
+        ret.sort_by(|a, b| a.1.cmp(&b.1));
+        ret
+    }
+
+    /// Names of all features of the given kind (`temp`, `fan`, `in`...)
+    /// that have an input file (or an average, which some drivers only
+    /// have for power), sorted by index
+    pub fn features(&self, kind: &str) -> Vec<String> {
+        let mut ret: Vec<(u32, String)> = read_dir(&self.path)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|e| {
+                let file = e.file_name().into_string().ok()?;
+                let feature = file
+                    .strip_suffix("_input")
+                    .or_else(|| file.strip_suffix("_average"))?;
+                let idx = feature.strip_prefix(kind)?.parse::<u32>().ok()?;
+                Some((idx, feature.to_string()))
+            })
+            .collect();
+
+        ret.sort();
+        ret.dedup();
+        ret.into_iter().map(|(_, f)| f).collect()
+    }
+}
+
+/// Build the libsensors-style name (e.g. `k10temp-pci-00c3`) for the
+/// hwmon device at `path`
+fn chip_name(path: &Path) -> Option<String> {
+    let device = path.join("device");
+    let prefix = read_to_string(path.join("name"))
+        .or_else(|_| read_to_string(device.join("name")))
+        .ok()?
+        .trim()
+        .to_string();
+
+    let dev_name = match device.canonicalize() {
+        Ok(d) => d.file_name()?.to_string_lossy().to_string(),
+        Err(_) => return Some(format!("{prefix}-virtual-0")),
+    };
+
+    let subsystem = read_link(device.join("subsystem"))
+        .ok()
+        .and_then(|s| Some(s.file_name()?.to_string_lossy().to_string()))
+        .unwrap_or_default();
+
+    let hex = |s: &str| u32::from_str_radix(s, 16).ok();
+
+    let name = match subsystem.as_str() {
+        "i2c" => {
+            let (bus, addr) = dev_name.split_once('-')?;
+            format!(
+                "{prefix}-i2c-{}-{:02x}",
+                bus.parse::<u32>().ok()?,
+                hex(addr)?
+            )
+        }
+        "pci" => {
+            // domain:bus:slot.fn
+            let (rest, func) = dev_name.rsplit_once('.')?;
+            let parts: Vec<&str> = rest.split(':').collect();
+            let [domain, bus, slot] = parts[..] else {
+                return None;
+            };
+
+            let addr = (hex(domain)? << 16)
+                + (hex(bus)? << 8)
+                + (hex(slot)? << 3)
+                + hex(func)?;
+            format!("{prefix}-pci-{addr:04x}")
+        }
+        "hid" => {
+            // bus:vendor:product.id
+            let (rest, id) = dev_name.rsplit_once('.')?;
+            let bus = hex(rest.split(':').next()?)?;
+            format!("{prefix}-hid-{bus}-{:x}", hex(id)?)
+        }
+        "spi" => {
+            let (bus, addr) = dev_name.strip_prefix("spi")?.split_once('.')?;
+            format!("{prefix}-spi-{bus}-{addr}")
+        }
+        "platform" | "of_platform" => {
+            let addr = dev_name
+                .rsplit_once('.')
+                .and_then(|(_, n)| n.parse::<u32>().ok())
+                .unwrap_or(0);
+            format!("{prefix}-isa-{addr:04x}")
+        }
+        "acpi" => format!("{prefix}-acpi-0"),
+        _ => format!("{prefix}-virtual-0"),
+    };
+
+    Some(name)
+}
+
+/// Every hwmon chip currently present
+pub fn chips() -> Vec<Chip> {
+    read_dir(HWMON_ROOT)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|e| {
+            let path = e.path();
+            Some(Chip {
+                name: chip_name(&path)?,
+                serial: device_serial(&path),
+                path,
+            })
+        })
+        .collect()
+}
+
+pub struct Sensors {
+    chips: Vec<Chip>,
+}
+
+pub fn init() -> Result<Sensors> {
+    let chips = chips();
+    if chips.is_empty() {
+        bail!("no hwmon devices found in {HWMON_ROOT}");
+    }
+
+    Ok(Sensors { chips })
+}
+
+impl Sensors {
+    /// Every temperature, power, fan and voltage feature of every chip
+    /// that currently has a value
+    pub fn readings(&self) -> Vec<Reading> {
+        let mut ret = Vec::new();
+
+        for chip in &self.chips {
+            let temps = chip
+                .features("temp")
+                .into_iter()
+                .filter_map(|f| Some((chip.temp(&f)?, f)));
+            let powers = chip
+                .features("power")
+                .into_iter()
+                .filter_map(|f| Some((chip.power(&f)?, f)));
+            let fans = chip
+                .features("fan")
+                .into_iter()
+                .filter_map(|f| Some((chip.fan(&f)?, f)));
+            let voltages = chip
+                .features("in")
+                .into_iter()
+                .filter_map(|f| Some((chip.voltage(&f)?, f)));
+
+            for (value, feature) in
+                temps.chain(powers).chain(fans).chain(voltages)
+            {
+                ret.push(Reading {
+                    chip: chip.name.clone(),
+                    serial: chip.serial.clone(),
+                    label: chip.label(&feature),
+                    feature,
+                    value,
+                });
+            }
+        }
+
+        ret
+    }
+
+    /// Every readable value of every chip
+    pub fn sub_features(&self) -> Vec<SubFeatureReading> {
+        let mut ret = Vec::new();
+
+        for chip in &self.chips {
+            for (feature, sub_feature, value) in chip.sub_features() {
+                ret.push(SubFeatureReading {
+                    chip: chip.name.clone(),
+                    serial: chip.serial.clone(),
+                    label: chip.label(&feature),
+                    feature,
+                    sub_feature,
+                    value,
+                });
+            }
+        }
+
+        ret
+    }
+}
@@ -0,0 +1,394 @@
+//! Sensor and fan rules read out of the configs of other fan tools, so
+//! their users don't describe the same hardware twice. fancontrol's
+//! config says which temperature drives which PWM output and which fan
+//! is on it. liquidctl has no config of its own, so the scripts and
+//! systemd units it's run from are read for the devices it's pointed at
+//! and the channels it sets speeds on
+
+use crate::config::{FanConfig, Selector, SensorConfig, DEFAULT_FAN_MAX_RPM};
+use anyhow::{bail, Result};
+use std::{collections::BTreeMap, fmt::Write};
+
+/// Where fancontrol's `pwmconfig` writes its config
+pub const FANCONTROL_PATH: &str = "/etc/fancontrol";
+
+/// Devices liquidctl drives that are read here too, by a word of their
+/// liquidctl description (matched in order, so the specific ones come
+/// first), with the name their rules are labelled with, the chips they
+/// show up as through the kernel driver or the USB HID backend, and
+/// whether they read the coolant temperature
+const DEVICES: &[(&str, &str, &[&str], bool)] = &[
+    ("kraken z", "Kraken", &["z53-*", "kraken-z3-*"], true),
+    (
+        "kraken x",
+        "Kraken",
+        &["x53-*", "kraken-x3-*", "kraken2-*", "kraken-x2-*"],
+        true,
+    ),
+    (
+        "kraken",
+        "Kraken",
+        &[
+            "z53-*",
+            "x53-*",
+            "kraken-z3-*",
+            "kraken-x3-*",
+            "kraken2-*",
+            "kraken-x2-*",
+        ],
+        true,
+    ),
+    ("smart device", "Smart Device", &["nzxtsmart2-*"], false),
+    (
+        "rgb & fan",
+        "RGB & Fan Controller",
+        &["nzxtsmart2-*"],
+        false,
+    ),
+    (
+        "commander pro",
+        "Commander Pro",
+        &["corsaircpro-*", "commander-pro-*"],
+        false,
+    ),
+    ("d5 next", "D5 Next", &["d5next-*"], true),
+    ("octo", "Octo", &["octo-*"], false),
+    ("quadro", "Quadro", &["quadro-*"], false),
+];
+
+/// Rules read out of another tool's config
+#[derive(Debug, Default)]
+pub struct Import {
+    pub sensors: Vec<SensorConfig>,
+
+    /// With a comment on how the other tool drove the fan
+    pub fans: Vec<(FanConfig, String)>,
+
+    /// What was in the config but couldn't be turned into a rule
+    pub skipped: Vec<String>,
+}
+
+impl Import {
+    fn sensor(
+        &mut self,
+        label: String,
+        chip: &str,
+        feature: &str,
+        serial: Option<&str>,
+    ) {
+        if self.sensors.iter().any(|s| {
+            s.label.as_ref() == Some(&label) && s.select.chip.as_str() == chip
+        }) {
+            return;
+        }
+
+        if let Some(select) = selector(chip, feature, serial) {
+            self.sensors.push(SensorConfig {
+                label: Some(label),
+                select,
+                chart: None,
+                samples: 1,
+            });
+        }
+    }
+
+    fn fan(
+        &mut self,
+        label: String,
+        chip: &str,
+        feature: &str,
+        serial: Option<&str>,
+        note: String,
+    ) {
+        if let Some((_, old)) = self
+            .fans
+            .iter_mut()
+            .find(|(f, _)| f.label == label && f.select.chip.as_str() == chip)
+        {
+            // a later command for the same channel is the one that sticks
+            *old = note;
+            return;
+        }
+
+        if let Some(select) = selector(chip, feature, serial) {
+            self.fans.push((
+                FanConfig {
+                    label,
+                    select,
+                    max_rpm: DEFAULT_FAN_MAX_RPM,
+                    noise: Vec::new(),
+                },
+                note,
+            ));
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sensors.is_empty() && self.fans.is_empty()
+    }
+
+    /// Leave out the rules labelled the same as one in `sensors` or
+    /// `fans`, which were imported before or written by hand
+    pub fn without(&mut self, sensors: &[SensorConfig], fans: &[FanConfig]) {
+        self.sensors
+            .retain(|s| !sensors.iter().any(|o| o.label == s.label));
+        self.fans
+            .retain(|(f, _)| !fans.iter().any(|o| o.label == f.label));
+    }
+
+    /// The rules as `[[sensors]]` and `[[fans]]` tables, headed by a
+    /// comment naming where they came from
+    pub fn to_toml(&self, source: &str) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "# imported from {source}");
+
+        for s in &self.sensors {
+            let _ = writeln!(out);
+            out.push_str(&s.to_toml());
+        }
+
+        for (f, note) in &self.fans {
+            let _ = writeln!(out);
+            let _ = writeln!(out, "# {note}");
+            out.push_str(&f.to_toml());
+        }
+
+        out
+    }
+}
+
+/// The selector for `chip` and `feature`, unless a name from the other
+/// tool's config doesn't make a valid pattern
+fn selector(
+    chip: &str,
+    feature: &str,
+    serial: Option<&str>,
+) -> Option<Selector> {
+    Some(Selector {
+        chip: glob::Pattern::new(chip).ok()?,
+        feature: glob::Pattern::new(feature).ok()?,
+        serial: serial.map(str::to_string),
+    })
+}
+
+/// `KEY=a=b c=d` lines of a fancontrol config, as `KEY -> [(a, b)]`
+fn fancontrol_vars(text: &str) -> BTreeMap<&str, Vec<(&str, &str)>> {
+    text.lines()
+        .map(str::trim)
+        .filter(|l| !l.starts_with('#'))
+        .filter_map(|l| l.split_once('='))
+        .map(|(key, value)| {
+            let pairs = value
+                .split_whitespace()
+                .filter_map(|p| p.split_once('='))
+                .collect();
+            (key.trim(), pairs)
+        })
+        .collect()
+}
+
+/// The hwmon device and feature name of a fancontrol path, like
+/// `hwmon1/device/temp2_input` -> (`hwmon1`, `temp2`)
+fn fancontrol_path(path: &str) -> Option<(&str, &str)> {
+    let dev = path.split('/').next()?;
+    let file = path.rsplit('/').next()?;
+    Some((dev, file.strip_suffix("_input").unwrap_or(file)))
+}
+
+/// Rules for the temperatures and fans of a fancontrol config
+pub fn fancontrol(text: &str) -> Result<Import> {
+    let vars = fancontrol_vars(text);
+    let Some(temps) = vars.get("FCTEMPS") else {
+        bail!("no FCTEMPS, is this a fancontrol config?");
+    };
+
+    let names: BTreeMap<&str, &str> =
+        vars.get("DEVNAME").into_iter().flatten().copied().collect();
+    let fans: BTreeMap<&str, &str> =
+        vars.get("FCFANS").into_iter().flatten().copied().collect();
+    let limit = |key: &str, pwm: &str| {
+        vars.get(key)?
+            .iter()
+            .find(|(p, _)| *p == pwm)
+            .map(|(_, v)| *v)
+    };
+
+    // without DEVNAME (configs from before it was added) the chip is
+    // left open and the rules go by feature name alone
+    let chip = |dev: &str| {
+        names
+            .get(dev)
+            .map_or_else(|| "*".to_string(), |name| format!("{name}-*"))
+    };
+    let name = |dev: &str| names.get(dev).copied().unwrap_or(dev).to_string();
+
+    let mut import = Import::default();
+
+    for (pwm, temp_paths) in temps {
+        let Some((pwm_dev, pwm_feature)) = fancontrol_path(pwm) else {
+            continue;
+        };
+
+        // newer fancontrol versions follow the hottest of several
+        // temperatures, joined by `+`
+        let mut followed = Vec::new();
+        for path in temp_paths.split('+') {
+            let Some((dev, feature)) = fancontrol_path(path) else {
+                import.skipped.push(format!("temperature `{path}`"));
+                continue;
+            };
+            let label = format!("{} {feature}", name(dev));
+            import.sensor(label.clone(), &chip(dev), feature, None);
+            followed.push(label);
+        }
+
+        let Some(fan_paths) = fans.get(pwm) else {
+            import
+                .skipped
+                .push(format!("`{pwm}`, it has no fan in FCFANS"));
+            continue;
+        };
+
+        let mut note = format!(
+            "{} {pwm_feature}, follows {}",
+            name(pwm_dev),
+            followed.join(" and ")
+        );
+        if let (Some(min), Some(max)) =
+            (limit("MINTEMP", pwm), limit("MAXTEMP", pwm))
+        {
+            let _ = write!(note, " from {min}C to {max}C");
+        }
+
+        for path in fan_paths.split('+') {
+            let Some((dev, feature)) = fancontrol_path(path) else {
+                import.skipped.push(format!("fan `{path}`"));
+                continue;
+            };
+            let label = format!("{} {feature}", name(dev));
+            import.fan(label, &chip(dev), feature, None, note.clone());
+        }
+    }
+
+    Ok(import)
+}
+
+/// The words of a shell command line, with quotes taken off
+fn words(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut quote = None;
+    let mut any = false;
+
+    for c in line.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => word.push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                any = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if any || !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+                any = false;
+            }
+            (None, c) => word.push(c),
+        }
+    }
+    if any || !word.is_empty() {
+        words.push(word);
+    }
+
+    words
+}
+
+/// Rules for the devices the `liquidctl` commands in a script or
+/// systemd unit set fan and pump speeds on
+pub fn liquidctl(text: &str) -> Import {
+    let mut import = Import::default();
+
+    for line in text.lines().map(str::trim) {
+        if line.starts_with('#') {
+            continue;
+        }
+        let words = words(line);
+        let Some(start) = words.iter().position(|w| w.ends_with("liquidctl"))
+        else {
+            continue;
+        };
+        let words = &words[start + 1..];
+
+        let mut matched = None;
+        let mut serial = None;
+        let mut i = 0;
+        while i < words.len() {
+            let word = words[i].as_str();
+            let value = words.get(i + 1).map(String::as_str);
+            if let Some(v) = word.strip_prefix("--match=") {
+                matched = Some(v);
+            } else if let Some(v) = word.strip_prefix("--serial=") {
+                serial = Some(v);
+            } else if word == "--match" || word == "-m" {
+                matched = value;
+                i += 1;
+            } else if word == "--serial" {
+                serial = value;
+                i += 1;
+            } else if word == "set" {
+                break;
+            }
+            i += 1;
+        }
+
+        // only speed settings say which fans there are, colors and
+        // initialization don't
+        let args = &words[i.min(words.len())..];
+        let [set, channel, speed, rest @ ..] = args else {
+            continue;
+        };
+        if set != "set" || speed != "speed" {
+            continue;
+        }
+
+        let Some(matched) = matched else {
+            import.skipped.push(format!(
+                "`{line}`, without --match it isn't clear which device it sets"
+            ));
+            continue;
+        };
+        let Some((_, name, chips, coolant)) = DEVICES
+            .iter()
+            .find(|(word, ..)| matched.to_lowercase().contains(word))
+        else {
+            import.skipped.push(format!(
+                "`{matched}`, it isn't a device sensors-mon reads"
+            ));
+            continue;
+        };
+
+        // drivers label their channels (`Pump`, `Fan 1 speed`), which
+        // feature patterns match as well as the feature names
+        let (label, feature) = match channel.as_str() {
+            "pump" => (format!("{name} pump"), "Pump*".to_string()),
+            "fan" => (format!("{name} fan"), "Fan*".to_string()),
+            "sync" => (format!("{name} fans"), "fan*".to_string()),
+            c if c.starts_with("fan") => (format!("{name} {c}"), c.to_string()),
+            c => {
+                import.skipped.push(format!("`{c}` channel of `{matched}`"));
+                continue;
+            }
+        };
+
+        let note = format!("set by liquidctl to speed {}", rest.join(" "));
+        for chip in *chips {
+            if *coolant {
+                import.sensor(format!("{name} liquid"), chip, "temp1", serial);
+            }
+            import.fan(label.clone(), chip, &feature, serial, note.clone());
+        }
+    }
+
+    import
+}
@@ -0,0 +1,167 @@
+//! GPU readings for Intel Arc and integrated GPUs, read from sysfs. The
+//! i915 and xe drivers don't have anything like NVML, so temperature
+//! comes from the card's hwmon device, power from its energy counter
+//! (or RAPL's uncore domain on integrated GPUs) and the clock from the
+//! driver's frequency files
+
+use crate::provider::NvmlValues;
+use anyhow::{bail, Result};
+use std::{
+    fs::{read_dir, read_link, read_to_string},
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+const DRM_ROOT: &str = "/sys/class/drm";
+const RAPL_ROOT: &str = "/sys/class/powercap";
+const DRIVERS: [&str; 2] = ["i915", "xe"];
+
+pub struct Gpu {
+    /// The `cardN` directory
+    card: PathBuf,
+    driver: String,
+    hwmon: Option<PathBuf>,
+
+    /// Energy counter in microjoules. Power is worked out from how much
+    /// it moved since the last reading
+    energy: Option<PathBuf>,
+    last_energy: Option<(Instant, u64)>,
+}
+
+fn read(path: &Path) -> Option<String> {
+    read_to_string(path).ok().map(|v| v.trim().to_string())
+}
+
+fn read_u64(path: &Path) -> Option<u64> {
+    read(path)?.parse().ok()
+}
+
+fn driver(card: &Path) -> Option<String> {
+    let link = read_link(card.join("device/driver")).ok()?;
+    Some(link.file_name()?.to_str()?.to_string())
+}
+
+/// The RAPL domain covering the integrated GPU, which has no energy
+/// counter of its own
+fn rapl_uncore() -> Option<PathBuf> {
+    read_dir(RAPL_ROOT)
+        .ok()?
+        .flatten()
+        .map(|e| e.path())
+        .find(|p| read(&p.join("name")).as_deref() == Some("uncore"))
+        .map(|p| p.join("energy_uj"))
+}
+
+/// The first card driven by i915 or xe
+pub fn init() -> Result<Gpu> {
+    let mut cards: Vec<PathBuf> = read_dir(DRM_ROOT)?
+        .flatten()
+        .filter(|e| {
+            let name = e.file_name();
+            let name = name.to_str().unwrap_or("");
+            // connectors show up as `card0-DP-1` next to the cards
+            name.starts_with("card") && !name.contains('-')
+        })
+        .map(|e| e.path())
+        .collect();
+    cards.sort();
+
+    let Some((card, driver)) = cards.into_iter().find_map(|c| {
+        let d = driver(&c)?;
+        DRIVERS.contains(&d.as_str()).then_some((c, d))
+    }) else {
+        bail!("no GPU using the i915 or xe driver");
+    };
+
+    let hwmon = read_dir(card.join("device/hwmon"))
+        .ok()
+        .and_then(|mut d| d.next())
+        .and_then(|e| e.ok())
+        .map(|e| e.path());
+
+    let energy = hwmon
+        .as_ref()
+        .map(|h| h.join("energy1_input"))
+        .filter(|p| p.exists())
+        .or_else(rapl_uncore);
+
+    Ok(Gpu {
+        card,
+        driver,
+        hwmon,
+        energy,
+        last_energy: None,
+    })
+}
+
+impl Gpu {
+    /// One line for `sensors-mon list` with the card, driver and
+    /// current temperature, power and clock
+    pub fn describe(&mut self) -> String {
+        let card = self.card.file_name().unwrap_or_default().to_string_lossy();
+        let card = format!("{card} ({})", self.driver);
+        let v = self.values();
+
+        format!(
+            "{card:<45} {:>6} {:>8} {:>9}",
+            format!("{:.0}C", v.temp),
+            format!("{:.1}W", v.watts),
+            format!("{}MHz", v.clock_mhz)
+        )
+    }
+
+    /// Temperature of the first `tempN` on the card's hwmon device. The
+    /// xe driver numbers them from 2, and integrated GPUs have none
+    fn temp(&self) -> Option<f64> {
+        let hwmon = self.hwmon.as_ref()?;
+
+        (1..=3)
+            .find_map(|i| read_u64(&hwmon.join(format!("temp{i}_input"))))
+            .map(|v| v as f64 / 1000.0)
+    }
+
+    /// Instantaneous power if the driver reports it, otherwise the
+    /// average since the last call from the energy counter. The first
+    /// call only primes the counter
+    fn watts(&mut self) -> Option<f64> {
+        if let Some(uw) = self
+            .hwmon
+            .as_ref()
+            .and_then(|h| read_u64(&h.join("power1_input")))
+        {
+            return Some(uw as f64 / 1_000_000.0);
+        }
+
+        let now = Instant::now();
+        let uj = read_u64(self.energy.as_ref()?)?;
+        let last = self.last_energy.replace((now, uj));
+
+        let (then, prev) = last?;
+        let secs = now.duration_since(then).as_secs_f64();
+        // the counter wraps, skip the reading where it does
+        if secs <= 0.0 || uj < prev {
+            return None;
+        }
+
+        Some((uj - prev) as f64 / 1_000_000.0 / secs)
+    }
+
+    fn clock_mhz(&self) -> Option<u32> {
+        let file = if self.driver == "xe" {
+            self.card.join("device/tile0/gt0/freq0/cur_freq")
+        } else {
+            self.card.join("gt_cur_freq_mhz")
+        };
+
+        read(&file)?.parse().ok()
+    }
+
+    pub fn values(&mut self) -> NvmlValues {
+        NvmlValues {
+            temp: self.temp().unwrap_or(0.0),
+            watts: self.watts().unwrap_or(0.0),
+            clock_mhz: self.clock_mhz().unwrap_or(0),
+            ..Default::default()
+        }
+    }
+}
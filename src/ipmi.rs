@@ -0,0 +1,158 @@
+//! Chassis temperatures, fans, PSU power and voltages from a server's
+//! BMC over IPMI, read with `ipmitool sdr`. Asking the BMC takes a
+//! second or more, so it's done on a thread of its own and the provider
+//! hands out whatever it read last, as long as that's recent
+
+use crate::provider::{Reading, SensorProvider};
+use anyhow::{bail, Context, Result};
+use std::{
+    path::Path,
+    process::{Command, Stdio},
+    sync::mpsc::{channel, Receiver, Sender, TryRecvError},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Where the kernel's IPMI driver puts its device, depending on udev
+const DEVICES: [&str; 3] = ["/dev/ipmi0", "/dev/ipmi/0", "/dev/ipmidev/0"];
+
+/// How often the BMC is asked. Most only refresh their sensors every few
+/// seconds anyway
+const IPMI_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Longest wait between tries while the BMC isn't answering
+const RETRY_MAX: Duration = Duration::from_secs(300);
+
+/// Readings older than this are dropped, the BMC has stopped answering
+/// (or `ipmitool` hangs) and they'd pass for current ones
+const STALE_AFTER: Duration = Duration::from_secs(30);
+
+/// Units `ipmitool` reports and the feature prefixes they're read as
+const UNITS: [(&str, &str); 4] = [
+    ("degrees C", "temp"),
+    ("RPM", "fan"),
+    ("Watts", "power"),
+    ("Volts", "in"),
+];
+
+pub struct Ipmi {
+    rx: Receiver<Vec<Reading>>,
+    last: Vec<Reading>,
+    /// When `last` was read
+    read_at: Instant,
+}
+
+/// Every analog sensor of the BMC in a unit we know, as `tempN`, `fanN`,
+/// `powerN` and `inN` of an `ipmi` chip, numbered from 1 in the order the
+/// BMC lists them and labelled with the sensor's name. Sensors without a
+/// reading (`na`, `disabled`) are skipped but keep their number
+pub fn readings() -> Result<Vec<Reading>> {
+    if !DEVICES.iter().any(|d| Path::new(d).exists()) {
+        bail!("no IPMI device, is the ipmi_si module loaded?");
+    }
+
+    let out = Command::new("ipmitool")
+        .args(["-c", "sdr", "list", "full"])
+        .stdin(Stdio::null())
+        .output()
+        .context("running ipmitool")?;
+    if !out.status.success() {
+        bail!(
+            "ipmitool failed: {}",
+            String::from_utf8_lossy(&out.stderr).trim()
+        );
+    }
+
+    let mut counts = [0; UNITS.len()];
+    let mut ret = Vec::new();
+
+    // `CPU1 Temp,45,degrees C,ok`
+    for line in String::from_utf8_lossy(&out.stdout).lines() {
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let [name, value, unit, ..] = fields[..] else {
+            continue;
+        };
+        let Some(i) = UNITS.iter().position(|(u, _)| *u == unit) else {
+            continue;
+        };
+        counts[i] += 1;
+
+        let Ok(value) = value.parse() else {
+            continue;
+        };
+        ret.push(Reading {
+            chip: "ipmi".to_string(),
+            serial: None,
+            feature: format!("{}{}", UNITS[i].1, counts[i]),
+            label: Some(name.to_string()),
+            value,
+        });
+    }
+
+    Ok(ret)
+}
+
+/// Read the BMC every [`IPMI_INTERVAL`] until the provider is gone. A
+/// failed read sends no readings and is tried again after twice the
+/// wait of the last one, up to [`RETRY_MAX`]
+fn run(tx: Sender<Vec<Reading>>) {
+    let mut wait = IPMI_INTERVAL;
+    loop {
+        thread::sleep(wait);
+        let readings = match readings() {
+            Ok(readings) => {
+                wait = IPMI_INTERVAL;
+                readings
+            }
+            Err(_) => {
+                wait = (wait * 2).min(RETRY_MAX);
+                Vec::new()
+            }
+        };
+        if tx.send(readings).is_err() {
+            return;
+        }
+    }
+}
+
+/// Read the BMC once and keep reading it in the background. Fails on
+/// machines without an IPMI device or `ipmitool`, or when the device
+/// can't be opened (it's usually root only)
+pub fn init() -> Result<Ipmi> {
+    let last = readings()?;
+    if last.is_empty() {
+        bail!("the BMC reports no sensors");
+    }
+
+    let (tx, rx) = channel();
+    thread::spawn(move || run(tx));
+    Ok(Ipmi {
+        rx,
+        last,
+        read_at: Instant::now(),
+    })
+}
+
+impl SensorProvider for Ipmi {
+    /// What the BMC last answered with, or nothing once that's older
+    /// than [`STALE_AFTER`] or the reading thread is gone
+    fn poll(&mut self) -> Vec<Reading> {
+        loop {
+            match self.rx.try_recv() {
+                Ok(readings) => {
+                    self.last = readings;
+                    self.read_at = Instant::now();
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.last.clear();
+                    break;
+                }
+            }
+        }
+        if self.read_at.elapsed() > STALE_AFTER {
+            self.last.clear();
+        }
+        self.last.clone()
+    }
+}
@@ -0,0 +1,34 @@
+//! Sensor polling, history and configuration behind the sensors-mon
+//! dashboard. The TUI binary is built on top of this, and other tools
+//! can use it to poll the same sensors or read the same history files
+
+#[cfg(not(any(feature = "libsensors", feature = "hwmon-sysfs")))]
+compile_error!("at least one of `libsensors` or `hwmon-sysfs` must be enabled");
+
+pub mod battery;
+pub mod config;
+pub mod cpustat;
+pub mod drives;
+pub mod exec;
+pub mod history;
+#[cfg(feature = "hwmon-sysfs")]
+pub mod hwmon;
+pub mod import;
+pub mod intel;
+pub mod ipmi;
+#[cfg(feature = "libsensors")]
+pub mod lmsensors;
+pub mod meminfo;
+pub mod model;
+pub mod net;
+pub mod noise;
+#[cfg(feature = "nvml")]
+pub mod nvml;
+pub mod procs;
+pub mod provider;
+pub mod psi;
+pub mod rapl;
+pub mod svg;
+pub mod thermal;
+#[cfg(feature = "usb-hid")]
+pub mod usbhid;
@@ -0,0 +1,98 @@
+//! Sensor backend built on libsensors
+
+use crate::provider::{device_serial, Reading, SubFeatureReading};
+use anyhow::Result;
+use lm_sensors::{Initializer, LMSensors};
+
+pub struct Sensors(LMSensors);
+
+pub fn init() -> Result<Sensors> {
+    Ok(Sensors(Initializer::default().initialize()?))
+}
+
+impl Sensors {
+    /// Every temperature, power, fan and voltage feature of every chip
+    /// that currently has a value. Power is the instantaneous reading if
+    /// the driver has one and the average otherwise
+    pub fn readings(&self) -> Vec<Reading> {
+        let mut ret = Vec::new();
+
+        for chip in self.0.chip_iter(None) {
+            let cname = chip.name().unwrap_or_default();
+            let serial = chip.path().and_then(device_serial);
+
+            for feature in chip.feature_iter() {
+                let name = feature.name().unwrap_or(Ok("")).unwrap_or("");
+                // `in` also starts `intrusion0`, which has no input
+                if !["temp", "power", "fan", "in"]
+                    .iter()
+                    .any(|k| name.starts_with(k))
+                {
+                    continue;
+                }
+
+                let mut value = None;
+                for sub_feature in feature.sub_feature_iter() {
+                    match sub_feature.value() {
+                        Ok(lm_sensors::Value::TemperatureInput(v))
+                        | Ok(lm_sensors::Value::PowerInput(v))
+                        | Ok(lm_sensors::Value::FanInput(v))
+                        | Ok(lm_sensors::Value::VoltageInput(v)) => {
+                            value = Some(v)
+                        }
+                        Ok(lm_sensors::Value::PowerAverage(v))
+                            if value.is_none() =>
+                        {
+                            value = Some(v)
+                        }
+                        _ => {}
+                    }
+                }
+
+                if let Some(value) = value {
+                    ret.push(Reading {
+                        chip: cname.clone(),
+                        serial: serial.clone(),
+                        feature: name.to_string(),
+                        label: feature.label().ok(),
+                        value,
+                    });
+                }
+            }
+        }
+
+        ret
+    }
+
+    /// Every readable value of every chip
+    pub fn sub_features(&self) -> Vec<SubFeatureReading> {
+        let mut ret = Vec::new();
+
+        for chip in self.0.chip_iter(None) {
+            let cname = chip.name().unwrap_or_default();
+            let serial = chip.path().and_then(device_serial);
+
+            for feature in chip.feature_iter() {
+                let name = feature.name().unwrap_or(Ok("")).unwrap_or("");
+                let label = feature.label().ok();
+
+                for sub_feature in feature.sub_feature_iter() {
+                    let sname =
+                        sub_feature.name().unwrap_or(Ok("")).unwrap_or("");
+                    if let Ok(value) = sub_feature.raw_value() {
+                        ret.push(SubFeatureReading {
+                            chip: cname.clone(),
+                            serial: serial.clone(),
+                            feature: name.to_string(),
+                            label: label.clone(),
+                            sub_feature: sname.to_string(),
+                            value,
+                        });
+                    }
+                }
+            }
+        }
+
+        ret
+    }
+}
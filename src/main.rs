@@ -1,40 +1,148 @@
-use anyhow::Result;
-use lm_sensors::{Initializer, LMSensors};
+mod alerts;
+mod baseline;
+mod clipboard;
+mod control;
+mod cores;
+mod diagnose;
+mod dryrun;
+mod export;
+mod gpudetail;
+mod heartbeat;
+mod openmetrics;
+mod prompt;
+mod selfmon;
+mod serial;
+mod session;
+mod settings;
+mod startup;
+mod tiles;
+mod uistate;
+#[cfg(feature = "update-check")]
+mod update;
+
+use anyhow::{Context, Result};
+use config::{Config, GpuProvider};
+use model::{is_present, Fusion, MinMax, Series};
 use num_format::{Locale, ToFormattedString};
-use nvml_wrapper::{enum_wrappers::device::TemperatureSensor, Nvml};
+use provider::{NvmlValues, Reading, SensorProvider, Sensors, Throttle};
 use ratatui::{
-    crossterm::event::{self, Event, KeyCode},
+    crossterm::event::{self, Event, KeyCode, KeyEvent},
     layout::{Constraint, Layout, Rect},
     style::{Color, Modifier, Style, Stylize},
     symbols,
-    text::Span,
+    text::{Line, Span, Text},
     widgets::{
         Axis, Block, Borders, Chart, Dataset, Gauge, GraphType, LegendPosition,
-        Padding, Row, Table,
+        Padding, Row, Sparkline, Table, Widget,
     },
-    DefaultTerminal, Frame,
+    DefaultTerminal, Frame, TerminalOptions, Viewport,
+};
+#[cfg(feature = "nvml")]
+use sensors_mon::nvml;
+#[cfg(feature = "usb-hid")]
+use sensors_mon::usbhid;
+use sensors_mon::{
+    battery, config, cpustat, exec, history, import, intel, ipmi, meminfo,
+    model, net, noise, procs, provider, psi, svg, thermal,
 };
+#[cfg(feature = "nvml")]
+use std::rc::Rc;
 use std::{
-    cmp::Ordering,
+    borrow::Cow,
+    collections::{BTreeMap, VecDeque},
     fs::read_to_string,
-    path::PathBuf,
+    io::Write,
+    path::{Path, PathBuf},
     process::{Command, Stdio},
-    time::{Duration, Instant},
+    sync::mpsc::{Receiver, TryRecvError},
+    time::{Duration, Instant, SystemTime},
 };
 
-const INTERVAL: u64 = 3000;
-const WINDOW_SIZE: u64 = (5 * 60) / (INTERVAL / 1000);
 const BOUNDS_PADDING: f64 = 2.0;
 const BOUNDS_MIN: f64 = 25.0;
 const BOUNDS_MAX: f64 = 90.0;
 
-const B_TO_MIB: u64 = 1024 * 1024;
+// height of the dashboard when drawing inline instead of on the
+// alternate screen
+const INLINE_HEIGHT: u16 = 24;
+
+// rows taken by the gauge stack in the bottom panel, including its
+// border, without any GPUs, and the rows each GPU's gauges add
+const GAUGES_HEIGHT_NO_GPU: u16 = 7;
+const GPU_GAUGES_HEIGHT: u16 = 6;
+const CPU_GAUGE_HEIGHT: u16 = 2;
+const MEM_GAUGE_HEIGHT: u16 = 2;
+const BATTERY_GAUGE_HEIGHT: u16 = 2;
+const NET_GAUGE_HEIGHT: u16 = 2;
+
+/// The CPU panel's border, its load line and two rows of sparkline
+const CPU_PANEL_HEIGHT: u16 = 5;
+
+/// Utilization samples kept for the sparkline, more than a wide panel
+/// can show
+const CPU_UTIL_HISTORY: usize = 300;
+
+/// The loop panel's border, its flow line and two rows of sparkline,
+/// without the pump table
+const LOOP_PANEL_HEIGHT: u16 = 5;
+const FLOW_HISTORY: usize = 300;
+
+// chart colors of the GPUs after the first, which uses the `hardware.gpu`
+// color
+const EXTRA_GPU_COLORS: [Color; 4] = [
+    Color::LightGreen,
+    Color::LightYellow,
+    Color::LightBlue,
+    Color::LightRed,
+];
+
+/// Chart colors of the drives, in device name order
+const DRIVE_COLORS: [Color; 4] = [
+    Color::Gray,
+    Color::LightCyan,
+    Color::LightMagenta,
+    Color::White,
+];
+
+/// How long status bar messages stay up
+const FLASH_DURATION: Duration = Duration::from_secs(4);
+
+/// Minutes between vertical gridlines. The smallest one that keeps the
+/// chart under `MAX_GRID_LINES` lines is used
+const GRID_STEPS: [u64; 6] = [1, 5, 10, 15, 30, 60];
+const MAX_GRID_LINES: u64 = 15;
+
+/// How long the screen stays inverted for a `flash` alert signal
+const SIGNAL_FLASH_DURATION: Duration = Duration::from_millis(300);
+
+/// Longest the UI waits between checks of the control socket
+const CONTROL_POLL: Duration = Duration::from_millis(200);
+
+const DELTA_T_LABEL: &str = "Coolant \u{0394}T";
+
+// hottest temperature any sensor here could plausibly report. some
+// drivers report milli-degrees where hwmon expects degrees (or
+// micro-degrees where it expects milli), so anything above this is
+// scaled down by 1000 until it isn't
+const TEMP_PLAUSIBLE_MAX: f64 = 250.0;
+
+// the same for fan and pump speeds. the fastest server fans spin at
+// around 20,000 RPM, a driver reporting milli-RPM gives millions
+const RPM_PLAUSIBLE_MAX: f64 = 30_000.0;
+
+// lm-sensors chip prefixes for Aquacomputer devices, which can expose
+// "virtual" temperature sensors whose values are pushed from userspace
+const AQUACOMPUTER_CHIPS: [&str; 4] = [
+    "quadro-hid-",
+    "d5next-hid-",
+    "octo-hid-",
+    "highflownext-hid-",
+];
 
-const CPU_CTL_LABEL: &str = "7800 X3D CTL";
-const CPU_CCD_LABEL: &str = "7800 X3D CCD";
-const COOLANT_1_LABEL: &str = "Coolant 1";
-const COOLANT_2_LABEL: &str = "Coolant 2";
-const GPU_LABEL: &str = "RTX 4070";
+// Tctl at which the CPU starts pulling clocks back (Tjmax of the 7800
+// X3D). AMD doesn't expose a throttle flag, so hitting this is taken to
+// mean the CPU is throttling
+const CPU_THROTTLE_TEMP: f64 = 89.0;
 
 fn notify(message: &str, critical: bool) {
     let mut cmd = Command::new("notify-send");
@@ -62,6 +170,44 @@ fn notify(message: &str, critical: bool) {
     let _ = child.wait();
 }
 
+/// Ring the terminal bell. Written straight to stdout since ratatui has
+/// no way to, which is fine as it doesn't move the cursor
+fn bell() {
+    let mut stdout = std::io::stdout();
+    let _ = stdout.write_all(b"\x07");
+    let _ = stdout.flush();
+}
+
+/// Speak `message` with the user's TTS command, replacing `{message}`
+/// in its arguments. Runs on its own thread since speech takes a while
+/// and we don't want to block the UI
+fn speak(command: &[String], message: &str) {
+    let Some((program, args)) = command.split_first() else {
+        return;
+    };
+
+    let mut cmd = Command::new(program);
+    cmd.args(args.iter().map(|a| a.replace("{message}", message)));
+
+    cmd.stdin(Stdio::null());
+    cmd.stdout(Stdio::null());
+    cmd.stderr(Stdio::null());
+
+    match cmd.spawn() {
+        Ok(mut child) => {
+            std::thread::spawn(move || child.wait());
+        }
+        Err(e) => notify(&format!("failed to spawn TTS command: {e}"), false),
+    }
+}
+
+/// Send a critical notification, and read it out loud if a TTS command
+/// is configured
+fn alert(message: &str, tts: &[String]) {
+    notify(message, true);
+    speak(tts, message);
+}
+
 fn liquidctl(level: u8) {
     let mut path = PathBuf::new();
     path.push(
@@ -97,397 +243,3957 @@ fn liquidctl(level: u8) {
     }
 }
 
-fn check_cooler_level(vals: &LmSensorsValues) {
-    if (vals.coolant1 - vals.coolant2).abs() > 0.5 {
-        notify(
+/// Adjust the cooling level based on the current coolant temperature.
+/// When `silenced` is set the cooling level is still managed but no
+/// notifications are sent. In a dry run the change is only logged
+fn check_cooler_level(
+    vals: &LmSensorsValues,
+    fusion: Fusion,
+    silenced: bool,
+    tts: &[String],
+    dry_run: Option<&mut dryrun::Log>,
+) {
+    // a probe that can't be read isn't a disagreement, the fusion
+    // below leaves it out
+    let both = is_present(vals.coolant1) && is_present(vals.coolant2);
+    if !silenced && both && (vals.coolant1 - vals.coolant2).abs() > 0.5 {
+        alert(
             &format!(
                 "Coolant levels differ by more than 0.5C ({} vs {})",
                 vals.coolant1, vals.coolant2
             ),
-            true,
+            tts,
         );
     }
 
-    let c = vals.coolant1;
+    let c = fusion.apply(&[vals.coolant1, vals.coolant2]);
     if c < 35.0 {
         return;
     }
 
-    let coolant_level = read_to_string("/tmp/liquidctl_level")
-        .ok()
-        .and_then(|v| v.parse::<u8>().ok())
+    let coolant_level = dry_run
+        .as_ref()
+        .and_then(|log| log.cooling_level)
+        .or_else(|| {
+            read_to_string("/tmp/liquidctl_level")
+                .ok()
+                .and_then(|v| v.parse::<u8>().ok())
+        })
         .unwrap_or(0);
 
-    let changed_level = if c >= 40.0 && coolant_level < 5 {
-        liquidctl(5);
-        true
+    let level = if c >= 40.0 && coolant_level < 5 {
+        5
     } else if c >= 37.0 && coolant_level < 4 {
-        liquidctl(4);
-        true
+        4
     } else if coolant_level < 3 {
-        liquidctl(3);
-        true
+        3
     } else {
-        false
+        return;
+    };
+
+    let message = match dry_run {
+        Some(log) => {
+            log.record(&format!("set cooling level {level}"));
+            log.cooling_level = Some(level);
+            format!("Dry run: cooling level would have been set to {level}")
+        }
+        None => {
+            liquidctl(level);
+            "WARNING: Cooling level was set automatically".to_string()
+        }
     };
 
-    if changed_level {
-        notify("WARNING: Cooling level was set automatically", true);
+    if !silenced {
+        alert(&message, tts);
     }
 }
 
+/// Fix up a reading in the wrong unit, scaling it down by 1000 until
+/// it's at most `plausible_max`. A configured scale factor always wins
+/// over the guesswork
+fn normalize(val: f64, scale: Option<f64>, plausible_max: f64) -> f64 {
+    let mut val = match scale {
+        Some(scale) => val * scale,
+        None => val,
+    };
+
+    while scale.is_none() && val > plausible_max {
+        val /= 1000.0;
+    }
+
+    val
+}
+
+fn normalize_temp(val: f64, scale: Option<f64>) -> f64 {
+    normalize(val, scale, TEMP_PLAUSIBLE_MAX)
+}
+
+fn normalize_rpm(val: f64, scale: Option<f64>) -> f64 {
+    normalize(val, scale, RPM_PLAUSIBLE_MAX)
+}
+
 #[derive(Debug)]
 struct LmSensorsValues {
     tctl: f64,
     tccd1: f64,
     coolant1: f64,
     coolant2: f64,
+    ambient: f64,
+}
+
+impl LmSensorsValues {
+    fn normalized(
+        self,
+        scale: &BTreeMap<String, f64>,
+        hw: &config::HardwareConfig,
+    ) -> Self {
+        let n = |role: &config::RoleConfig, val| {
+            normalize_temp(val, scale.get(&role.label).copied())
+        };
+
+        Self {
+            tctl: n(&hw.cpu, self.tctl),
+            tccd1: n(&hw.ccd, self.tccd1),
+            coolant1: n(&hw.coolant1, self.coolant1),
+            coolant2: n(&hw.coolant2, self.coolant2),
+            ambient: n(&hw.ambient, self.ambient),
+        }
+    }
 }
 
+/// A temperature shown in the table below the fixed sensors: either a
+/// software-defined sensor on an Aquacomputer device, one matched by a
+/// `[[sensors]]` rule in the config, a drive or a `[[fused]]` sensor
 #[derive(Debug)]
-struct NvmlValues {
-    temp: f64,
-    watts: f64,
-    mem_used: u64,
-    mem_total: u64,
+struct VirtualSensor {
+    label: String,
+    value: f64,
+
+    /// Chart color and history, for `[[sensors]]` rules with `chart`
+    /// set and for drives
+    chart: Option<(Color, Series)>,
+
+    /// A drive temperature, charted only when the `drives` series is
+    /// shown
+    drive: bool,
 }
 
-fn get_nvml_values(nvml: &Nvml) -> NvmlValues {
-    let mut temp: f64 = 0.0;
-    let mut watts: f64 = 0.0;
-    let mut mem_used: u64 = 0;
-    let mut mem_total: u64 = 0;
+/// Min/max and chart-window samples of every temperature and chart
+/// series, kept for each view on its own so switching between, say, a
+/// gaming and an idle view doesn't mix their numbers. Only the view
+/// that's shown is updated
+#[derive(Default)]
+struct ViewStats {
+    /// By sensor or series label
+    min_max: BTreeMap<String, MinMax>,
+    /// Min/max of each loop's hottest point, by loop name
+    loops: BTreeMap<String, MinMax>,
+    /// Readings over the chart window for every sensor, by label
+    window_samples: BTreeMap<String, VecDeque<f64>>,
+}
 
-    if let Ok(device) = nvml.device_by_index(0) {
-        if let Ok(c) = device.temperature(TemperatureSensor::Gpu) {
-            temp = c as f64;
+/// Take `val` into the min/max of `label`, which starts at the first
+/// reading that isn't missing
+fn track(mm: &mut BTreeMap<String, MinMax>, label: &str, val: f64) {
+    match mm.get_mut(label) {
+        Some(mm) => mm.update(val),
+        None if is_present(val) => {
+            mm.insert(label.to_string(), MinMax::new(val));
         }
+        None => {}
+    }
+}
 
-        if let Ok(mw) = device.power_usage() {
-            watts = mw as f64 / 1000.0;
-        }
+/// A fan's latest speed and the range it's been in
+struct Fan {
+    label: String,
+    rpm: f64,
+    mm: MinMax,
+    max_rpm: u16,
+    /// Estimated level in dB(A), None while it's stopped
+    db: Option<f64>,
+}
 
-        if let Ok(mem_info) = device.memory_info() {
-            mem_used = mem_info.used / B_TO_MIB;
-            mem_total = mem_info.total / B_TO_MIB;
-        }
+/// A voltage rail's latest reading, the range it's been in and the
+/// range it should stay in
+struct Rail {
+    label: String,
+    volts: f64,
+    mm: MinMax,
+    min: Option<f64>,
+    max: Option<f64>,
+}
+
+impl Rail {
+    fn out_of_spec(&self) -> bool {
+        self.min.is_some_and(|m| self.volts < m)
+            || self.max.is_some_and(|m| self.volts > m)
     }
+}
+
+/// Receive and transmit history of a `[[network]]` interface, in MB/s
+struct Link {
+    rx_label: String,
+    tx_label: String,
+    rx: Series,
+    tx: Series,
+}
 
-    NvmlValues {
-        temp,
-        watts,
-        mem_used,
-        mem_total,
+/// Unit of a series that's scaled to the plot instead of drawn on the
+/// temperature axis
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Unit {
+    Watts,
+    MegabytesPerSec,
+}
+
+impl Unit {
+    /// `val` with the unit, a thousand and up in the next larger one so
+    /// the labels stay short
+    fn format(self, val: f64) -> String {
+        let (unit, big) = match self {
+            Unit::Watts => ("W", "kW"),
+            Unit::MegabytesPerSec => ("MB/s", "GB/s"),
+        };
+        if val.abs() >= 1000.0 {
+            format!("{:.1} {big}", val / 1000.0)
+        } else if val.abs() >= 100.0 {
+            format!("{val:.0} {unit}")
+        } else {
+            format!("{val:.1} {unit}")
+        }
     }
 }
 
-fn get_lmsensors_vals(sensors: &LMSensors) -> LmSensorsValues {
-    let mut tctl: f64 = 0.0;
-    let mut tccd1: f64 = 0.0;
-    let mut coolant1: f64 = 0.0;
-    let mut coolant2: f64 = 0.0;
+/// What's drawn at the top of the plot for a scaled series
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Scale {
+    top: f64,
+    unit: Unit,
+}
+
+/// A series the chart can plot
+struct ChartLine<'a> {
+    /// Whether the current view shows it
+    shown: bool,
+    label: &'a str,
+    series: &'a Series,
+    color: Color,
+    /// For rates (MB/s, watts). These don't fit the temperature axis,
+    /// so they're scaled to the plot's height instead and zero is a
+    /// reading rather than a missing one
+    scale: Option<Scale>,
+}
+
+/// A pump or fan header of an Aquacomputer device and what it draws
+struct Pump {
+    label: String,
+    rpm: f64,
+    watts: Option<f64>,
+    volts: Option<f64>,
+}
 
-    for chip in sensors.chip_iter(None) {
-        let cname = chip.name();
-        let cname = cname.as_deref().unwrap_or("");
-        if cname.starts_with("quadro-hid-") || cname == "k10temp-pci-00c3" {
-            for feature in chip.feature_iter() {
-                let name = feature.name().unwrap_or(Ok("")).unwrap_or("");
+/// How a temps table row is drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RowKind {
+    Sensor,
+    /// A `[[loops]]` summary
+    Summary,
+    /// A GPU that's throttling right now
+    Throttling,
+}
 
-                if let fname @ ("temp1" | "temp2" | "temp3") = name {
-                    for sub_feature in feature.sub_feature_iter() {
-                        let sname =
-                            sub_feature.name().unwrap_or(Ok("")).unwrap_or("");
+/// One GPU's latest readings and temperature history
+#[derive(Debug)]
+struct GpuState {
+    /// Device name as the driver reports it, with the index added when
+    /// there are several of the same card
+    name: String,
+    temp: Series,
+    watts: f64,
+    clock_mhz: u32,
+    mem_used: u64,
+    mem_reserved: u64,
+    mem_max: u64,
+    mem_busy: u32,
 
-                        if !sname.ends_with("_input") {
-                            continue;
-                        }
+    /// x positions of the samples where it was throttling, a strip on
+    /// the chart
+    throttled: Vec<f64>,
+    /// Every throttle reason seen this session, for the table badge
+    throttle_seen: Throttle,
 
-                        if let Ok(lm_sensors::Value::TemperatureInput(t)) =
-                            sub_feature.value()
-                        {
-                            if cname.starts_with("quadro-hid-") {
-                                match fname {
-                                    "temp1" => coolant1 = t,
-                                    "temp2" => coolant2 = t,
-                                    _ => {}
-                                }
-                            } else {
-                                match fname {
-                                    "temp1" => tctl = t,
-                                    "temp3" => tccd1 = t,
-                                    _ => {}
-                                }
-                            }
-                        }
-                    }
-                }
-            }
+    /// Latest readings as they came, for the GPU panel
+    last: NvmlValues,
+}
+
+impl GpuState {
+    fn new(name: String, size: usize, end: f64, vals: &NvmlValues) -> Self {
+        Self {
+            name,
+            temp: Series::ending(size, end, vals.temp),
+            watts: vals.watts,
+            clock_mhz: vals.clock_mhz,
+            mem_used: vals.mem_used,
+            mem_reserved: vals.mem_reserved,
+            mem_max: vals.mem_total,
+            mem_busy: vals.mem_busy,
+            throttled: Vec::new(),
+            throttle_seen: vals.throttle,
+            last: vals.clone(),
+        }
+    }
+
+    /// Returns the throttle reasons when one started that wasn't there
+    /// last tick
+    fn update(&mut self, x: f64, vals: &NvmlValues) -> Option<Throttle> {
+        let before = self.last.throttle;
+        self.temp.push(x, vals.temp);
+        self.watts = vals.watts;
+        self.clock_mhz = vals.clock_mhz;
+        self.mem_used = vals.mem_used;
+        self.mem_reserved = vals.mem_reserved;
+        self.mem_max = vals.mem_total;
+        self.mem_busy = vals.mem_busy;
+        self.last = vals.clone();
+
+        if !vals.throttle.any() {
+            return None;
         }
+
+        self.throttled.push(x);
+        self.throttle_seen = self.throttle_seen.union(vals.throttle);
+        (before.union(vals.throttle) != before).then_some(vals.throttle)
     }
+}
+
+/// Readings of the fixed sensors, found by the `[hardware]` roles. A
+/// role that matches nothing reads as missing
+fn role_values(
+    readings: &[Reading],
+    hw: &config::HardwareConfig,
+) -> LmSensorsValues {
+    let find = |role: &config::RoleConfig| {
+        readings
+            .iter()
+            .find(|r| {
+                r.is_temp() && role.chip.matches(&r.chip) && r.is(&role.feature)
+            })
+            .map(|r| r.value)
+    };
+
+    let coolant1 = find(&hw.coolant1);
+    // a Kraken read over USB is the coolant unless the role says
+    // otherwise
+    #[cfg(feature = "usb-hid")]
+    let coolant1 = coolant1.or_else(|| usbhid::coolant(readings));
+    // and so is a TEMPer for the ambient
+    let ambient = find(&hw.ambient);
+    #[cfg(feature = "usb-hid")]
+    let ambient = ambient.or_else(|| usbhid::ambient(readings));
 
     LmSensorsValues {
-        tctl,
-        tccd1,
-        coolant1,
-        coolant2,
+        // on boards where the thermal zones stand in, the CPU zone is
+        // the CPU unless the role says otherwise
+        tctl: find(&hw.cpu)
+            .or_else(|| thermal::cpu(readings))
+            .unwrap_or(0.0),
+        tccd1: find(&hw.ccd).unwrap_or(0.0),
+        coolant1: coolant1.unwrap_or(0.0),
+        coolant2: find(&hw.coolant2).unwrap_or(0.0),
+        ambient: ambient.unwrap_or(0.0),
     }
 }
 
-fn main() -> Result<()> {
-    let terminal = ratatui::init();
-    let app_result = App::new().run(terminal);
-    ratatui::restore();
-    app_result
+/// The Aquacomputer virtual sensors that have a value, as (label,
+/// value). Ones nothing has written to fail to read and don't show up
+fn virtual_sensors(readings: &[Reading]) -> Vec<(String, f64)> {
+    readings
+        .iter()
+        .filter(|r| {
+            r.is_temp()
+                && AQUACOMPUTER_CHIPS.iter().any(|p| r.chip.starts_with(p))
+        })
+        .filter_map(|r| {
+            let label =
+                r.label.as_ref().filter(|l| l.starts_with("Virtual"))?;
+            Some((label.clone(), r.value))
+        })
+        .collect()
 }
 
-struct App {
-    sensors: LMSensors,
-    nvml: Nvml,
-    tctl: Vec<(f64, f64)>,
-    tctl_mm: (f64, f64),
-    tccd1: f64,
-    tccd1_mm: (f64, f64),
-    coolant1: Vec<(f64, f64)>,
-    coolant1_mm: (f64, f64),
-    coolant2: f64,
-    coolant2_mm: (f64, f64),
-    gpu_temp: Vec<(f64, f64)>,
-    gpu_temp_mm: (f64, f64),
-    gpu_w: f64,
-    gpu_mem_used: u64,
-    gpu_mem_max: u64,
-    window: [f64; 2],
+/// GPU temperature and power from `temp1` and `power1` of the first chip
+/// matching `chip`, if there is one
+fn hwmon_gpu_values(
+    readings: &[Reading],
+    chip: &glob::Pattern,
+) -> Option<NvmlValues> {
+    let name = &readings.iter().find(|r| chip.matches(&r.chip))?.chip;
+    let value = |feature: &str| {
+        readings
+            .iter()
+            .find(|r| r.chip == *name && r.feature == feature)
+            .map(|r| r.value)
+            .unwrap_or(0.0)
+    };
+
+    Some(NvmlValues {
+        temp: value("temp1"),
+        watts: value("power1"),
+        ..Default::default()
+    })
 }
 
-impl App {
-    fn new() -> Self {
-        let sensors: LMSensors = Initializer::default()
-            .initialize()
-            .expect("Failed to init lm-sensors");
+/// Whether any role or `[[sensors]]` rule asks for more than one read
+/// per tick
+fn wants_median(config: &Config) -> bool {
+    config::ROLES
+        .iter()
+        .filter_map(|n| config.hardware.role(n))
+        .map(|role| role.samples)
+        .chain(config.sensors.iter().map(|rule| rule.samples))
+        .any(|s| s > 1)
+}
+
+/// How many reads a reading gets per tick: the most any role or
+/// `[[sensors]]` rule matching it asks for
+fn median_samples(config: &Config) -> impl Fn(&Reading) -> usize + 'static {
+    let hw = config.hardware.clone();
+    let rules = config.sensors.clone();
+
+    move |r| {
+        let roles = config::ROLES
+            .iter()
+            .filter_map(|n| hw.role(n))
+            .filter(|role| role.chip.matches(&r.chip) && r.is(&role.feature))
+            .map(|role| role.samples);
+        let rules = rules
+            .iter()
+            .filter(|rule| rule.select.matches(r))
+            .map(|rule| rule.samples);
+
+        roles.chain(rules).max().unwrap_or(1)
+    }
+}
+
+/// Total power of the CPU packages RAPL reports, in watts
+fn cpu_watts(readings: &[Reading]) -> f64 {
+    readings
+        .iter()
+        .filter(|r| r.chip == "rapl")
+        .map(|r| r.value)
+        .sum()
+}
+
+/// Total charge or discharge rate of the batteries, in watts
+fn battery_watts(readings: &[Reading]) -> f64 {
+    readings
+        .iter()
+        .filter(|r| r.chip == "battery" && r.feature.starts_with("power"))
+        .map(|r| r.value)
+        .sum()
+}
 
-        let nvml = Nvml::init().expect("Failed to initialize NVML");
+/// Temperatures of the drives, as (label, value, chart color)
+fn drive_temps(readings: &[Reading]) -> Vec<(String, f64, Option<Color>)> {
+    readings
+        .iter()
+        .filter(|r| r.chip == "drive")
+        .enumerate()
+        .map(|(i, r)| {
+            let label = r.label.clone().unwrap_or_else(|| r.feature.clone());
+            let color = DRIVE_COLORS[i % DRIVE_COLORS.len()];
+            (label, r.value, Some(color))
+        })
+        .collect()
+}
+
+/// Which sensor with a `crit` alert threshold is closest to it, with its
+/// reading as a percentage of the threshold
+fn hottest(
+    readings: &[(String, f64)],
+    rules: &[config::AlertConfig],
+) -> Option<(String, f64)> {
+    rules
+        .iter()
+        .filter_map(|rule| {
+            let crit = rule.crit.filter(|c| *c > 0.0)?;
+            let (label, value) = readings
+                .iter()
+                .find(|(l, v)| *l == rule.sensor && is_present(*v))?;
+            Some((label.clone(), value / crit * 100.0))
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+}
+
+/// Flow rate of the first flow sensor in l/h, if there is one
+fn flow_rate(readings: &[Reading]) -> Option<f64> {
+    // the driver reports dL/h
+    readings
+        .iter()
+        .find(|r| r.is_flow())
+        .map(|r| r.value / 10.0)
+}
+
+/// Pumps and fan headers of Aquacomputer devices. The driver labels a
+/// header's speed, power and voltage `<name> speed`, `<name> power` and
+/// `<name> voltage`, which is how they're matched up. Speeds are
+/// normalized like the fans', with `scale` factors by header name
+fn pump_metrics(
+    readings: &[Reading],
+    scale: &BTreeMap<String, f64>,
+) -> Vec<Pump> {
+    let aqua =
+        |r: &&Reading| AQUACOMPUTER_CHIPS.iter().any(|p| r.chip.starts_with(p));
+
+    readings
+        .iter()
+        .filter(aqua)
+        .filter(|r| r.is_fan())
+        .filter_map(|fan| {
+            let name = fan.label.as_deref()?.strip_suffix(" speed")?;
+            let find = |what: &str| {
+                let label = format!("{name} {what}");
+                readings
+                    .iter()
+                    .find(|r| {
+                        r.chip == fan.chip && r.label.as_deref() == Some(&label)
+                    })
+                    .map(|r| r.value)
+            };
+
+            Some(Pump {
+                label: name.to_string(),
+                rpm: normalize_rpm(fan.value, scale.get(name).copied()),
+                watts: find("power"),
+                volts: find("voltage"),
+            })
+        })
+        .collect()
+}
+
+/// Speeds of the fans picked by `[[fans]]` rules, as (label, RPM, gauge
+/// maximum, noise). Without rules every fan is shown, by the driver's
+/// label or else by its chip and feature name. Speeds are normalized
+/// like temperatures, with `scale` factors by label
+fn fan_speeds(
+    readings: &[Reading],
+    rules: &[config::FanConfig],
+    scale: &BTreeMap<String, f64>,
+) -> Vec<(String, f64, u16, Option<f64>)> {
+    let fans = readings.iter().filter(|r| r.is_fan());
+    let rpm = |label: &str, val| normalize_rpm(val, scale.get(label).copied());
+
+    if rules.is_empty() {
+        return fans
+            .map(|r| {
+                let label = r.label.clone().unwrap_or_else(|| {
+                    let prefix = r.chip.split('-').next().unwrap_or(&r.chip);
+                    format!("{prefix} {}", r.feature)
+                });
+                let rpm = rpm(&label, r.value);
+                let db = noise::level(rpm, &[]);
+                (label, rpm, config::DEFAULT_FAN_MAX_RPM, db)
+            })
+            .collect();
+    }
 
-        let mut tctl = Vec::with_capacity(WINDOW_SIZE as usize);
-        let mut coolant1 = Vec::with_capacity(WINDOW_SIZE as usize);
-        let mut gpu = Vec::with_capacity(WINDOW_SIZE as usize);
+    let mut ret = Vec::new();
 
-        for i in 0..(WINDOW_SIZE - 1) {
-            tctl.push((i as f64, 0.0));
-            coolant1.push((i as f64, 0.0));
-            gpu.push((i as f64, 0.0));
+    for rule in rules {
+        let matched: Vec<&Reading> =
+            fans.clone().filter(|f| rule.select.matches(f)).collect();
+
+        for f in &matched {
+            let label = if matched.len() == 1 {
+                rule.label.clone()
+            } else {
+                let own = f.label.as_deref().unwrap_or(&f.feature);
+                format!("{} {own}", rule.label)
+            };
+            let rpm = rpm(&label, f.value);
+            let db = noise::level(rpm, &rule.noise);
+            ret.push((label, rpm, rule.max_rpm, db));
         }
+    }
 
-        let values = get_lmsensors_vals(&sensors);
-        tctl.push(((WINDOW_SIZE - 1) as f64, values.tctl));
-        coolant1.push(((WINDOW_SIZE - 1) as f64, values.coolant1));
+    ret
+}
 
-        let nvml_values = get_nvml_values(&nvml);
-        let gpu_temp = nvml_values.temp;
-        gpu.push(((WINDOW_SIZE - 1) as f64, gpu_temp));
+/// The reading of each `[[leaks]]` rule, from the first sensor it
+/// matches, in rule order
+fn leak_values(
+    readings: &[Reading],
+    rules: &[config::LeakConfig],
+) -> Vec<Option<f64>> {
+    rules
+        .iter()
+        .map(|rule| {
+            readings
+                .iter()
+                .find(|r| rule.select.matches(r))
+                .map(|r| r.value)
+        })
+        .collect()
+}
 
-        Self {
-            sensors,
-            nvml,
-            tctl,
-            tctl_mm: (values.tctl, values.tctl),
-            tccd1: values.tccd1,
-            tccd1_mm: (values.tccd1, values.tccd1),
-            coolant1,
-            coolant1_mm: (values.coolant1, values.coolant1),
-            coolant2: values.coolant2,
-            coolant2_mm: (values.coolant2, values.coolant2),
-            gpu_temp: gpu,
-            gpu_temp_mm: (gpu_temp, gpu_temp),
-            gpu_w: nvml_values.watts,
-            gpu_mem_used: nvml_values.mem_used,
-            gpu_mem_max: nvml_values.mem_total,
-            window: [0.0, WINDOW_SIZE as f64],
-        }
-    }
-
-    fn run(mut self, mut terminal: DefaultTerminal) -> Result<()> {
-        let tick_rate = Duration::from_millis(INTERVAL);
-        self.on_tick();
-        let mut last_tick = Instant::now();
+/// Voltages picked by `[[voltages]]` rules, scaled and with their
+/// ranges. Without rules every voltage is shown, by the driver's label
+/// or else by its chip and feature name, with no range
+fn rail_voltages(
+    readings: &[Reading],
+    rules: &[config::VoltageConfig],
+) -> Vec<Rail> {
+    let voltages = readings.iter().filter(|r| r.is_voltage());
+    let rail = |label: String, volts: f64, min, max| Rail {
+        label,
+        volts,
+        mm: MinMax::new(volts),
+        min,
+        max,
+    };
 
-        loop {
-            terminal.draw(|frame| self.draw(frame))?;
+    if rules.is_empty() {
+        return voltages
+            .map(|r| {
+                let label = r.label.clone().unwrap_or_else(|| {
+                    let prefix = r.chip.split('-').next().unwrap_or(&r.chip);
+                    format!("{prefix} {}", r.feature)
+                });
+                rail(label, r.value, None, None)
+            })
+            .collect();
+    }
 
-            let timeout = tick_rate.saturating_sub(last_tick.elapsed());
-            if event::poll(timeout)? {
-                if let Event::Key(key) = event::read()? {
-                    if key.code == KeyCode::Char('q') {
-                        return Ok(());
-                    }
-                }
-            }
-            if last_tick.elapsed() >= tick_rate {
-                self.on_tick();
-                last_tick = Instant::now();
-            }
+    let mut ret = Vec::new();
+
+    for rule in rules {
+        let matched: Vec<&Reading> = voltages
+            .clone()
+            .filter(|v| rule.select.matches(v))
+            .collect();
+
+        for v in &matched {
+            let label = if matched.len() == 1 {
+                rule.label.clone()
+            } else {
+                let own = v.label.as_deref().unwrap_or(&v.feature);
+                format!("{} {own}", rule.label)
+            };
+            ret.push(rail(label, v.value * rule.scale, rule.min, rule.max));
         }
     }
 
-    fn on_tick(&mut self) {
-        let vals = get_lmsensors_vals(&self.sensors);
-        let nvml_vals = get_nvml_values(&self.nvml);
+    ret
+}
 
-        check_cooler_level(&vals);
+/// Readings of the `[[commands]]`, as (label, value, chart color)
+fn command_readings(
+    readings: &[Reading],
+    commands: &[config::CommandConfig],
+) -> Vec<(String, f64, Option<Color>)> {
+    readings
+        .iter()
+        .filter(|r| r.chip == exec::CHIP)
+        .filter_map(|r| {
+            let label = r.label.clone()?;
+            let chart = commands
+                .iter()
+                .find(|c| c.label == label)
+                .and_then(|c| c.chart);
+            Some((label, r.value, chart))
+        })
+        .collect()
+}
 
-        self.window[0] += 1.0;
-        self.window[1] += 1.0;
+/// Readings of the sensors picked by `[[sensors]]` rules, as
+/// (label, value, chart color)
+fn configured(
+    readings: &[Reading],
+    rules: &[config::SensorConfig],
+) -> Vec<(String, f64, Option<Color>)> {
+    if rules.is_empty() {
+        return Vec::new();
+    }
 
-        let w = self.window[1];
+    let mut ret = Vec::new();
 
-        self.tctl.remove(0);
-        self.coolant1.remove(0);
-        self.gpu_temp.remove(0);
+    for rule in rules {
+        let matched: Vec<&Reading> = readings
+            .iter()
+            .filter(|t| t.is_temp() && rule.select.matches(t))
+            .collect();
 
-        self.tctl.push((w, vals.tctl));
-        self.coolant1.push((w, vals.coolant1));
-        self.gpu_temp.push((w, nvml_vals.temp));
+        for t in &matched {
+            let own = t.label.as_deref().unwrap_or(&t.feature);
+            let label = match &rule.label {
+                Some(l) if matched.len() == 1 => l.clone(),
+                Some(l) => format!("{l} {own}"),
+                None => own.to_string(),
+            };
+            ret.push((label, t.value, rule.chart));
+        }
+    }
 
-        self.tccd1 = vals.tccd1;
-        self.coolant2 = vals.coolant2;
-        self.gpu_w = nvml_vals.watts;
-        self.gpu_mem_used = nvml_vals.mem_used;
-        self.gpu_mem_max = nvml_vals.mem_total;
+    ret
+}
+
+/// Runtime options parsed from the command line. Apart from `inline`
+/// and `heartbeat` these override the matching keys in the config file
+#[derive(Debug)]
+struct Options {
+    /// Draw inline below the cursor instead of taking over the alternate
+    /// screen, appending a one-line summary to the scrollback every tick
+    inline: bool,
+    vram_reserved: bool,
+    rate: Option<f64>,
+    silence_minutes: Option<u64>,
+    interval: Option<u64>,
+    window: Option<u64>,
+
+    /// Don't read the GPU at all, for machines without one or where
+    /// probing it is slow
+    no_gpu: bool,
+    dry_run: bool,
+    summary: bool,
+
+    /// File or FIFO to write a one-line summary to every tick
+    heartbeat: Option<PathBuf>,
+}
+
+impl Options {
+    fn from_args(mut args: pico_args::Arguments) -> Result<Self> {
+        let opts = Self {
+            inline: args.contains("--inline"),
+            vram_reserved: args.contains("--vram-reserved"),
+            rate: args.opt_value_from_str("--rate")?,
+            silence_minutes: args.opt_value_from_str("--silence-minutes")?,
+            interval: args.opt_value_from_str("--interval")?,
+            window: args.opt_value_from_str("--window")?,
+            no_gpu: args.contains("--no-gpu"),
+            dry_run: args.contains("--dry-run"),
+            summary: args.contains("--summary"),
+            heartbeat: args.opt_value_from_str("--heartbeat")?,
+        };
 
-        if vals.tctl < self.tctl_mm.0 {
-            self.tctl_mm.0 = vals.tctl
+        if opts.interval == Some(0) {
+            anyhow::bail!("--interval must be greater than zero");
         }
-        if vals.tctl > self.tctl_mm.1 {
-            self.tctl_mm.1 = vals.tctl
+        if opts.window == Some(0) {
+            anyhow::bail!("--window must be greater than zero");
         }
-        if vals.tccd1 < self.tccd1_mm.0 {
-            self.tccd1_mm.0 = vals.tccd1
+
+        let rest = args.finish();
+        if !rest.is_empty() {
+            anyhow::bail!("unexpected arguments: {rest:?}");
         }
-        if vals.tccd1 > self.tccd1_mm.1 {
-            self.tccd1_mm.1 = vals.tccd1
+
+        Ok(opts)
+    }
+
+    fn apply(&self, config: &mut Config) {
+        let mut set = |key: &str| {
+            config
+                .origins
+                .insert(key.to_string(), config::Origin::CommandLine);
+        };
+        if self.vram_reserved {
+            config.vram_reserved = true;
+            set("vram_reserved");
         }
-        if vals.coolant1 < self.coolant1_mm.0 {
-            self.coolant1_mm.0 = vals.coolant1
+        if self.dry_run {
+            config.dry_run = true;
+            set("dry_run");
         }
-        if vals.coolant1 > self.coolant1_mm.1 {
-            self.coolant1_mm.1 = vals.coolant1
+        if self.summary {
+            config.exit_summary = true;
+            set("exit_summary");
         }
-        if vals.coolant2 < self.coolant2_mm.0 {
-            self.coolant2_mm.0 = vals.coolant2
+        if self.rate.is_some() {
+            config.rate = self.rate;
+            set("rate");
         }
-        if vals.coolant2 > self.coolant2_mm.1 {
-            self.coolant2_mm.1 = vals.coolant2
+        if let Some(m) = self.silence_minutes {
+            config.silence_minutes = m;
+            set("silence_minutes");
         }
-        if nvml_vals.temp < self.gpu_temp_mm.0 {
-            self.gpu_temp_mm.0 = nvml_vals.temp
+        if let Some(ms) = self.interval {
+            config.interval = ms;
+            set("interval");
         }
-        if nvml_vals.temp > self.gpu_temp_mm.1 {
-            self.gpu_temp_mm.1 = nvml_vals.temp
+        if let Some(secs) = self.window {
+            config.window = secs;
+            set("window");
         }
     }
+}
 
-    fn draw(&self, frame: &mut Frame) {
-        let [top, bottom] =
-            Layout::vertical([Constraint::Fill(1), Constraint::Length(9)])
-                .areas(frame.area());
+/// `$XDG_STATE_HOME/sensors-mon`, or `~/.local/state/sensors-mon`
+fn state_dir() -> PathBuf {
+    let mut path = match std::env::var_os("XDG_STATE_HOME") {
+        Some(dir) => PathBuf::from(dir),
+        None => {
+            let mut home =
+                PathBuf::from(std::env::var_os("HOME").unwrap_or_default());
+            home.push(".local/state");
+            home
+        }
+    };
 
-        let [bottom_left, bottom_right] =
-            Layout::horizontal([Constraint::Fill(1), Constraint::Length(34)])
-                .areas(bottom);
+    path.push("sensors-mon");
+    path
+}
 
-        let [bottom_left_1, bottom_left_2, bottom_left_3, bottom_left_4] =
-            Layout::vertical([
-                Constraint::Length(2),
-                Constraint::Length(2),
-                Constraint::Length(2),
-                Constraint::Length(3),
-            ])
-            .areas(bottom_left);
+/// Format a number of seconds the way the chart axis labels do, e.g.
+/// `2m30s`
+fn fmt_duration(secs: u64) -> String {
+    match (secs / 60, secs % 60) {
+        (0, s) => format!("{s}s"),
+        (m, 0) => format!("{m}m"),
+        (m, s) => format!("{m}m{s}s"),
+    }
+}
 
-        self.render_temps_chart(frame, top);
-        self.render_temps_table(frame, bottom_right);
+/// `sensors-mon compare <FROM..TO> <FROM..TO> [--history <path>]`
+fn compare(mut args: pico_args::Arguments, config: &Config) -> Result<()> {
+    let path: Option<PathBuf> = args.opt_value_from_str("--history")?;
+    let path = path.or_else(|| config.history.clone()).ok_or_else(|| {
+        anyhow::anyhow!(
+            "no history file, set `history` in the config or pass --history"
+        )
+    })?;
 
-        let c1 = self.coolant1.last().unwrap().1;
-        let b1 = Block::default()
-            .borders(Borders::LEFT | Borders::RIGHT)
-            .padding(Padding::new(0, 0, 1, 0));
+    let a = history::parse_range(&args.free_from_str::<String>()?)?;
+    let b = history::parse_range(&args.free_from_str::<String>()?)?;
 
-        let c2 = self.coolant2;
-        let b2 = Block::default()
-            .borders(Borders::TOP | Borders::LEFT | Borders::RIGHT)
-            .title(COOLANT_2_LABEL);
+    let rest = args.finish();
+    if !rest.is_empty() {
+        anyhow::bail!("unexpected arguments: {rest:?}");
+    }
 
-        self.render_coolant_gauge(c1, b1, frame, bottom_left_1);
-        self.render_coolant_gauge(c2, b2, frame, bottom_left_2);
+    print!("{}", history::compare(&path, a, b)?);
+    Ok(())
+}
 
-        self.render_gpu_watts_gauge(self.gpu_w, frame, bottom_left_3);
-        self.render_gpu_mem_gauge(
-            self.gpu_mem_used,
-            self.gpu_mem_max,
-            frame,
-            bottom_left_4,
-        );
+/// Print a histogram of one sensor's values in the history file
+fn histogram(mut args: pico_args::Arguments, config: &Config) -> Result<()> {
+    let path: Option<PathBuf> = args.opt_value_from_str("--history")?;
+    let path = path.or_else(|| config.history.clone()).ok_or_else(|| {
+        anyhow::anyhow!(
+            "no history file, set `history` in the config or pass --history"
+        )
+    })?;
+    let bins: usize = args.opt_value_from_str("--bins")?.unwrap_or(20);
+    if bins == 0 {
+        anyhow::bail!("--bins must be greater than zero");
+    }
+
+    let sensor: String = args.free_from_str()?;
+    let range = args
+        .opt_free_from_str::<String>()?
+        .map(|r| history::parse_range(&r))
+        .transpose()?;
 
-        // enclosing border for bottom left gauges
-        let b = Block::bordered().title(COOLANT_1_LABEL);
-        frame.render_widget(b, bottom_left);
+    let rest = args.finish();
+    if !rest.is_empty() {
+        anyhow::bail!("unexpected arguments: {rest:?}");
     }
 
-    fn render_coolant_gauge(
-        &self,
-        val: f64,
-        block: Block,
-        frame: &mut Frame,
-        area: Rect,
-    ) {
-        let label = Span::styled(
-            format!("{:.1}C", val),
-            Style::new().bold().fg(Color::Gray).bg(Color::Reset),
-        );
+    print!("{}", history::histogram(&path, &sensor, range, bins)?);
+    Ok(())
+}
 
-        let color = if val < 34.0 {
-            Color::Green
-        } else if val < 38.0 {
-            Color::Yellow
-        } else {
-            Color::Red
-        };
+/// Print the temperatures a sensor settled at for each speed a fan held
+/// steady at, with suggestions for the fan curve
+fn fan_curve(mut args: pico_args::Arguments, config: &Config) -> Result<()> {
+    let path: Option<PathBuf> = args.opt_value_from_str("--history")?;
+    let path = path.or_else(|| config.history.clone()).ok_or_else(|| {
+        anyhow::anyhow!(
+            "no history file, set `history` in the config or pass --history"
+        )
+    })?;
+    let step: f64 = args.opt_value_from_str("--step")?.unwrap_or(100.0);
+    if step.is_nan() || step <= 0.0 {
+        anyhow::bail!("--step must be greater than zero");
+    }
 
-        let g1 = Gauge::default()
-            .block(block)
-            .gauge_style(color)
-            .ratio(((val - 25.0) / 20.0).clamp(0.0, 1.0))
-            .label(label);
+    let fan: String = args.free_from_str()?;
+    let sensor: String = args.free_from_str()?;
+    let range = args
+        .opt_free_from_str::<String>()?
+        .map(|r| history::parse_range(&r))
+        .transpose()?;
 
-        frame.render_widget(g1, area);
+    let rest = args.finish();
+    if !rest.is_empty() {
+        anyhow::bail!("unexpected arguments: {rest:?}");
     }
 
-    fn render_gpu_watts_gauge(&self, val: f64, frame: &mut Frame, area: Rect) {
-        let label = Span::styled(
-            format!("{:.1}W / 200W", val),
-            Style::new().bold().fg(Color::Gray).bg(Color::Reset),
-        );
+    print!("{}", history::fan_curve(&path, &fan, &sensor, range, step)?);
+    Ok(())
+}
 
-        let g1 = Gauge::default()
-            .block(
-                Block::default()
-                    .borders(Borders::TOP | Borders::LEFT | Borders::RIGHT)
-                    .title("RTX 4070 Power"),
-            )
-            .gauge_style(Color::Blue)
-            .ratio((val / 200.0).clamp(0.0, 1.0))
-            .label(label);
+/// `sensors-mon diagnose [--output <dir>] [--seconds <n>]`
+fn diagnose(mut args: pico_args::Arguments, config: &Config) -> Result<()> {
+    let dir: Option<PathBuf> = args.opt_value_from_str("--output")?;
+    let seconds: u64 = args.opt_value_from_str("--seconds")?.unwrap_or(10);
 
-        frame.render_widget(g1, area);
+    let rest = args.finish();
+    if !rest.is_empty() {
+        anyhow::bail!("unexpected arguments: {rest:?}");
     }
 
-    fn render_gpu_mem_gauge(
-        &self,
-        used: u64,
-        total: u64,
-        frame: &mut Frame,
-        area: Rect,
-    ) {
+    println!("recording {seconds}s of readings...");
+    let all = glob::Pattern::new("*")?;
+    let path = diagnose::run(
+        config,
+        &dir.unwrap_or_else(|| PathBuf::from(".")),
+        seconds,
+        |out| inventory(&all, &all, out),
+    )?;
+    println!("wrote {}, attach it to the issue", path.display());
+    Ok(())
+}
+
+/// Rewrite the user config in the current format, keeping a backup, and
+/// warn about keys that didn't make it across
+fn migrate_config(path: &Path) -> Result<()> {
+    if !path.exists() {
+        anyhow::bail!("no config at {}", path.display());
+    }
+
+    let migration = Config::migrate(path)?;
+    for key in &migration.dropped {
+        eprintln!("warning: dropped `{key}`, it's not a setting (any more)");
+    }
+    println!(
+        "rewrote {}, the original is in {}",
+        path.display(),
+        migration.backup.display()
+    );
+    Ok(())
+}
+
+/// `sensors-mon import <fancontrol|liquidctl> [<file>] [--write]`. Print
+/// the sensor and fan rules read from another tool's config, or append
+/// the ones not in the config yet to the user file
+fn import(
+    mut args: pico_args::Arguments,
+    config: &Config,
+    config_path: &Path,
+) -> Result<()> {
+    let write = args.contains("--write");
+    let tool: String = args.free_from_str()?;
+    let file: Option<PathBuf> = args.opt_free_from_str()?;
+
+    let rest = args.finish();
+    if !rest.is_empty() {
+        anyhow::bail!("unexpected arguments: {rest:?}");
+    }
+
+    let path = match (tool.as_str(), file) {
+        (_, Some(file)) => file,
+        ("fancontrol", None) => PathBuf::from(import::FANCONTROL_PATH),
+        ("liquidctl", None) => anyhow::bail!(
+            "liquidctl has no config file, pass the script or systemd unit \
+             that runs it"
+        ),
+        _ => anyhow::bail!(
+            "unknown tool `{tool}`, expected fancontrol or liquidctl"
+        ),
+    };
+    let text = read_to_string(&path)
+        .with_context(|| format!("reading {}", path.display()))?;
+
+    let mut imported = match tool.as_str() {
+        "fancontrol" => import::fancontrol(&text)?,
+        "liquidctl" => import::liquidctl(&text),
+        _ => anyhow::bail!(
+            "unknown tool `{tool}`, expected fancontrol or liquidctl"
+        ),
+    };
+    for what in &imported.skipped {
+        eprintln!("warning: skipped {what}");
+    }
+
+    let source = path.display().to_string();
+    if !write {
+        print!("{}", imported.to_toml(&source));
+        return Ok(());
+    }
+
+    imported.without(&config.sensors, &config.fans);
+    if imported.is_empty() {
+        println!("nothing to add, the config has every rule already");
+        return Ok(());
+    }
+
+    let old = match read_to_string(config_path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => {
+            return Err(e)
+                .with_context(|| format!("reading {}", config_path.display()))
+        }
+    };
+    let mut new = old;
+    if !new.is_empty() {
+        if !new.ends_with('\n') {
+            new.push('\n');
+        }
+        new.push('\n');
+    }
+    new.push_str(&imported.to_toml(&source));
+
+    // an inline `sensors = [...]` array can't take more tables, check
+    // the result parses before writing it over the user's file
+    new.parse::<toml_edit::DocumentMut>().with_context(|| {
+        format!("the rules don't fit into {}", config_path.display())
+    })?;
+
+    if let Some(dir) = config_path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(config_path, new)
+        .with_context(|| format!("writing {}", config_path.display()))?;
+    println!(
+        "added {} sensor and {} fan rules to {}",
+        imported.sensors.len(),
+        imported.fans.len(),
+        config_path.display()
+    );
+    Ok(())
+}
+
+/// Print every reading of every chip, optionally filtered by chip and
+/// feature glob patterns, followed by the NVML devices. The chip and
+/// feature (or label) columns are what `[[sensors]]` rules and
+/// `[hardware]` roles match on
+fn list(mut args: pico_args::Arguments) -> Result<()> {
+    let mut pattern = |arg: &'static str| -> Result<glob::Pattern> {
+        let p: Option<String> = args.opt_value_from_str(arg)?;
+        Ok(glob::Pattern::new(p.as_deref().unwrap_or("*"))?)
+    };
+    let chip = pattern("--chip")?;
+    let feature = pattern("--feature")?;
+
+    let rest = args.finish();
+    if !rest.is_empty() {
+        anyhow::bail!("unexpected arguments: {rest:?}");
+    }
+
+    inventory(&chip, &feature, &mut std::io::stdout().lock())
+}
+
+/// What `list` prints: every sub-feature of every chip matching the
+/// `chip` and `feature` patterns, then the thermal zones, IPMI sensors,
+/// USB devices, network interfaces and GPUs
+fn inventory(
+    chip: &glob::Pattern,
+    feature: &glob::Pattern,
+    out: &mut impl Write,
+) -> Result<()> {
+    let sensors = Sensors::init()?;
+    writeln!(
+        out,
+        "{:<32} {:<16} {:<8} {:<20} {:<20} {:>10}",
+        "CHIP", "SERIAL", "FEATURE", "LABEL", "SUB-FEATURE", "VALUE"
+    )?;
+    for s in sensors.sub_features() {
+        if !chip.matches(&s.chip) || !feature.matches(&s.feature) {
+            continue;
+        }
+
+        writeln!(
+            out,
+            "{:<32} {:<16} {:<8} {:<20} {:<20} {:>10.2}",
+            s.chip,
+            s.serial.as_deref().unwrap_or("-"),
+            s.feature,
+            s.label.as_deref().unwrap_or("-"),
+            s.sub_feature,
+            s.value
+        )?;
+    }
+
+    if !sensors.has_temps() {
+        if let Ok(zones) = thermal::init() {
+            writeln!(out)?;
+            writeln!(out, "{:<8} {:<20} {:>10}", "THERMAL", "LABEL", "VALUE")?;
+            for line in zones.describe() {
+                writeln!(out, "{line}")?;
+            }
+        }
+    }
+
+    if let Some(readings) = ipmi::readings().ok().filter(|r| !r.is_empty()) {
+        writeln!(out)?;
+        writeln!(out, "{:<8} {:<20} {:>10}", "IPMI", "LABEL", "VALUE")?;
+        for r in readings {
+            writeln!(
+                out,
+                "{:<8} {:<20} {:>10.2}",
+                r.feature,
+                r.label.as_deref().unwrap_or("-"),
+                r.value
+            )?;
+        }
+    }
+
+    #[cfg(feature = "usb-hid")]
+    {
+        let devices = usbhid::describe();
+        if !devices.is_empty() {
+            writeln!(out)?;
+            writeln!(out, "{:<32} DEVICE", "USB HID")?;
+            for line in devices {
+                writeln!(out, "{line}")?;
+            }
+        }
+    }
+
+    let interfaces = net::interfaces();
+    if !interfaces.is_empty() {
+        writeln!(out)?;
+        writeln!(out, "NETWORK")?;
+        for name in interfaces {
+            writeln!(out, "{name}")?;
+        }
+    }
+
+    #[cfg(feature = "nvml")]
+    if let Ok(gpu) = nvml::init() {
+        writeln!(out)?;
+        writeln!(
+            out,
+            "{:<4} {:<40} {:>6} {:>8}",
+            "NVML", "NAME", "TEMP", "POWER"
+        )?;
+        for line in gpu.describe() {
+            writeln!(out, "{line}")?;
+        }
+    }
+
+    if let Ok(mut gpu) = intel::init() {
+        writeln!(out)?;
+        writeln!(
+            out,
+            "{:<45} {:>6} {:>8} {:>9}",
+            "INTEL", "TEMP", "POWER", "CLOCK"
+        )?;
+        writeln!(out, "{}", gpu.describe())?;
+    }
+
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    let mut args = pico_args::Arguments::from_env();
+
+    // --config replaces the user file, the system-wide one still applies
+    let config_path: Option<PathBuf> = args.opt_value_from_str("--config")?;
+    let config_path = config_path.unwrap_or_else(config::default_path);
+    let mut config =
+        Config::load(&[Path::new(config::SYSTEM_PATH), &config_path])?;
+
+    if args.contains("--list-sensors") {
+        return list(args);
+    }
+
+    match args.subcommand()?.as_deref() {
+        Some("compare") => return compare(args, &config),
+        Some("histogram") => return histogram(args, &config),
+        Some("fan-curve") => return fan_curve(args, &config),
+        Some("list") => return list(args),
+        Some("diagnose") => return diagnose(args, &config),
+        Some("migrate-config") => return migrate_config(&config_path),
+        Some("import") => return import(args, &config, &config_path),
+        Some(cmd) => anyhow::bail!("unknown command `{cmd}`"),
+        None => {}
+    }
+
+    let opts = Options::from_args(args)?;
+    opts.apply(&mut config);
+
+    let history = config
+        .history
+        .as_deref()
+        .map(history::Recorder::open)
+        .transpose()?;
+    let dry_run = config
+        .dry_run
+        .then(|| dryrun::Log::open(&dryrun::default_path()))
+        .transpose()?;
+    let heartbeat = opts.heartbeat.clone().map(heartbeat::Heartbeat::spawn);
+
+    // everything that can fail is opened before the terminal is taken
+    // over, so an error leaves it as it was
+    let serial = config
+        .serial
+        .as_ref()
+        .map(serial::Display::open)
+        .transpose()?;
+
+    let control = config
+        .socket
+        .as_deref()
+        .map(control::Control::open)
+        .transpose()?;
+
+    let exporters = config
+        .exports
+        .iter()
+        .map(export::Exporter::spawn)
+        .collect::<Result<Vec<_>>>()?;
+
+    let terminal = if opts.inline {
+        ratatui::init_with_options(TerminalOptions {
+            viewport: Viewport::Inline(INLINE_HEIGHT),
+        })
+    } else {
+        ratatui::init()
+    };
+
+    let mut app = App::new(
+        opts,
+        config,
+        config_path,
+        history,
+        control,
+        serial,
+        exporters,
+    );
+    app.dry_run = dry_run;
+    app.heartbeat = heartbeat;
+    if let Some(state) = uistate::UiState::load(&uistate::default_path()) {
+        app.restore_ui_state(state);
+    }
+    let app_result = app.run(terminal);
+    ratatui::restore();
+    if app_result.is_ok() && app.config.exit_summary {
+        print!(
+            "{}",
+            app.session.report(
+                app.alerts.fired,
+                app.energy_wh,
+                app.config.rate
+            )
+        );
+    }
+    app_result
+}
+
+/// Whether the terminal can show Braille characters. The Linux console's
+/// fonts don't have them, and without a UTF-8 locale they can't be
+/// printed at all
+fn braille_supported() -> bool {
+    let var = |name| std::env::var(name).unwrap_or_default();
+
+    if matches!(var("TERM").as_str(), "linux" | "dumb" | "") {
+        return false;
+    }
+
+    // the first of these that's set decides the locale
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .into_iter()
+        .map(var)
+        .find(|v| !v.is_empty())
+        .unwrap_or_default()
+        .to_lowercase();
+
+    locale.contains("utf-8") || locale.contains("utf8")
+}
+
+/// Y bounds covering every reading in `series`, padded and clamped to
+/// [`BOUNDS_MIN`, `BOUNDS_MAX`]. Missing readings are skipped, so a
+/// series that has gone stale doesn't drag the bounds down to zero
+fn y_bounds(series: &[&[(f64, f64)]]) -> [f64; 2] {
+    let (min, max) = series
+        .iter()
+        .flat_map(|s| s.iter())
+        .map(|p| p.1)
+        .filter(|v| is_present(*v))
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), v| {
+            (min.min(v), max.max(v))
+        });
+
+    if min > max {
+        return [BOUNDS_MIN, BOUNDS_MAX];
+    }
+
+    [
+        (min - BOUNDS_PADDING).max(BOUNDS_MIN),
+        (max + BOUNDS_PADDING).min(BOUNDS_MAX),
+    ]
+}
+
+/// Split a series into runs of consecutive ticks, so the chart leaves a
+/// gap where ticks were dropped instead of joining across it
+fn segments(data: &[(f64, f64)]) -> Vec<&[(f64, f64)]> {
+    let mut ret = Vec::new();
+    let mut start = 0;
+
+    for i in 1..data.len() {
+        if data[i].0 - data[i - 1].0 > 1.5 {
+            ret.push(&data[start..i]);
+            start = i;
+        }
+    }
+    if start < data.len() {
+        ret.push(&data[start..]);
+    }
+
+    ret
+}
+
+/// Colors of the value gradient, coolest first
+const GRADIENT: [Color; 6] = [
+    Color::Blue,
+    Color::Cyan,
+    Color::Green,
+    Color::Yellow,
+    Color::LightRed,
+    Color::Red,
+];
+
+/// Split a run of points into pieces by the gradient color of their
+/// values between `lo` and `hi`, each line between two points taking
+/// the color of where it ends. Neighbouring pieces share a point so the
+/// line stays joined
+fn gradient_runs(
+    data: &[(f64, f64)],
+    lo: f64,
+    hi: f64,
+) -> Vec<(Color, &[(f64, f64)])> {
+    let color = |v: f64| {
+        let frac = ((v - lo) / (hi - lo)).clamp(0.0, 1.0);
+        let i = (frac * GRADIENT.len() as f64) as usize;
+        GRADIENT[i.min(GRADIENT.len() - 1)]
+    };
+
+    let Some(first) = data.first() else {
+        return Vec::new();
+    };
+    let mut ret = Vec::new();
+    let mut start = 0;
+    let mut current = color(data.get(1).unwrap_or(first).1);
+
+    for i in 2..data.len() {
+        let c = color(data[i].1);
+        if c != current {
+            ret.push((current, &data[start..i]));
+            start = i - 1;
+            current = c;
+        }
+    }
+    ret.push((current, &data[start..]));
+
+    ret
+}
+
+/// Number of samples that fit in the chart window
+fn window_points(config: &Config) -> usize {
+    ((config.window * 1000 / config.interval) as usize).max(2)
+}
+
+/// How far `val` is past a gauge's `max`, as a ` +12W` suffix for its
+/// label, or nothing when it fits
+fn overflow(val: f64, max: f64, unit: &str, precision: usize) -> String {
+    if val > max {
+        format!(" +{:.precision$}{unit}", val - max)
+    } else {
+        String::new()
+    }
+}
+
+/// Blink the last cell of a gauge's bar when its value is past the
+/// maximum, since the full bar alone doesn't say it was cut off. `bar`
+/// is the area inside the gauge's block
+fn render_overflow_cap(val: f64, max: f64, frame: &mut Frame, bar: Rect) {
+    if val <= max || bar.is_empty() {
+        return;
+    }
+
+    let cap = Span::styled(
+        "\u{25b6}",
+        Style::new()
+            .fg(Color::White)
+            .bg(Color::Red)
+            .add_modifier(Modifier::BOLD | Modifier::SLOW_BLINK),
+    );
+    frame.render_widget(cap, Rect::new(bar.right() - 1, bar.y, 1, 1));
+}
+
+struct App {
+    opts: Options,
+    config: Config,
+    config_path: PathBuf,
+
+    /// Settings were changed in the menu since the config was loaded or
+    /// last saved
+    unsaved: bool,
+    settings: Option<settings::SettingsMenu>,
+    note_prompt: Option<prompt::NotePrompt>,
+    history: Option<history::Recorder>,
+    control: Option<control::Control>,
+    serial: Option<serial::Display>,
+    exporters: Vec<export::Exporter>,
+    /// Where control actions go instead of being carried out, in a dry
+    /// run
+    dry_run: Option<dryrun::Log>,
+    heartbeat: Option<heartbeat::Heartbeat>,
+    baseline: Option<baseline::Baseline>,
+    alerts: alerts::Escalations,
+    session: session::Session,
+
+    /// Chart markers added over the control socket, as (x, text)
+    annotations: Vec<(f64, String)>,
+
+    /// Stats of each view, by index into `config.views` with None for
+    /// the `[chart]` settings
+    view_stats: BTreeMap<Option<usize>, ViewStats>,
+
+    /// x positions of the samples where the CPU was throttling, an alert
+    /// was up or the loop had no flow, drawn as strips along the bottom
+    /// of the chart
+    cpu_throttled: Vec<f64>,
+    alerting: Vec<f64>,
+    no_flow: Vec<f64>,
+
+    /// The sensor closest to its `crit` alert threshold and how much of
+    /// it the reading is at, in percent
+    hottest: Option<(String, f64)>,
+    /// Everything readings come from, in the order of `provider_ranks`
+    providers: Vec<Box<dyn SensorProvider>>,
+    provider_ranks: Vec<startup::Rank>,
+    /// The probes of the slower providers while any is still going
+    startup: Option<Receiver<startup::Found>>,
+    /// What the providers returned on the last tick
+    polled: Vec<Reading>,
+    /// Why neither libsensors nor the thermal zones could be opened
+    backend_error: Option<String>,
+    #[cfg(feature = "nvml")]
+    nvml: Option<Rc<nvml::Gpu>>,
+    intel: Option<intel::Gpu>,
+    tctl: Series,
+    tccd1: f64,
+    coolant1: Series,
+    coolant2: f64,
+    ambient: Series,
+    virtual_sensors: Vec<VirtualSensor>,
+    fans: Vec<Fan>,
+    /// Averaged estimate of the fans' combined noise
+    noise: noise::Average,
+    rails: Vec<Rail>,
+
+    /// Loop flow rate in l/h, None without a flow sensor
+    flow: Option<f64>,
+    flow_mm: Option<MinMax>,
+    /// Flow rates rounded to l/h, newest last
+    flow_history: VecDeque<u64>,
+    pumps: Vec<Pump>,
+    /// Reading of each `[[leaks]]` rule, None while its sensor is missing
+    leak_values: Vec<Option<f64>>,
+    leaks: alerts::Leaks,
+
+    /// The GPUs found at startup. With none the GPU gauges, series and
+    /// table rows are left out
+    gpus: Vec<GpuState>,
+    psi_cpu: Series,
+    psi_io: Series,
+    psi_memory: Series,
+
+    /// CPU package power. None without a readable RAPL counter
+    cpu_power: Option<Series>,
+
+    /// Battery charge or discharge rate and charge. None without a
+    /// battery
+    battery_power: Option<Series>,
+    charge: Option<battery::Charge>,
+
+    cpu_stat: cpustat::CpuStat,
+    cpu_load: cpustat::Load,
+    net_stat: net::NetStat,
+    /// Only kept with the processes panel on, reading every process
+    /// isn't free
+    proc_stat: Option<procs::ProcStat>,
+    /// The busiest processes as of the last tick
+    processes: Vec<procs::Process>,
+    /// One per `[[network]]` entry, in config order
+    links: Vec<Link>,
+    /// Utilization percentages, newest last
+    cpu_util: VecDeque<u64>,
+    memory: meminfo::Memory,
+    window: [f64; 2],
+
+    /// Chart y bounds, cached by `update_y_bounds`
+    y_bounds: [f64; 2],
+
+    fullscreen: bool,
+
+    /// Index into `config.views`, or `None` for the `[chart]` settings
+    view: Option<usize>,
+
+    /// Chart marker, Braille unless the terminal can't show it
+    marker: symbols::Marker,
+    energy_wh: f64,
+    silenced_until: Option<Instant>,
+
+    /// Status bar message and when it disappears
+    flash: Option<(String, Instant)>,
+
+    /// When the screen goes back to normal after an alert inverted it
+    inverted_until: Option<Instant>,
+
+    /// Wall clock time of the last tick. This is used rather than an
+    /// `Instant` so time spent suspended counts
+    last_tick: Option<SystemTime>,
+
+    /// x positions of ticks that were missed, shaded on the chart, and
+    /// how many have been missed this session
+    dropped: Vec<f64>,
+    dropped_ticks: u64,
+
+    selfmon: selfmon::SelfStats,
+    debug: bool,
+
+    /// Show the per-core and per-CCD temperatures
+    cores: bool,
+    /// Show the GPU utilization, clocks and fan panel
+    gpu_detail: bool,
+
+    #[cfg(feature = "update-check")]
+    update: update::Checker,
+    #[cfg(feature = "update-check")]
+    changelog: bool,
+}
+
+impl App {
+    fn new(
+        opts: Options,
+        config: Config,
+        config_path: PathBuf,
+        history: Option<history::Recorder>,
+        control: Option<control::Control>,
+        serial: Option<serial::Display>,
+        exporters: Vec<export::Exporter>,
+    ) -> Self {
+        let startup = startup::spawn(&config, opts.no_gpu);
+
+        let window_size = window_points(&config);
+
+        let pressure = psi::read();
+        let mut cpu_stat = cpustat::CpuStat::default();
+        let cpu_load = cpu_stat.read();
+        let proc_stat = config.processes.enabled.then(procs::ProcStat::default);
+        let mut net_stat = net::NetStat::default();
+        net_stat.read();
+        let links = config
+            .network
+            .iter()
+            .map(|n| Link {
+                rx_label: format!("{} RX", n.label),
+                tx_label: format!("{} TX", n.label),
+                rx: Series::new(window_size, 0.0),
+                tx: Series::new(window_size, 0.0),
+            })
+            .collect();
+
+        let baseline = config.baseline_minutes.map(baseline::Baseline::new);
+        let alerts = alerts::Escalations::new(&config.alerts);
+        let leaks = alerts::Leaks::new(&config.leaks);
+        let marker = if config.chart.braille.unwrap_or_else(braille_supported) {
+            symbols::Marker::Braille
+        } else {
+            symbols::Marker::Dot
+        };
+
+        Self {
+            opts,
+            config,
+            config_path,
+            unsaved: false,
+            settings: None,
+            note_prompt: None,
+            history,
+            control,
+            serial,
+            exporters,
+            dry_run: None,
+            heartbeat: None,
+            baseline,
+            alerts,
+            session: session::Session::new(),
+            annotations: Vec::new(),
+            view_stats: BTreeMap::new(),
+            cpu_throttled: Vec::new(),
+            alerting: Vec::new(),
+            no_flow: Vec::new(),
+            hottest: None,
+            providers: Vec::new(),
+            provider_ranks: Vec::new(),
+            startup: Some(startup),
+            polled: Vec::new(),
+            backend_error: None,
+            #[cfg(feature = "nvml")]
+            nvml: None,
+            intel: None,
+            tctl: Series::new(window_size, 0.0),
+            tccd1: 0.0,
+            coolant1: Series::new(window_size, 0.0),
+            coolant2: 0.0,
+            ambient: Series::new(window_size, 0.0),
+            virtual_sensors: Vec::new(),
+            fans: Vec::new(),
+            noise: noise::Average::default(),
+            rails: Vec::new(),
+            flow: None,
+            flow_mm: None,
+            flow_history: VecDeque::new(),
+            pumps: Vec::new(),
+            leak_values: Vec::new(),
+            leaks,
+            gpus: Vec::new(),
+            psi_cpu: Series::new(window_size, pressure.cpu),
+            psi_io: Series::new(window_size, pressure.io),
+            psi_memory: Series::new(window_size, pressure.memory),
+            cpu_stat,
+            cpu_load,
+            net_stat,
+            links,
+            proc_stat,
+            processes: Vec::new(),
+            cpu_util: VecDeque::new(),
+            memory: meminfo::read(),
+            cpu_power: None,
+            battery_power: None,
+            charge: battery::charge(),
+            window: [0.0, window_size as f64],
+            y_bounds: [BOUNDS_MIN, BOUNDS_MAX],
+            fullscreen: false,
+            view: None,
+            marker,
+            energy_wh: 0.0,
+            silenced_until: None,
+            flash: None,
+            inverted_until: None,
+            last_tick: None,
+            dropped: Vec::new(),
+            dropped_ticks: 0,
+            selfmon: selfmon::SelfStats::new(),
+            debug: false,
+            cores: false,
+            gpu_detail: false,
+            #[cfg(feature = "update-check")]
+            update: update::Checker::spawn(),
+            #[cfg(feature = "update-check")]
+            changelog: false,
+        }
+    }
+
+    /// Open libsensors, and the thermal zones when it has no
+    /// temperatures. Kept out of `new` so the first frame is up while
+    /// libsensors walks the buses
+    fn start_backend(&mut self) {
+        let sensors = Sensors::init();
+        let zones = if sensors.as_ref().is_ok_and(Sensors::has_temps) {
+            None
+        } else {
+            thermal::init().ok()
+        };
+        let samples = median_samples(&self.config);
+        match sensors {
+            Ok(sensors) if wants_median(&self.config) => self.attach_provider(
+                startup::Rank::Backend,
+                Box::new(provider::Median::new(sensors, samples)),
+            ),
+            Ok(sensors) => {
+                self.attach_provider(startup::Rank::Backend, Box::new(sensors))
+            }
+            Err(_) if zones.is_some() => {}
+            // the other providers and the system gauges still work,
+            // the dashboard says why the sensors are missing
+            Err(e) => self.backend_error = Some(e.to_string()),
+        }
+        if let Some(zones) = zones {
+            self.attach_provider(startup::Rank::Backend, Box::new(zones));
+        }
+    }
+
+    fn attach_provider(
+        &mut self,
+        rank: startup::Rank,
+        provider: Box<dyn SensorProvider>,
+    ) {
+        let at = self.provider_ranks.partition_point(|r| *r <= rank);
+        self.provider_ranks.insert(at, rank);
+        self.providers.insert(at, provider);
+    }
+
+    /// Take on whatever the startup probes found since the last call.
+    /// Series for a provider that joins late start out missing up to
+    /// the current tick
+    fn attach_found(&mut self) {
+        let Some(startup) = &self.startup else {
+            return;
+        };
+        let mut found = Vec::new();
+        loop {
+            match startup.try_recv() {
+                Ok(f) => found.push(f),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.startup = None;
+                    break;
+                }
+            }
+        }
+
+        let window_size = window_points(&self.config);
+        let end = self.window[1];
+        for found in found {
+            match found {
+                startup::Found::Provider(rank, provider) => {
+                    match rank {
+                        startup::Rank::Rapl => {
+                            self.cpu_power =
+                                Some(Series::ending(window_size, end, 0.0));
+                        }
+                        startup::Rank::Battery => {
+                            self.battery_power =
+                                Some(Series::ending(window_size, end, 0.0));
+                        }
+                        _ => {}
+                    }
+                    self.attach_provider(rank, provider);
+                }
+                #[cfg(feature = "nvml")]
+                startup::Found::Nvml(gpu) => {
+                    let gpu = Rc::from(gpu);
+                    // GPU temperatures can be matched by `[[sensors]]`
+                    // rules too
+                    self.attach_provider(
+                        startup::Rank::Nvml,
+                        Box::new(Rc::clone(&gpu)),
+                    );
+                    self.nvml = Some(gpu);
+                    self.find_gpus();
+                }
+                startup::Found::Intel(gpu) => {
+                    self.intel = Some(gpu);
+                    // NVML and hwmon come first, whichever already has
+                    // the GPU keeps it
+                    if self.gpus.is_empty() {
+                        self.find_gpus();
+                    }
+                }
+            }
+        }
+    }
+
+    /// Set up the GPU gauges and series for whatever `read_gpu` finds
+    fn find_gpus(&mut self) {
+        let window_size = window_points(&self.config);
+        let end = self.window[1];
+
+        // only NVML knows the device names, the other providers have a
+        // single GPU that goes by the `hardware.gpu` label anyway
+        let gpu_values = self.read_gpu();
+        #[cfg(feature = "nvml")]
+        let names = self
+            .nvml
+            .as_deref()
+            .map(nvml::Gpu::names)
+            .unwrap_or_default();
+        #[cfg(not(feature = "nvml"))]
+        let names: Vec<String> = Vec::new();
+
+        self.gpus = gpu_values
+            .iter()
+            .enumerate()
+            .map(|(i, v)| {
+                let name = names.get(i).cloned().unwrap_or_default();
+                // identical cards are told apart by their index
+                let name = if name.is_empty() {
+                    format!("GPU {i}")
+                } else if names.iter().filter(|n| **n == name).count() > 1 {
+                    format!("{name} #{i}")
+                } else {
+                    name
+                };
+                GpuState::new(name, window_size, end, v)
+            })
+            .collect();
+    }
+
+    fn run(&mut self, mut terminal: DefaultTerminal) -> Result<()> {
+        terminal.draw(|frame| self.draw(frame))?;
+        self.start_backend();
+        self.attach_found();
+        self.on_tick();
+        self.find_gpus();
+        self.update_y_bounds();
+        let mut last_tick = Instant::now();
+
+        loop {
+            let draw_start = Instant::now();
+            terminal.draw(|frame| self.draw(frame))?;
+            self.selfmon.draw = draw_start.elapsed();
+
+            self.attach_found();
+
+            let tick_rate = Duration::from_millis(self.config.interval);
+            let mut timeout = tick_rate.saturating_sub(last_tick.elapsed());
+
+            // wake up regularly so socket clients aren't kept waiting
+            // for a whole tick
+            if let Some(control) = &self.control {
+                for client in control.poll() {
+                    let result = self.handle_request(&client.request);
+                    client.reply(result);
+                }
+                timeout = timeout.min(CONTROL_POLL);
+            }
+
+            // redraw as soon as an alert flash is over
+            if let Some(until) = self.inverted_until {
+                let left = until.saturating_duration_since(Instant::now());
+                if left.is_zero() {
+                    self.inverted_until = None;
+                    continue;
+                }
+                timeout = timeout.min(left);
+            }
+
+            if event::poll(timeout)? {
+                if let Event::Key(key) = event::read()? {
+                    if !self.handle_key(key) {
+                        self.save_ui_state();
+                        return Ok(());
+                    }
+                }
+            }
+            if last_tick.elapsed() >= tick_rate {
+                self.on_tick();
+                last_tick = Instant::now();
+
+                if self.opts.inline {
+                    let summary = self.summary_line();
+                    terminal.insert_before(1, |buf| {
+                        summary.render(buf.area, buf);
+                    })?;
+                }
+            }
+        }
+    }
+
+    fn handle_request(
+        &mut self,
+        request: &control::Request,
+    ) -> Result<Option<String>> {
+        match request {
+            control::Request::Annotate(text) => {
+                if let Some(history) = &mut self.history {
+                    history.annotate(text);
+                }
+                self.annotations.push((self.window[1], text.clone()));
+                Ok(None)
+            }
+            control::Request::Snapshot(path) => {
+                let path = self.write_snapshot(path.clone())?;
+                Ok(Some(path.display().to_string()))
+            }
+            control::Request::Chart(path) => {
+                let path = self.write_chart(path.clone())?;
+                Ok(Some(path.display().to_string()))
+            }
+        }
+    }
+
+    /// Write an OpenMetrics snapshot of the current readings to `path`,
+    /// or to a timestamped file in the working directory
+    fn write_snapshot(&self, path: Option<PathBuf>) -> Result<PathBuf> {
+        let path = path.unwrap_or_else(|| {
+            PathBuf::from(format!("sensors-mon-{}.prom", history::now()))
+        });
+
+        let readings = self.readings();
+
+        let mut snapshot = openmetrics::Snapshot::default();
+        snapshot.readings(&readings, &self.reading_kinds(&readings));
+
+        // total memory is only zero when the provider doesn't report
+        // memory
+        let gpus: Vec<(&str, &GpuState)> = (0..self.gpus.len())
+            .map(|i| (self.gpu_label(i), &self.gpus[i]))
+            .filter(|(_, gpu)| gpu.mem_max > 0)
+            .collect();
+        if !gpus.is_empty() {
+            const MIB: f64 = 1024.0 * 1024.0;
+            let gauge = |f: fn(&GpuState) -> f64| -> Vec<(&str, f64)> {
+                gpus.iter().map(|(l, gpu)| (*l, f(gpu))).collect()
+            };
+
+            snapshot.gauge(
+                "sensors_mon_gpu_power_watts",
+                "watts",
+                &gauge(|gpu| gpu.watts),
+            );
+            snapshot.gauge(
+                "sensors_mon_gpu_memory_used_bytes",
+                "bytes",
+                &gauge(|gpu| gpu.mem_used as f64 * MIB),
+            );
+            snapshot.gauge(
+                "sensors_mon_gpu_memory_total_bytes",
+                "bytes",
+                &gauge(|gpu| gpu.mem_max as f64 * MIB),
+            );
+            snapshot.gauge(
+                "sensors_mon_gpu_memory_busy_ratio",
+                "ratio",
+                &gauge(|gpu| gpu.mem_busy as f64 / 100.0),
+            );
+        }
+
+        std::fs::write(&path, snapshot.finish())
+            .with_context(|| format!("writing {}", path.display()))?;
+
+        Ok(path)
+    }
+
+    /// Put back the view and toggles from the last run
+    fn restore_ui_state(&mut self, state: uistate::UiState) {
+        self.view = state.view.and_then(|name| {
+            self.config.views.iter().position(|v| v.name == name)
+        });
+        self.fullscreen = state.fullscreen;
+        self.config.legend_min_max = state.legend_min_max;
+        self.cores = state.cores;
+        self.gpu_detail = state.gpu_detail;
+        self.debug = state.debug;
+        self.update_y_bounds();
+    }
+
+    fn save_ui_state(&self) {
+        let state = uistate::UiState {
+            view: self
+                .view
+                .and_then(|i| self.config.views.get(i))
+                .map(|v| v.name.clone()),
+            fullscreen: self.fullscreen,
+            legend_min_max: self.config.legend_min_max,
+            cores: self.cores,
+            gpu_detail: self.gpu_detail,
+            debug: self.debug,
+        };
+
+        // losing the toggles isn't worth holding up quitting over
+        let _ = state.save(&uistate::default_path());
+    }
+
+    /// Save a session note to the history file, where the reports show
+    /// it, and mark it on the chart
+    fn add_note(&mut self, text: String) {
+        match &mut self.history {
+            Some(history) => {
+                history.note(&text);
+                self.flash("note saved".to_string());
+            }
+            None => self.flash("note not saved, no history file".to_string()),
+        }
+        self.annotations.push((self.window[1], text));
+    }
+
+    /// Show a message in the status bar for a few seconds
+    fn flash(&mut self, message: String) {
+        self.flash = Some((message, Instant::now() + FLASH_DURATION));
+    }
+
+    /// Returns false when the app should quit
+    fn handle_key(&mut self, key: KeyEvent) -> bool {
+        if let Some(menu) = &mut self.settings {
+            match menu.handle_key(key.code, &mut self.config) {
+                settings::Action::None => {}
+                settings::Action::Changed => {
+                    menu.message = None;
+                    self.unsaved = true;
+                    self.resize_window();
+                    self.update_y_bounds();
+                    self.trim_window_samples();
+                }
+                settings::Action::Save => {
+                    let saved =
+                        self.config.save(&self.config_path, &menu.changed);
+                    menu.message = Some(match saved {
+                        Ok(()) => {
+                            menu.changed.clear();
+                            self.unsaved = false;
+                            " saved ".to_string()
+                        }
+                        Err(e) => format!(" save failed: {e:#} "),
+                    });
+                }
+                settings::Action::Close => self.settings = None,
+            }
+
+            return true;
+        }
+
+        if let Some(prompt) = &mut self.note_prompt {
+            match prompt.handle_key(key.code) {
+                prompt::Action::None => {}
+                prompt::Action::Submit(text) => {
+                    self.note_prompt = None;
+                    self.add_note(text);
+                }
+                prompt::Action::Cancel => self.note_prompt = None,
+            }
+
+            return true;
+        }
+
+        #[cfg(feature = "update-check")]
+        if self.changelog {
+            if matches!(key.code, KeyCode::Char('c' | 'q') | KeyCode::Esc) {
+                self.changelog = false;
+            }
+            return true;
+        }
+
+        match key.code {
+            KeyCode::Char('q') => return false,
+            KeyCode::Char('l') => {
+                self.config.legend_min_max = !self.config.legend_min_max
+            }
+            KeyCode::Char('f') => self.fullscreen = !self.fullscreen,
+            KeyCode::Char('v') => self.next_view(),
+            KeyCode::Char('s') => self.toggle_silence(),
+            KeyCode::Char('r') => self.reset_min_max(),
+            KeyCode::Char('d') => self.debug = !self.debug,
+            KeyCode::Char('p') => self.cores = !self.cores,
+            KeyCode::Char('g') => self.gpu_detail = !self.gpu_detail,
+            KeyCode::Char('y') => {
+                let via = clipboard::copy(&self.temps_markdown());
+                self.flash(format!("table copied through {via}"));
+            }
+            KeyCode::Char('e') => {
+                let message = match self.write_snapshot(None) {
+                    Ok(path) => {
+                        format!("snapshot written to {}", path.display())
+                    }
+                    Err(e) => format!("snapshot failed: {e:#}"),
+                };
+                self.flash(message);
+            }
+            KeyCode::Char('x') => {
+                let message = match self.write_chart(None) {
+                    Ok(path) => format!("chart written to {}", path.display()),
+                    Err(e) => format!("chart export failed: {e:#}"),
+                };
+                self.flash(message);
+            }
+            KeyCode::Char('o') => {
+                self.settings = Some(settings::SettingsMenu::default())
+            }
+            KeyCode::Char('n') => {
+                self.note_prompt = Some(prompt::NotePrompt::default())
+            }
+            #[cfg(feature = "update-check")]
+            KeyCode::Char('c') => {
+                self.changelog = self.update.release.is_some()
+            }
+            _ => {}
+        }
+
+        true
+    }
+
+    /// Grow or shrink the chart series after the interval or window
+    /// setting changed. Growing pads the start with empty samples
+    fn resize_window(&mut self) {
+        let size = window_points(&self.config);
+        let end = self.window[1];
+
+        for series in [
+            &mut self.tctl,
+            &mut self.coolant1,
+            &mut self.ambient,
+            &mut self.psi_cpu,
+            &mut self.psi_io,
+            &mut self.psi_memory,
+        ] {
+            series.resize(size, end);
+        }
+        if let Some(series) = &mut self.cpu_power {
+            series.resize(size, end);
+        }
+        if let Some(series) = &mut self.battery_power {
+            series.resize(size, end);
+        }
+        for link in &mut self.links {
+            link.rx.resize(size, end);
+            link.tx.resize(size, end);
+        }
+        for gpu in &mut self.gpus {
+            gpu.temp.resize(size, end);
+        }
+        for v in &mut self.virtual_sensors {
+            if let Some((_, series)) = &mut v.chart {
+                series.resize(size, end);
+            }
+        }
+
+        self.window[0] = end - size as f64;
+    }
+
+    /// Take the current readings, chart series and loops' hottest
+    /// points into the shown view's min/max
+    fn update_min_max(&mut self) {
+        let readings: Vec<(String, f64)> = self
+            .readings()
+            .into_iter()
+            .chain(
+                self.chart_lines()
+                    .iter()
+                    .map(|l| (l.label.to_string(), l.series.last())),
+            )
+            .collect();
+        let loops: Vec<(String, f64)> = self
+            .config
+            .loops
+            .iter()
+            .filter_map(|l| {
+                Some((l.name.clone(), self.loop_hottest(&l.sensors)?))
+            })
+            .collect();
+
+        let stats = self.view_stats.entry(self.view).or_default();
+        for (label, value) in readings {
+            track(&mut stats.min_max, &label, value);
+        }
+        for (name, hottest) in loops {
+            track(&mut stats.loops, &name, hottest);
+        }
+    }
+
+    /// The shown view's min/max of `label`
+    fn min_max(&self, label: &str) -> Option<MinMax> {
+        self.view_stats.get(&self.view)?.min_max.get(label).copied()
+    }
+
+    /// Whether the chart shows `series` (one of `config::CHART_SERIES`)
+    /// in the current view
+    fn shows(&self, series: &str) -> bool {
+        if series == "gpu" && self.gpus.is_empty() {
+            return false;
+        }
+        if series == "cpu_power" && self.cpu_power.is_none() {
+            return false;
+        }
+        if series == "battery" && self.battery_power.is_none() {
+            return false;
+        }
+        if series == "ambient" && !is_present(self.ambient.last()) {
+            return false;
+        }
+
+        if let Some(view) = self.view.and_then(|i| self.config.views.get(i)) {
+            return view.series.iter().any(|s| s == series);
+        }
+
+        match series {
+            "cpu" => self.config.chart.cpu,
+            "coolant" => self.config.chart.coolant,
+            "ambient" => self.config.chart.ambient,
+            "gpu" => self.config.chart.gpu,
+            "psi_cpu" => self.config.chart.psi_cpu,
+            "psi_io" => self.config.chart.psi_io,
+            "psi_memory" => self.config.chart.psi_memory,
+            "drives" => self.config.chart.drives,
+            "cpu_power" => self.config.chart.cpu_power,
+            "battery" => self.config.chart.battery,
+            // each interface's `chart` key decides outside of views
+            "network" => true,
+            _ => false,
+        }
+    }
+
+    /// Switch to the next chart view, going back to the `[chart]`
+    /// settings after the last one
+    fn next_view(&mut self) {
+        if self.config.views.is_empty() {
+            return;
+        }
+
+        self.view = match self.view {
+            None => Some(0),
+            Some(i) if i + 1 < self.config.views.len() => Some(i + 1),
+            Some(_) => None,
+        };
+
+        let name = match self.view {
+            Some(i) => self.config.views[i].name.clone(),
+            None => "default".to_string(),
+        };
+        self.flash(format!("view: {name}"));
+        self.update_y_bounds();
+        // a view shown for the first time starts from the current
+        // readings
+        self.update_min_max();
+    }
+
+    fn chart_series(&self) -> Vec<&[(f64, f64)]> {
+        let mut ret: Vec<&[(f64, f64)]> = Vec::new();
+
+        if self.shows("cpu") {
+            ret.push(self.tctl.data());
+        }
+        if self.shows("coolant") {
+            ret.push(self.coolant1.data());
+        }
+        if self.shows("ambient") {
+            ret.push(self.ambient.data());
+        }
+        if self.shows("gpu") {
+            ret.extend(self.gpus.iter().map(|gpu| gpu.temp.data()));
+        }
+        for v in &self.virtual_sensors {
+            if let Some((_, series)) = &v.chart {
+                if !v.drive || self.shows("drives") {
+                    ret.push(series.data());
+                }
+            }
+        }
+        for (name, series) in self.pressure_series() {
+            if self.shows(name) {
+                ret.push(series.data());
+            }
+        }
+
+        // after dropped ticks the series reach back past the window,
+        // and what's off screen shouldn't affect the bounds
+        ret.into_iter()
+            .map(|d| &d[d.partition_point(|p| p.0 < self.window[0])..])
+            .collect()
+    }
+
+    /// Recompute the chart's y bounds. This only needs to happen when
+    /// the data or the visible series change, not on every redraw
+    fn update_y_bounds(&mut self) {
+        self.y_bounds = y_bounds(&self.chart_series());
+
+        // stall percentages are usually in the single digits, far below
+        // any temperature
+        if self
+            .pressure_series()
+            .iter()
+            .any(|(name, _)| self.shows(name))
+        {
+            self.y_bounds[0] = 0.0;
+        }
+    }
+
+    /// The pressure stall series by their `CHART_SERIES` name
+    fn pressure_series(&self) -> [(&'static str, &Series); 3] {
+        [
+            ("psi_cpu", &self.psi_cpu),
+            ("psi_io", &self.psi_io),
+            ("psi_memory", &self.psi_memory),
+        ]
+    }
+
+    fn update_virtual_sensors(&mut self) {
+        let mut readings: Vec<(String, f64, Option<Color>)> =
+            virtual_sensors(&self.polled)
+                .into_iter()
+                .map(|(l, v)| (l, v, None))
+                .collect();
+        readings.extend(configured(&self.polled, &self.config.sensors));
+        let drives = drive_temps(&self.polled);
+        let drive_labels: Vec<String> =
+            drives.iter().map(|(l, ..)| l.clone()).collect();
+        readings.extend(drives);
+        for (label, value, _) in &mut readings {
+            *value =
+                normalize_temp(*value, self.config.scale.get(label).copied());
+        }
+        // commands print whatever they measure, which mustn't be taken
+        // for milli-degrees
+        readings.extend(command_readings(&self.polled, &self.config.commands));
+
+        // sensors that stopped being written to drop out of the table
+        let fused = &self.config.fused;
+        self.virtual_sensors.retain(|v| {
+            readings.iter().any(|(l, ..)| *l == v.label)
+                || fused.iter().any(|f| f.label == v.label)
+        });
+
+        for (label, value, color) in readings {
+            let drive = drive_labels.contains(&label);
+            self.set_virtual_sensor(label, value, color, drive);
+        }
+
+        // fused sensors go last so they can combine any of the above,
+        // and the fused sensors before them
+        for i in 0..self.config.fused.len() {
+            let fused = &self.config.fused[i];
+            let values: Vec<f64> = fused
+                .sensors
+                .iter()
+                .map(|s| self.reading(s).unwrap_or(0.0))
+                .collect();
+            let value = fused.mode.apply(&values);
+            let label = fused.label.clone();
+            self.set_virtual_sensor(label, value, None, false);
+        }
+    }
+
+    fn set_virtual_sensor(
+        &mut self,
+        label: String,
+        value: f64,
+        color: Option<Color>,
+        drive: bool,
+    ) {
+        let size = window_points(&self.config);
+        let x = self.window[1];
+
+        match self.virtual_sensors.iter_mut().find(|v| v.label == label) {
+            Some(v) => {
+                v.value = value;
+                if let Some((_, series)) = &mut v.chart {
+                    series.push(x, value);
+                }
+            }
+            None => self.virtual_sensors.push(VirtualSensor {
+                drive,
+                label,
+                value,
+                chart: color.map(|c| (c, Series::ending(size, x, value))),
+            }),
+        }
+    }
+
+    fn update_fans(&mut self) {
+        let speeds =
+            fan_speeds(&self.polled, &self.config.fans, &self.config.scale);
+
+        // fans that stopped reporting (unplugged Quadro headers) drop
+        // out of the panel
+        self.fans
+            .retain(|f| speeds.iter().any(|(l, ..)| *l == f.label));
+
+        for (label, rpm, max_rpm, db) in speeds {
+            match self.fans.iter_mut().find(|f| f.label == label) {
+                Some(f) => {
+                    f.rpm = rpm;
+                    f.mm.update(rpm);
+                    f.db = db;
+                }
+                None => self.fans.push(Fan {
+                    label,
+                    rpm,
+                    mm: MinMax::new(rpm),
+                    max_rpm,
+                    db,
+                }),
+            }
+        }
+
+        self.noise
+            .update(noise::combine(self.fans.iter().filter_map(|f| f.db)));
+    }
+
+    fn update_rails(&mut self) {
+        let rails = rail_voltages(&self.polled, &self.config.voltages);
+
+        self.rails
+            .retain(|r| rails.iter().any(|n| n.label == r.label));
+
+        for rail in rails {
+            match self.rails.iter_mut().find(|r| r.label == rail.label) {
+                Some(r) => {
+                    r.volts = rail.volts;
+                    r.mm.update(rail.volts);
+                }
+                None => self.rails.push(rail),
+            }
+        }
+    }
+
+    /// Rows the voltages panel takes in the default layout. It's only
+    /// there with `[[voltages]]` rules, a board can have a dozen rails
+    /// that nobody has labelled
+    fn rails_height(&self) -> u16 {
+        if self.config.voltages.is_empty() || self.rails.is_empty() {
+            0
+        } else {
+            self.rails.len() as u16 + 3
+        }
+    }
+
+    fn update_loop(&mut self) {
+        self.flow = flow_rate(&self.polled);
+        self.pumps = pump_metrics(&self.polled, &self.config.scale);
+
+        let Some(flow) = self.flow else {
+            return;
+        };
+        match &mut self.flow_mm {
+            Some(mm) => mm.update(flow),
+            None => self.flow_mm = Some(MinMax::new(flow)),
+        }
+        if self.flow_history.len() == FLOW_HISTORY {
+            self.flow_history.pop_front();
+        }
+        self.flow_history.push_back(flow.round() as u64);
+    }
+
+    /// Processes on the GPU, when it's read through NVML
+    fn gpu_processes(&self) -> Vec<procs::GpuProcess> {
+        #[cfg(feature = "nvml")]
+        if let Some(gpu) =
+            self.nvml.as_ref().filter(|_| self.proc_stat.is_some())
+        {
+            return gpu.processes();
+        }
+        Vec::new()
+    }
+
+    /// Rows the processes panel takes in the default layout, 0 unless
+    /// it's turned on
+    fn processes_height(&self) -> u16 {
+        if self.proc_stat.is_none() {
+            0
+        } else {
+            self.config.processes.count as u16 + 3
+        }
+    }
+
+    /// Rows the loop panel needs, 0 when there's nothing to show
+    fn loop_height(&self) -> u16 {
+        let leaks = self.config.leaks.len() as u16;
+        if self.flow.is_none() && self.pumps.is_empty() {
+            return if leaks == 0 { 0 } else { leaks + 2 };
+        }
+
+        let pumps = if self.pumps.is_empty() {
+            0
+        } else {
+            self.pumps.len() as u16 + 1
+        };
+        LOOP_PANEL_HEIGHT + pumps + leaks
+    }
+
+    /// Readings of every GPU of the first provider that has any, in the
+    /// order NVML, hwmon, Intel. Providers the config rules out are
+    /// never initialized or asked
+    fn read_gpu(&mut self) -> Vec<NvmlValues> {
+        self.read_gpu_raw()
+            .into_iter()
+            .map(NvmlValues::sanitized)
+            .collect()
+    }
+
+    fn read_gpu_raw(&mut self) -> Vec<NvmlValues> {
+        if self.opts.no_gpu {
+            return Vec::new();
+        }
+
+        #[cfg(feature = "nvml")]
+        if let Some(gpu) = &self.nvml {
+            let values = gpu.values();
+            if !values.is_empty() {
+                return values;
+            }
+        }
+
+        // without NVML (no driver library, or no permission to use it
+        // in an unprivileged container) fall back to what the open
+        // kernel modules expose through hwmon
+        let hw = &self.config.hardware;
+        if hw.gpu_provider.allows(GpuProvider::Hwmon) {
+            if let Some(v) = hwmon_gpu_values(&self.polled, &hw.gpu.chip) {
+                return vec![v];
+            }
+        }
+
+        self.intel
+            .as_mut()
+            .map(intel::Gpu::values)
+            .into_iter()
+            .collect()
+    }
+
+    fn gpu_values(&mut self) -> Vec<NvmlValues> {
+        if self.gpus.is_empty() {
+            return Vec::new();
+        }
+
+        self.read_gpu()
+    }
+
+    /// Label GPU `i` is shown and matched by. The first GPU is the
+    /// `hardware.gpu` role, the others go by their device name
+    fn gpu_label(&self, i: usize) -> &str {
+        match i {
+            0 => &self.config.hardware.gpu.label,
+            _ => &self.gpus[i].name,
+        }
+    }
+
+    fn gpu_color(&self, i: usize) -> Color {
+        match i {
+            0 => self.config.hardware.gpu.color,
+            _ => EXTRA_GPU_COLORS[(i - 1) % EXTRA_GPU_COLORS.len()],
+        }
+    }
+
+    /// Combined power draw of every GPU
+    fn gpu_watts(&self) -> f64 {
+        self.gpus.iter().map(|gpu| gpu.watts).sum()
+    }
+
+    /// Every power draw that's measured: the GPUs and, with a readable
+    /// RAPL counter, the CPU package
+    fn measured_watts(&self) -> f64 {
+        self.gpu_watts() + self.cpu_power.as_ref().map_or(0.0, Series::last)
+    }
+
+    /// The top of the plot for watt series: the highest value shown in
+    /// the window rounded up to 1, 2 or 5 times a power of ten, and at
+    /// least 10W
+    fn watts_scale(&self) -> f64 {
+        let max = [
+            ("cpu_power", &self.cpu_power),
+            ("battery", &self.battery_power),
+        ]
+        .into_iter()
+        .filter(|(name, _)| self.shows(name))
+        .filter_map(|(_, s)| s.as_ref())
+        .flat_map(|s| s.data())
+        .filter(|p| p.0 >= self.window[0])
+        .fold(10.0, |max: f64, p| max.max(p.1));
+
+        let magnitude = 10f64.powf(max.log10().floor());
+        [1.0, 2.0, 5.0, 10.0]
+            .into_iter()
+            .map(|m| m * magnitude)
+            .find(|top| *top >= max)
+            .unwrap_or(max)
+    }
+
+    /// Current value of the sensor displayed with the given label, or
+    /// None if there's no such sensor or it couldn't be read
+    fn reading(&self, label: &str) -> Option<f64> {
+        let hw = &self.config.hardware;
+
+        let val = if label == hw.cpu.label {
+            self.tctl.last()
+        } else if label == hw.ccd.label {
+            self.tccd1
+        } else if label == hw.coolant1.label {
+            self.coolant1.last()
+        } else if label == hw.coolant2.label {
+            self.coolant2
+        } else if label == hw.ambient.label {
+            self.ambient.last()
+        } else if let Some(i) =
+            (0..self.gpus.len()).find(|i| self.gpu_label(*i) == label)
+        {
+            self.gpus[i].temp.last()
+        } else if label == DELTA_T_LABEL {
+            let coolant = self.coolant();
+            let ambient = self.ambient.last();
+            if !is_present(ambient) || !is_present(coolant) {
+                return None;
+            }
+
+            // can legitimately be zero or negative, so this skips the
+            // missing check below
+            return Some(coolant - ambient);
+        } else {
+            self.virtual_sensors
+                .iter()
+                .find(|v| v.label == label)?
+                .value
+        };
+
+        Some(val).filter(|v| is_present(*v))
+    }
+
+    /// The coolant probes combined by `hardware.coolant_fusion`
+    fn coolant(&self) -> f64 {
+        self.config
+            .hardware
+            .coolant_fusion
+            .apply(&[self.coolant1.last(), self.coolant2])
+    }
+
+    /// Hottest current reading among the given sensors, ignoring ones
+    /// that couldn't be read
+    fn loop_hottest(&self, sensors: &[String]) -> Option<f64> {
+        sensors
+            .iter()
+            .filter_map(|l| self.reading(l))
+            .max_by(f64::total_cmp)
+    }
+
+    /// Average of a sensor's readings over the chart window, formatted
+    /// for the temps table
+    fn window_avg(&self, label: &str) -> String {
+        let samples = self
+            .view_stats
+            .get(&self.view)
+            .and_then(|s| s.window_samples.get(label));
+        match samples {
+            Some(s) if !s.is_empty() => {
+                format!("{:.1}", s.iter().sum::<f64>() / s.len() as f64)
+            }
+            _ => "-".to_string(),
+        }
+    }
+
+    /// Keep the last window's worth of every reading for the averages.
+    /// Sensors that couldn't be read don't get a sample, so they don't
+    /// drag the average down
+    fn update_window_samples(&mut self) {
+        let readings = self.readings();
+        let stats = self.view_stats.entry(self.view).or_default();
+        for (label, value) in readings {
+            stats
+                .window_samples
+                .entry(label)
+                .or_default()
+                .push_back(value);
+        }
+        self.trim_window_samples();
+    }
+
+    /// Drop the samples that have fallen out of the chart window, also
+    /// when the window shrinks
+    fn trim_window_samples(&mut self) {
+        let size = window_points(&self.config);
+
+        let views = self.view_stats.values_mut();
+        for samples in views.flat_map(|s| s.window_samples.values_mut()) {
+            while samples.len() > size {
+                samples.pop_front();
+            }
+        }
+    }
+
+    /// Every current reading with its label, skipping sensors that
+    /// couldn't be read
+    fn readings(&self) -> Vec<(String, f64)> {
+        let hw = &self.config.hardware;
+        let labels = [
+            hw.cpu.label.as_str(),
+            &hw.ccd.label,
+            &hw.coolant1.label,
+            &hw.coolant2.label,
+            &hw.ambient.label,
+            DELTA_T_LABEL,
+        ];
+
+        labels
+            .into_iter()
+            .chain((0..self.gpus.len()).map(|i| self.gpu_label(i)))
+            .chain(self.virtual_sensors.iter().map(|v| v.label.as_str()))
+            .filter_map(|l| Some((l.to_string(), self.reading(l)?)))
+            .collect()
+    }
+
+    /// What each of `readings` measures, for the metric it's exported as
+    fn reading_kinds(
+        &self,
+        readings: &[(String, f64)],
+    ) -> Vec<openmetrics::Kind> {
+        readings
+            .iter()
+            .map(|(label, _)| {
+                if label == DELTA_T_LABEL {
+                    openmetrics::Kind::CoolantDelta
+                } else if self.config.commands.iter().any(|c| c.label == *label)
+                {
+                    openmetrics::Kind::Command
+                } else {
+                    openmetrics::Kind::Temperature
+                }
+            })
+            .collect()
+    }
+
+    /// Restart the shown view's min/max tracking from the current
+    /// readings. The other views keep theirs
+    fn reset_min_max(&mut self) {
+        if let Some(stats) = self.view_stats.get_mut(&self.view) {
+            stats.min_max.clear();
+            stats.loops.clear();
+        }
+        self.update_min_max();
+    }
+
+    fn toggle_silence(&mut self) {
+        self.silenced_until = match self.silenced_until {
+            Some(_) => None,
+            None => Some(
+                Instant::now()
+                    + Duration::from_secs(self.config.silence_minutes * 60),
+            ),
+        };
+    }
+
+    fn on_tick(&mut self) {
+        let start = Instant::now();
+        self.polled = provider::poll_all(&mut self.providers);
+        let hw = &self.config.hardware;
+        let vals =
+            role_values(&self.polled, hw).normalized(&self.config.scale, hw);
+        let gpu_vals = self.gpu_values();
+
+        if self
+            .silenced_until
+            .is_some_and(|until| Instant::now() >= until)
+        {
+            self.silenced_until = None;
+        }
+
+        check_cooler_level(
+            &vals,
+            self.config.hardware.coolant_fusion,
+            self.silenced_until.is_some(),
+            &self.config.tts_command,
+            self.dry_run.as_mut(),
+        );
+
+        // ticks that should have happened since the last one but didn't
+        // (the machine was suspended or too busy to wake us) still take
+        // up room on the chart so the time axis stays true
+        let now = SystemTime::now();
+        let missed = self
+            .last_tick
+            .and_then(|last| now.duration_since(last).ok())
+            .map(|elapsed| {
+                let ticks =
+                    elapsed.as_millis() as f64 / self.config.interval as f64;
+                (ticks.round() as u64).saturating_sub(1)
+            })
+            .unwrap_or(0);
+        self.last_tick = Some(now);
+        self.dropped_ticks += missed;
+
+        for _ in 0..missed.min(self.tctl.data().len() as u64) {
+            self.window[0] += 1.0;
+            self.window[1] += 1.0;
+            self.dropped.push(self.window[1]);
+        }
+        // past a full window the old data is off screen anyway
+        let skipped = missed.saturating_sub(self.tctl.data().len() as u64);
+        self.window[0] += skipped as f64 + 1.0;
+        self.window[1] += skipped as f64 + 1.0;
+        self.dropped.retain(|x| *x >= self.window[0]);
+
+        let w = self.window[1];
+
+        self.tctl.push(w, vals.tctl);
+        self.coolant1.push(w, vals.coolant1);
+        self.ambient.push(w, vals.ambient);
+        // a GPU that drops out of a tick reads zero so its series stays
+        // lined up with the others
+        let mut throttle_events = Vec::new();
+        for (i, gpu) in self.gpus.iter_mut().enumerate() {
+            let vals = gpu_vals.get(i).cloned().unwrap_or_default();
+            if let Some(throttle) = gpu.update(w, &vals) {
+                throttle_events.push((i, throttle));
+            }
+        }
+        for (i, throttle) in throttle_events {
+            let text = format!(
+                "{} throttling: {}",
+                self.gpu_label(i),
+                throttle.describe()
+            );
+            if let Some(history) = &mut self.history {
+                history.annotate(&text);
+            }
+        }
+
+        let pressure = psi::read();
+        self.psi_cpu.push(w, pressure.cpu);
+        self.psi_io.push(w, pressure.io);
+        self.psi_memory.push(w, pressure.memory);
+        self.cpu_load = self.cpu_stat.read();
+        if self.cpu_util.len() == CPU_UTIL_HISTORY {
+            self.cpu_util.pop_front();
+        }
+        self.cpu_util
+            .push_back(self.cpu_load.utilization.round() as u64);
+        self.memory = meminfo::read();
+        let gpu_processes = self.gpu_processes();
+        if let Some(stat) = &mut self.proc_stat {
+            self.processes = procs::top(
+                stat.read(),
+                &gpu_processes,
+                self.config.processes.count,
+            );
+        }
+        let rates = self.net_stat.read();
+        for (n, link) in self.config.network.iter().zip(&mut self.links) {
+            // an interface that's gone reads zero
+            let rate = rates
+                .iter()
+                .find(|(name, _)| *name == n.interface)
+                .map(|(_, r)| *r)
+                .unwrap_or_default();
+            link.rx.push(w, rate.rx);
+            link.tx.push(w, rate.tx);
+        }
+
+        let cpu_watts = cpu_watts(&self.polled);
+        if let Some(series) = &mut self.cpu_power {
+            series.push(w, cpu_watts);
+        }
+        let battery_watts = battery_watts(&self.polled);
+        if let Some(series) = &mut self.battery_power {
+            series.push(w, battery_watts);
+            self.charge = battery::charge();
+        }
+
+        self.tccd1 = vals.tccd1;
+        self.coolant2 = vals.coolant2;
+        self.update_virtual_sensors();
+        self.update_fans();
+        self.update_loop();
+        self.update_rails();
+        self.energy_wh +=
+            self.measured_watts() * self.config.interval as f64 / 3_600_000.0;
+
+        self.annotations.retain(|(x, _)| *x >= self.window[0]);
+
+        if vals.tctl >= CPU_THROTTLE_TEMP {
+            self.cpu_throttled.push(w);
+        }
+        // a stopped pump shows up as no flow or a header reading zero
+        let stalled = self.flow.is_some_and(|f| f < 1.0)
+            || self.pumps.iter().any(|p| p.rpm < 1.0);
+        if stalled {
+            self.no_flow.push(w);
+        }
+        self.cpu_throttled.retain(|x| *x >= self.window[0]);
+        self.alerting.retain(|x| *x >= self.window[0]);
+        self.no_flow.retain(|x| *x >= self.window[0]);
+        for gpu in &mut self.gpus {
+            gpu.throttled.retain(|x| *x >= self.window[0]);
+        }
+        self.update_y_bounds();
+        self.update_window_samples();
+        self.update_min_max();
+
+        #[cfg(feature = "update-check")]
+        self.update.poll();
+
+        if let Some(display) = &self.serial {
+            let values: Vec<Option<f64>> =
+                display.sensors().iter().map(|l| self.reading(l)).collect();
+            display.send(&values);
+        }
+
+        let readings = self.readings();
+        let mut signals = self.alerts.update(
+            &self.config.alerts,
+            &readings,
+            self.silenced_until.is_some(),
+            &self.config.tts_command,
+            &self.config.signals,
+            self.dry_run.as_mut(),
+        );
+        self.leak_values = leak_values(&self.polled, &self.config.leaks);
+        let leaks = self.leaks.update(
+            &self.config.leaks,
+            &self.leak_values,
+            &self.config.tts_command,
+            self.dry_run.as_mut(),
+        );
+        self.alerts.fired += leaks.len();
+        for text in leaks {
+            if let Some(history) = &mut self.history {
+                history.annotate(&text);
+            }
+            self.annotations.push((self.window[1], text));
+            signals.push(self.config.signals.emergency);
+        }
+        if self.alerts.active() || self.leaks.active() {
+            self.alerting.push(self.window[1]);
+        }
+        self.hottest = hottest(&readings, &self.config.alerts);
+        self.session.record(&readings);
+        for signal in signals {
+            match signal {
+                config::Signal::None => {}
+                config::Signal::Bell => bell(),
+                config::Signal::Flash => {
+                    self.inverted_until =
+                        Some(Instant::now() + SIGNAL_FLASH_DURATION);
+                }
+            }
+        }
+
+        if let Some(history) = &mut self.history {
+            let fans = self.fans.iter().map(|f| {
+                (format!("{}{}", f.label, history::FAN_SUFFIX), f.rpm)
+            });
+            let rows: Vec<(String, f64)> =
+                readings.iter().cloned().chain(fans).collect();
+            history.record(&rows);
+        }
+
+        if let Some(heartbeat) = &self.heartbeat {
+            heartbeat.send(self.summary_text());
+        }
+
+        let ts = history::now();
+        let kinds = self.reading_kinds(&readings);
+        for exporter in &self.exporters {
+            exporter.send(export::Sample {
+                ts,
+                readings: readings.clone(),
+                kinds: kinds.clone(),
+            });
+        }
+
+        if let Some(baseline) = &mut self.baseline {
+            if baseline.update(&readings) {
+                self.flash("idle baseline captured".to_string());
+            }
+        }
+
+        self.selfmon.tick = start.elapsed();
+        self.selfmon.sample();
+    }
+
+    /// Compact single-line rendering of the current readings, used for
+    /// the scrollback log in inline mode
+    fn summary_line(&self) -> Line<'static> {
+        Line::from(self.summary_text())
+    }
+
+    /// The readings in `summary_line`, also written by `--heartbeat`
+    fn summary_text(&self) -> String {
+        let hw = &self.config.hardware;
+        let mut line = format!(
+            "{} {:.1}  {} {:.1}  {} {:.1}  {} {:.1}",
+            hw.cpu.label,
+            self.tctl.last(),
+            hw.ccd.label,
+            self.tccd1,
+            hw.coolant1.label,
+            self.coolant1.last(),
+            hw.coolant2.label,
+            self.coolant2,
+        );
+        for (i, gpu) in self.gpus.iter().enumerate() {
+            line += &format!(
+                "  {} {:.1} ({:.1}W)",
+                self.gpu_label(i),
+                gpu.temp.last(),
+                gpu.watts
+            );
+        }
+
+        line
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [main, status] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(1)])
+                .areas(frame.area());
+
+        self.render_status_bar(frame, status);
+
+        if self.fullscreen {
+            self.render_temps_chart(frame, main);
+        } else {
+            self.render_dashboard(frame, main);
+        }
+
+        if self.debug {
+            self.selfmon.render(frame, main);
+        }
+
+        if self.cores {
+            let temps =
+                cores::temps(&self.polled, &self.config.hardware.cpu.chip);
+            cores::render(&temps, frame, main);
+        }
+
+        if self.gpu_detail {
+            let gpus: Vec<(&str, &NvmlValues)> = (0..self.gpus.len())
+                .map(|i| (self.gpu_label(i), &self.gpus[i].last))
+                .collect();
+            gpudetail::render(&gpus, frame, main);
+        }
+
+        if let Some(menu) = &self.settings {
+            menu.render(&self.config, &self.config_path, frame, frame.area());
+        }
+
+        if let Some(prompt) = &self.note_prompt {
+            prompt.render(frame, frame.area());
+        }
+
+        #[cfg(feature = "update-check")]
+        if let Some(release) = &self.update.release {
+            if self.changelog {
+                update::render_changelog(release, frame, frame.area());
+            }
+        }
+
+        if self.inverted_until.is_some() {
+            let area = frame.area();
+            frame.buffer_mut().set_style(
+                area,
+                Style::default().add_modifier(Modifier::REVERSED),
+            );
+        }
+    }
+
+    fn render_dashboard(&self, frame: &mut Frame, area: Rect) {
+        if let Some(tree) = &self.config.layout.tree {
+            self.render_layout_node(tree, frame, area);
+            return;
+        }
+
+        let area = if self.config.pinned.is_empty() {
+            area
+        } else {
+            let [tiles_area, rest] = Layout::vertical([
+                Constraint::Length(tiles::TILE_HEIGHT),
+                Constraint::Fill(1),
+            ])
+            .areas(area);
+
+            self.render_tiles(frame, tiles_area);
+            rest
+        };
+
+        // tall enough for the gauges, or for every table row if there
+        // are more of those (the fixed sensors, a header and the border)
+        let table_height = 4
+            + self.gpus.len() as u16
+            + self.virtual_sensors.len() as u16
+            + self.config.loops.len() as u16
+            + 3;
+        // a gauge and a table row per fan, the table header and the border
+        let fans_height = if self.fans.is_empty() {
+            0
+        } else {
+            2 * self.fans.len() as u16 + 3
+        };
+        let bottom_height = self.config.layout.bottom_height.unwrap_or(
+            table_height
+                .max(
+                    CPU_PANEL_HEIGHT
+                        + self.loop_height()
+                        + fans_height
+                        + self.rails_height()
+                        + self.processes_height(),
+                )
+                .max(
+                    GAUGES_HEIGHT_NO_GPU
+                        + CPU_GAUGE_HEIGHT * self.cpu_power.is_some() as u16
+                        + BATTERY_GAUGE_HEIGHT
+                            * self.battery_power.is_some() as u16
+                        + MEM_GAUGE_HEIGHT * self.mem_gauges() as u16
+                        + NET_GAUGE_HEIGHT * self.links.len() as u16
+                        + GPU_GAUGES_HEIGHT * self.gpus.len() as u16,
+                ),
+        );
+
+        let [top, bottom] = Layout::vertical([
+            Constraint::Fill(1),
+            Constraint::Length(bottom_height),
+        ])
+        .areas(area);
+
+        let [bottom_left, bottom_middle, bottom_right] = Layout::horizontal([
+            Constraint::Fill(1),
+            Constraint::Fill(1),
+            Constraint::Length(self.config.layout.table_width),
+        ])
+        .areas(bottom);
+
+        // the CPU panel sits above the loop panel and the fans, and
+        // takes what's left of the column when there are no fans
+        let (cpu_size, fans_size) = if self.fans.is_empty() {
+            (Constraint::Fill(1), Constraint::Length(0))
+        } else {
+            (Constraint::Length(CPU_PANEL_HEIGHT), Constraint::Fill(1))
+        };
+        let loop_height = self.loop_height();
+        let rails_height = self.rails_height();
+        let processes_height = self.processes_height();
+        let [cpu_area, loop_area, fans_area, rails_area, processes_area] =
+            Layout::vertical([
+                cpu_size,
+                Constraint::Length(loop_height),
+                fans_size,
+                Constraint::Length(rails_height),
+                Constraint::Length(processes_height),
+            ])
+            .areas(bottom_middle);
+
+        self.render_temps_chart(frame, top);
+        self.render_temps_table(frame, bottom_right);
+        self.render_gauges(frame, bottom_left);
+        self.render_cpu(frame, cpu_area);
+        if loop_height > 0 {
+            self.render_loop(frame, loop_area);
+        }
+        if !self.fans.is_empty() {
+            self.render_fans(frame, fans_area);
+        }
+        if rails_height > 0 {
+            self.render_voltages(frame, rails_area);
+        }
+        if processes_height > 0 {
+            self.render_processes(frame, processes_area);
+        }
+    }
+
+    /// Draw a `[layout] tree` node and everything under it into `area`
+    fn render_layout_node(
+        &self,
+        node: &config::LayoutNode,
+        frame: &mut Frame,
+        area: Rect,
+    ) {
+        match node {
+            config::LayoutNode::Pane(pane, _) => match pane {
+                config::Pane::Chart => self.render_temps_chart(frame, area),
+                config::Pane::Table => self.render_temps_table(frame, area),
+                config::Pane::Gauges => self.render_gauges(frame, area),
+                config::Pane::Tiles => self.render_tiles(frame, area),
+                config::Pane::Fans => self.render_fans(frame, area),
+                config::Pane::Cpu => self.render_cpu(frame, area),
+                config::Pane::Loop => self.render_loop(frame, area),
+                config::Pane::Voltages => self.render_voltages(frame, area),
+                config::Pane::Processes => self.render_processes(frame, area),
+            },
+            config::LayoutNode::Split {
+                vertical, children, ..
+            } => {
+                let constraints = children.iter().map(|c| match c.size() {
+                    config::Size::Fill => Constraint::Fill(1),
+                    config::Size::Length(n) => Constraint::Length(n),
+                    config::Size::Percent(p) => Constraint::Percentage(p),
+                });
+                let areas = if *vertical {
+                    Layout::vertical(constraints).split(area)
+                } else {
+                    Layout::horizontal(constraints).split(area)
+                };
+
+                for (child, area) in children.iter().zip(areas.iter()) {
+                    self.render_layout_node(child, frame, *area);
+                }
+            }
+        }
+    }
+
+    /// Big number tiles for the `pinned` sensors
+    fn render_tiles(&self, frame: &mut Frame, area: Rect) {
+        let tiles: Vec<(String, Option<f64>)> = self
+            .config
+            .pinned
+            .iter()
+            .map(|label| (label.clone(), self.reading(label)))
+            .collect();
+
+        tiles::render(&tiles, frame, area);
+    }
+
+    /// RAM, and swap when there is any
+    fn mem_gauges(&self) -> usize {
+        1 + (self.memory.swap_total > 0) as usize
+    }
+
+    /// The coolant gauges, CPU power when RAPL can be read, the battery
+    /// on laptops, one per `[[network]]` interface, three for each GPU
+    /// and system memory, stacked in one bordered box
+    fn render_gauges(&self, frame: &mut Frame, area: Rect) {
+        let battery = self.battery_power.is_some() as usize;
+        let cpu = self.cpu_power.is_some() as usize;
+        let links = self.links.len();
+        let extra = cpu + battery + links;
+        let gpu_rows = 3 * self.gpus.len();
+        let rows = Layout::vertical(
+            (0..3 + extra + gpu_rows + self.mem_gauges())
+                .map(|_| Constraint::Length(2)),
+        )
+        .split(area);
+        let [gauge_1, gauge_2, gauge_3] = [rows[0], rows[1], rows[2]];
+
+        let c1 = self.coolant1.last();
+        let b1 = Block::default()
+            .borders(Borders::LEFT | Borders::RIGHT)
+            .padding(Padding::new(0, 0, 1, 0));
+
+        let c2 = self.coolant2;
+        let b2 = Block::default()
+            .borders(Borders::TOP | Borders::LEFT | Borders::RIGHT)
+            .title(self.config.hardware.coolant2.label.as_str());
+
+        self.render_coolant_gauge(c1, b1, frame, gauge_1);
+        self.render_coolant_gauge(c2, b2, frame, gauge_2);
+        self.render_delta_t_gauge(frame, gauge_3);
+        if cpu == 1 {
+            self.render_cpu_watts_gauge(frame, rows[3]);
+        }
+        if battery == 1 {
+            self.render_battery_gauge(frame, rows[3 + cpu]);
+        }
+        for i in 0..links {
+            self.render_network_gauge(i, frame, rows[3 + cpu + battery + i]);
+        }
+
+        for (i, areas) in
+            rows[3 + extra..3 + extra + gpu_rows].chunks(3).enumerate()
+        {
+            self.render_gpu_watts_gauge(i, frame, areas[0]);
+            self.render_gpu_mem_busy_gauge(i, frame, areas[1]);
+            self.render_gpu_mem_gauge(i, frame, areas[2]);
+        }
+
+        let mem = &rows[3 + extra + gpu_rows..];
+        let m = self.memory;
+        self.render_mem_gauge("Memory", m.used, m.total, frame, mem[0]);
+        if let Some(&area) = mem.get(1) {
+            self.render_mem_gauge(
+                "Swap",
+                m.swap_used,
+                m.swap_total,
+                frame,
+                area,
+            );
+        }
+
+        // enclosing border for the gauges
+        let b = Block::bordered()
+            .title(self.config.hardware.coolant1.label.as_str());
+        frame.render_widget(b, area);
+    }
+
+    /// Overall utilization and clocks over a sparkline of recent
+    /// utilization
+    fn render_cpu(&self, frame: &mut Frame, area: Rect) {
+        let block = Block::bordered().title("CPU");
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let [text_area, spark_area] =
+            Layout::vertical([Constraint::Length(1), Constraint::Fill(1)])
+                .areas(inner);
+
+        let load = self.cpu_load;
+        let mut line = Line::from(vec![
+            "Load ".into(),
+            format!("{:.1}%", load.utilization).bold(),
+        ]);
+        if load.max_mhz > 0.0 {
+            line.push_span("  avg ");
+            line.push_span(format!("{:.0} MHz", load.avg_mhz).bold());
+            line.push_span("  max ");
+            line.push_span(format!("{:.0} MHz", load.max_mhz).bold());
+        }
+        frame.render_widget(line, text_area);
+
+        // newest on the right, as much as fits
+        let width = spark_area.width as usize;
+        let skip = self.cpu_util.len().saturating_sub(width);
+        let data: Vec<u64> = self.cpu_util.iter().skip(skip).copied().collect();
+        let spark = Sparkline::default()
+            .data(&data)
+            .max(100)
+            .style(Style::new().fg(Color::Green));
+        frame.render_widget(spark, spark_area);
+    }
+
+    /// Flow rate over a sparkline of recent flow, and the speed, power
+    /// and voltage of the Aquacomputer pumps and fan headers
+    fn render_loop(&self, frame: &mut Frame, area: Rect) {
+        let block = Block::bordered().title("Loop");
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let pumps_height = if self.pumps.is_empty() {
+            0
+        } else {
+            self.pumps.len() as u16 + 1
+        };
+        let leaks_height = self.config.leaks.len() as u16;
+        let [leaks_area, text_area, spark_area, table_area] =
+            Layout::vertical([
+                Constraint::Length(leaks_height),
+                Constraint::Length(1),
+                Constraint::Fill(1),
+                Constraint::Length(pumps_height),
+            ])
+            .areas(inner);
+
+        // leak detectors and fill levels go first, they matter most
+        let leaks: Vec<Line> = self
+            .config
+            .leaks
+            .iter()
+            .enumerate()
+            .map(|(i, rule)| {
+                let tripped = self.leaks.is_tripped(i);
+                let value = match (
+                    self.leak_values.get(i).copied().flatten(),
+                    rule.kind,
+                ) {
+                    (None, _) => "not found".dark_gray(),
+                    (Some(_), config::LeakKind::Leak) if tripped => {
+                        "LEAK".bold().fg(Color::Red)
+                    }
+                    (Some(_), config::LeakKind::Leak) => "dry".fg(Color::Green),
+                    (Some(v), config::LeakKind::Level) => {
+                        let color =
+                            if tripped { Color::Red } else { Color::Cyan };
+                        format!("{v:.0}%").bold().fg(color)
+                    }
+                };
+                Line::from(vec![format!("{} ", rule.label).into(), value])
+            })
+            .collect();
+        frame.render_widget(Text::from(leaks), leaks_area);
+
+        if self.flow.is_none() && self.pumps.is_empty() {
+            return;
+        }
+
+        let line = match (self.flow, self.flow_mm) {
+            (Some(flow), Some(mm)) => {
+                // a stopped flow means a dead pump or a blocked loop
+                let color = if flow < 1.0 { Color::Red } else { Color::Cyan };
+                Line::from(vec![
+                    "Flow ".into(),
+                    format!("{flow:.1} l/h").bold().fg(color),
+                    format!("  min {:.1}  max {:.1}", mm.min, mm.max).into(),
+                ])
+            }
+            _ => Line::from("no flow sensor").dark_gray(),
+        };
+        frame.render_widget(line, text_area);
+
+        let width = spark_area.width as usize;
+        let skip = self.flow_history.len().saturating_sub(width);
+        let data: Vec<u64> =
+            self.flow_history.iter().skip(skip).copied().collect();
+        let spark = Sparkline::default()
+            .data(&data)
+            .style(Style::new().fg(Color::Cyan));
+        frame.render_widget(spark, spark_area);
+
+        let value = |v: Option<f64>, unit: &str| {
+            v.map_or("-".to_string(), |v| format!("{v:.1}{unit}"))
+        };
+        let rows = self.pumps.iter().map(|p| {
+            Row::new(vec![
+                p.label.clone(),
+                format!("{:.0}", p.rpm),
+                value(p.watts, "W"),
+                value(p.volts, "V"),
+            ])
+        });
+        let widths = [
+            Constraint::Fill(1),
+            Constraint::Length(5),
+            Constraint::Length(6),
+            Constraint::Length(6),
+        ];
+
+        let table = Table::new(rows, widths).column_spacing(1).header(
+            Row::new(vec!["Header", "RPM", "Power", "Volts"])
+                .style(Style::new().bold()),
+        );
+        frame.render_widget(table, table_area);
+    }
+
+    /// Each rail's voltage with the lowest and highest seen, in red when
+    /// it's outside its range
+    fn render_voltages(&self, frame: &mut Frame, area: Rect) {
+        let block = Block::bordered().title("Voltages");
+
+        if self.rails.is_empty() {
+            let inner = block.inner(area);
+            frame.render_widget(block, area);
+            frame.render_widget(
+                Line::from("no voltages found").dark_gray(),
+                inner,
+            );
+            return;
+        }
+
+        let rows = self.rails.iter().map(|r| {
+            let row = Row::new(vec![
+                r.label.clone(),
+                format!("{:.3}", r.volts),
+                format!("{:.3}", r.mm.min),
+                format!("{:.3}", r.mm.max),
+            ]);
+            if r.out_of_spec() {
+                row.style(Style::new().bold().fg(Color::Red))
+            } else {
+                row
+            }
+        });
+        let widths = [
+            Constraint::Fill(1),
+            Constraint::Length(6),
+            Constraint::Length(6),
+            Constraint::Length(6),
+        ];
+
+        let table = Table::new(rows, widths)
+            .column_spacing(1)
+            .header(
+                Row::new(vec!["Rail", "Volts", "Min", "Max"])
+                    .style(Style::new().bold()),
+            )
+            .block(block);
+        frame.render_widget(table, area);
+    }
+
+    /// The busiest processes with their CPU use (share of one core) and
+    /// GPU utilization and memory
+    fn render_processes(&self, frame: &mut Frame, area: Rect) {
+        let block = Block::bordered().title("Processes");
+
+        if self.proc_stat.is_none() || self.processes.is_empty() {
+            let inner = block.inner(area);
+            frame.render_widget(block, area);
+            let text = if self.proc_stat.is_none() {
+                "turn on with [processes] enabled = true"
+            } else {
+                "no busy processes"
+            };
+            frame.render_widget(Line::from(text).dark_gray(), inner);
+            return;
+        }
+
+        let rows = self.processes.iter().map(|p| {
+            Row::new(vec![
+                p.pid.to_string(),
+                p.name.clone(),
+                format!("{:.0}", p.cpu),
+                p.gpu.map_or("-".to_string(), |u| u.to_string()),
+                match p.gpu_mem {
+                    0 => "-".to_string(),
+                    mib => mib.to_string(),
+                },
+            ])
+        });
+        let widths = [
+            Constraint::Length(7),
+            Constraint::Fill(1),
+            Constraint::Length(5),
+            Constraint::Length(4),
+            Constraint::Length(6),
+        ];
+
+        let table = Table::new(rows, widths)
+            .column_spacing(1)
+            .header(
+                Row::new(vec!["PID", "Name", "CPU%", "GPU%", "MiB"])
+                    .style(Style::new().bold()),
+            )
+            .block(block);
+        frame.render_widget(table, area);
+    }
+
+    /// A speed gauge for each fan, over a table of the speeds with the
+    /// lowest and highest seen
+    fn render_fans(&self, frame: &mut Frame, area: Rect) {
+        let mut block = Block::bordered().title("Fans");
+        if let Some(db) = self.noise.value() {
+            block = block
+                .title(Line::from(format!(" ~{db:.0} dB(A) ")).right_aligned());
+        }
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        if self.fans.is_empty() {
+            frame.render_widget(Line::from("no fans found").dark_gray(), inner);
+            return;
+        }
+
+        let [gauges_area, table_area] = Layout::vertical([
+            Constraint::Length(self.fans.len() as u16),
+            Constraint::Fill(1),
+        ])
+        .areas(inner);
+        let rows =
+            Layout::vertical(self.fans.iter().map(|_| Constraint::Length(1)))
+                .split(gauges_area);
+
+        for (fan, area) in self.fans.iter().zip(rows.iter()) {
+            // a stopped fan is usually a dead pump or an unplugged
+            // header, so it stands out
+            let color = if fan.rpm < 1.0 {
+                Color::Red
+            } else {
+                Color::Cyan
+            };
+            let max = fan.max_rpm as f64;
+            let label = Span::styled(
+                format!(
+                    "{} {:.0} RPM{}",
+                    fan.label,
+                    fan.rpm,
+                    overflow(fan.rpm, max, " RPM", 0)
+                ),
+                Style::new().bold().fg(Color::Gray).bg(Color::Reset),
+            );
+
+            let gauge = Gauge::default()
+                .gauge_style(color)
+                .ratio((fan.rpm / max).clamp(0.0, 1.0))
+                .label(label);
+            frame.render_widget(gauge, *area);
+            render_overflow_cap(fan.rpm, max, frame, *area);
+        }
+
+        let rows = self.fans.iter().map(|f| {
+            Row::new(vec![
+                f.label.clone(),
+                format!("{:.0}", f.rpm),
+                format!("{:.0}", f.mm.min),
+                format!("{:.0}", f.mm.max),
+                f.db.map_or("-".to_string(), |db| format!("{db:.0}")),
+            ])
+        });
+        let widths = [
+            Constraint::Fill(1),
+            Constraint::Length(5),
+            Constraint::Length(5),
+            Constraint::Length(5),
+            Constraint::Length(3),
+        ];
+
+        let table = Table::new(rows, widths).column_spacing(1).header(
+            Row::new(vec!["Fan", "RPM", "Min", "Max", "dB"])
+                .style(Style::new().bold()),
+        );
+        frame.render_widget(table, table_area);
+    }
+
+    fn render_status_bar(&self, frame: &mut Frame, area: Rect) {
+        let mut hints = Line::from(vec![
+            " q".bold(),
+            " quit  ".into(),
+            "l".bold(),
+            " legend  ".into(),
+            "f".bold(),
+            " fullscreen  ".into(),
+            "s".bold(),
+            " silence  ".into(),
+            "r".bold(),
+            " reset min/max  ".into(),
+            "e".bold(),
+            " snapshot  ".into(),
+            "x".bold(),
+            " svg  ".into(),
+            "y".bold(),
+            " copy table  ".into(),
+            "d".bold(),
+            " debug  ".into(),
+            "p".bold(),
+            " cores  ".into(),
+            "g".bold(),
+            " gpu  ".into(),
+            "n".bold(),
+            " note  ".into(),
+            "o".bold(),
+            " settings".into(),
+        ])
+        .fg(Color::Gray);
+
+        if self.dry_run.is_some() {
+            hints.push_span("  ");
+            hints.push_span("DRY RUN".bold().fg(Color::Yellow));
+        }
+
+        if self.last_tick.is_none() {
+            hints.push_span("  ");
+            hints.push_span("waiting for data".fg(Color::DarkGray));
+        }
+
+        if let Some(e) = &self.backend_error {
+            hints.push_span("  ");
+            hints.push_span(format!("no sensors: {e}").fg(Color::Red));
+        }
+
+        if self.dropped_ticks > 0 {
+            hints.push_span("  ");
+            hints.push_span(
+                format!("{} ticks dropped", self.dropped_ticks)
+                    .fg(Color::Yellow),
+            );
+        }
+
+        #[cfg(feature = "update-check")]
+        if let Some(release) = &self.update.release {
+            hints.push_span("  ");
+            hints.push_span(
+                format!("{} available, c changelog", release.version)
+                    .fg(Color::Yellow),
+            );
+        }
+
+        frame.render_widget(hints, area);
+
+        if let Some(until) = self.silenced_until {
+            let left = until.saturating_duration_since(Instant::now());
+            let badge = Line::from(format!(
+                " ALERTS SILENCED {}:{:02} ",
+                left.as_secs() / 60,
+                left.as_secs() % 60
+            ))
+            .style(Style::new().bold().fg(Color::Black).bg(Color::Yellow))
+            .centered();
+
+            frame.render_widget(badge, area);
+        }
+
+        if let Some((message, until)) = &self.flash {
+            if Instant::now() < *until {
+                let flash = Line::from(format!(" {message} "))
+                    .style(Style::new().bold().fg(Color::Black).bg(Color::Cyan))
+                    .centered();
+                frame.render_widget(flash, area);
+            }
+        }
+
+        // stays up for as long as the leak or low level does, on top of
+        // anything else
+        if self.leaks.active() {
+            let labels: Vec<&str> = self
+                .config
+                .leaks
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| self.leaks.is_tripped(*i))
+                .map(|(_, rule)| rule.label.as_str())
+                .collect();
+            let badge = Line::from(format!(" LEAK: {} ", labels.join(", ")))
+                .style(Style::new().bold().fg(Color::White).bg(Color::Red))
+                .centered();
+            frame.render_widget(badge, area);
+        }
+
+        // one number for overall thermal health: how close the sensor
+        // nearest its critical limit is to it
+        let mut right = Line::default().right_aligned();
+        if let Some((label, pct)) = &self.hottest {
+            let color = match *pct {
+                p if p >= 90.0 => Color::Red,
+                p if p >= 75.0 => Color::Yellow,
+                _ => Color::Green,
+            };
+            right.push_span(format!("{label} ").fg(Color::Gray));
+            right.push_span(format!("{pct:.0}% of crit").bold().fg(color));
+            right.push_span("  ");
+        }
+
+        // which files the settings came from, so it's clear when a
+        // setting is coming from the system file or was never saved
+        right.push_span(self.config_sources().fg(Color::DarkGray));
+        if self.unsaved {
+            right.push_span(" (unsaved)".fg(Color::Yellow));
+        }
+        right.push_span("  ");
+
+        if let Some(rate) = self.config.rate {
+            let watts = self.measured_watts();
+            let per_hour = watts / 1000.0 * rate;
+            let session = self.energy_wh / 1000.0 * rate;
+
+            right.push_span(format!("{watts:.1}W ").fg(Color::Gray));
+            right.push_span(format!("{per_hour:.3}/h").bold().fg(Color::Gray));
+            right.push_span(format!("  session {session:.3} ").fg(Color::Gray));
+        }
+
+        frame.render_widget(right, area);
+    }
+
+    /// The config files in use for the status bar, with the home
+    /// directory shortened to `~`
+    fn config_sources(&self) -> String {
+        if self.config.sources.is_empty() {
+            return "default config".to_string();
+        }
+
+        let home = std::env::var_os("HOME").map(PathBuf::from);
+        let paths: Vec<String> = self
+            .config
+            .sources
+            .iter()
+            .map(
+                |p| match home.as_ref().and_then(|h| p.strip_prefix(h).ok()) {
+                    Some(rest) => format!("~/{}", rest.display()),
+                    None => p.display().to_string(),
+                },
+            )
+            .collect();
+
+        paths.join(" + ")
+    }
+
+    /// Label of a gauge without a value, which is either a missing
+    /// sensor or nothing sampled yet
+    fn no_reading(&self) -> Span<'static> {
+        let text = if self.last_tick.is_none() {
+            "waiting for data"
+        } else {
+            "no reading"
+        };
+        Span::styled(text, Style::new().fg(Color::DarkGray))
+    }
+
+    fn render_coolant_gauge(
+        &self,
+        val: f64,
+        block: Block,
+        frame: &mut Frame,
+        area: Rect,
+    ) {
+        if self.last_tick.is_none() {
+            let g1 = Gauge::default()
+                .block(block)
+                .ratio(0.0)
+                .label(self.no_reading());
+            frame.render_widget(g1, area);
+            return;
+        }
+
+        let label = Span::styled(
+            format!("{:.1}C{}", val, overflow(val, 45.0, "C", 1)),
+            Style::new().bold().fg(Color::Gray).bg(Color::Reset),
+        );
+
+        let color = if val < 34.0 {
+            Color::Green
+        } else if val < 38.0 {
+            Color::Yellow
+        } else {
+            Color::Red
+        };
+
+        let bar = block.inner(area);
+        let g1 = Gauge::default()
+            .block(block)
+            .gauge_style(color)
+            .ratio(((val - 25.0) / 20.0).clamp(0.0, 1.0))
+            .label(label);
+
+        frame.render_widget(g1, area);
+        render_overflow_cap(val, 45.0, frame, bar);
+    }
+
+    /// Coolant temperature above ambient, which (unlike the absolute
+    /// coolant temperature) tells you how well the radiators are keeping
+    /// up regardless of how warm the room is
+    fn render_delta_t_gauge(&self, frame: &mut Frame, area: Rect) {
+        let block = Block::default()
+            .borders(Borders::TOP | Borders::LEFT | Borders::RIGHT)
+            .title(DELTA_T_LABEL);
+
+        let coolant = self.coolant();
+
+        let ambient = self.ambient.last();
+        if !is_present(ambient) || !is_present(coolant) {
+            let g1 = Gauge::default()
+                .block(block)
+                .ratio(0.0)
+                .label(self.no_reading());
+            frame.render_widget(g1, area);
+            return;
+        }
+
+        let val = coolant - ambient;
+        let label = Span::styled(
+            format!("{:.1}C{}", val, overflow(val, 20.0, "C", 1)),
+            Style::new().bold().fg(Color::Gray).bg(Color::Reset),
+        );
+
+        let color = if val < 10.0 {
+            Color::Green
+        } else if val < 15.0 {
+            Color::Yellow
+        } else {
+            Color::Red
+        };
+
+        let bar = block.inner(area);
+        let g1 = Gauge::default()
+            .block(block)
+            .gauge_style(color)
+            .ratio((val / 20.0).clamp(0.0, 1.0))
+            .label(label);
+
+        frame.render_widget(g1, area);
+        render_overflow_cap(val, 20.0, frame, bar);
+    }
+
+    fn render_cpu_watts_gauge(&self, frame: &mut Frame, area: Rect) {
+        let val = self.cpu_power.as_ref().map_or(0.0, Series::last);
+        let label = Span::styled(
+            format!("{:.1}W / 200W{}", val, overflow(val, 200.0, "W", 0)),
+            Style::new().bold().fg(Color::Gray).bg(Color::Reset),
+        );
+        let block = Block::default()
+            .borders(Borders::TOP | Borders::LEFT | Borders::RIGHT)
+            .title("CPU Power");
+        let bar = block.inner(area);
+
+        let g1 = Gauge::default()
+            .block(block)
+            .gauge_style(Color::Red)
+            .ratio((val / 200.0).clamp(0.0, 1.0))
+            .label(label);
+
+        frame.render_widget(g1, area);
+        render_overflow_cap(val, 200.0, frame, bar);
+    }
+
+    /// Fills with whichever direction is busier
+    fn render_network_gauge(&self, i: usize, frame: &mut Frame, area: Rect) {
+        let n = &self.config.network[i];
+        let link = &self.links[i];
+        let (rx, tx) = (link.rx.last(), link.tx.last());
+        let busier = rx.max(tx);
+
+        let label = Span::styled(
+            format!(
+                "RX {rx:.1} TX {tx:.1} MB/s{}",
+                overflow(busier, n.max, "MB/s", 1)
+            ),
+            Style::new().bold().fg(Color::Gray).bg(Color::Reset),
+        );
+        let block = Block::default()
+            .borders(Borders::TOP | Borders::LEFT | Borders::RIGHT)
+            .title(n.label.as_str());
+        let bar = block.inner(area);
+
+        let g1 = Gauge::default()
+            .block(block)
+            .gauge_style(n.chart.map_or(Color::Cyan, |(rx, _)| rx))
+            .ratio((busier / n.max).clamp(0.0, 1.0))
+            .label(label);
+
+        frame.render_widget(g1, area);
+        render_overflow_cap(busier, n.max, frame, bar);
+    }
+
+    fn render_battery_gauge(&self, frame: &mut Frame, area: Rect) {
+        let charge = self.charge.unwrap_or_default();
+        let watts = self.battery_power.as_ref().map_or(0.0, Series::last);
+
+        let state = if charge.charging {
+            format!("charging {watts:.1}W")
+        } else if charge.ac_online {
+            "on AC".to_string()
+        } else {
+            format!("discharging {watts:.1}W")
+        };
+        let label = Span::styled(
+            format!("{:.0}% {state}", charge.percent),
+            Style::new().bold().fg(Color::Gray).bg(Color::Reset),
+        );
+        let color = match charge.percent {
+            p if p < 15.0 => Color::Red,
+            p if p < 30.0 => Color::Yellow,
+            _ => Color::Green,
+        };
+
+        let g1 = Gauge::default()
+            .block(
+                Block::default()
+                    .borders(Borders::TOP | Borders::LEFT | Borders::RIGHT)
+                    .title("Battery"),
+            )
+            .gauge_style(color)
+            .ratio((charge.percent / 100.0).clamp(0.0, 1.0))
+            .label(label);
+
+        frame.render_widget(g1, area);
+    }
+
+    fn render_gpu_watts_gauge(&self, i: usize, frame: &mut Frame, area: Rect) {
+        let gpu = &self.gpus[i];
+        let val = gpu.watts;
+        let mut title = format!("{} Power", self.gpu_label(i));
+        if gpu.clock_mhz > 0 {
+            title.push_str(&format!(" @ {} MHz", gpu.clock_mhz));
+        }
+        let label = Span::styled(
+            format!("{:.1}W / 200W{}", val, overflow(val, 200.0, "W", 0)),
+            Style::new().bold().fg(Color::Gray).bg(Color::Reset),
+        );
+        let block = Block::default()
+            .borders(Borders::TOP | Borders::LEFT | Borders::RIGHT)
+            .title(title);
+        let bar = block.inner(area);
+
+        let g1 = Gauge::default()
+            .block(block)
+            .gauge_style(Color::Blue)
+            .ratio((val / 200.0).clamp(0.0, 1.0))
+            .label(label);
+
+        frame.render_widget(g1, area);
+        render_overflow_cap(val, 200.0, frame, bar);
+    }
+
+    /// How busy the memory bus is, which is unrelated to how full the
+    /// memory is
+    fn render_gpu_mem_busy_gauge(
+        &self,
+        i: usize,
+        frame: &mut Frame,
+        area: Rect,
+    ) {
+        let pct = self.gpus[i].mem_busy;
+        let label = Span::styled(
+            format!("{pct}%"),
+            Style::new().bold().fg(Color::Gray).bg(Color::Reset),
+        );
+
+        let g1 = Gauge::default()
+            .block(
+                Block::default()
+                    .borders(Borders::TOP | Borders::LEFT | Borders::RIGHT)
+                    .title(format!("{} Memory Bandwidth", self.gpu_label(i))),
+            )
+            .gauge_style(Color::Magenta)
+            .ratio((pct as f64 / 100.0).clamp(0.0, 1.0))
+            .label(label);
+
+        frame.render_widget(g1, area);
+    }
+
+    /// System RAM or swap, in MiB like the GPU memory gauge
+    fn render_mem_gauge(
+        &self,
+        title: &str,
+        used: u64,
+        total: u64,
+        frame: &mut Frame,
+        area: Rect,
+    ) {
         let label = Span::styled(
             format!(
                 "{}MiB / {}MiB",
@@ -498,140 +4204,722 @@ impl App {
         );
 
         let g1 = Gauge::default()
-            .block(Block::bordered().title("RTX 4070 Memory"))
+            .block(
+                Block::default()
+                    .borders(Borders::TOP | Borders::LEFT | Borders::RIGHT)
+                    .title(title),
+            )
+            .gauge_style(Color::Cyan)
+            .ratio((used as f64 / total.max(1) as f64).clamp(0.0, 1.0))
+            .label(label);
+
+        frame.render_widget(g1, area);
+    }
+
+    fn render_gpu_mem_gauge(&self, i: usize, frame: &mut Frame, area: Rect) {
+        let gpu = &self.gpus[i];
+        let (used, reserved, total) =
+            (gpu.mem_used, gpu.mem_reserved, gpu.mem_max);
+
+        // the driver sets aside some memory that nvidia-smi counts as
+        // used but NVML reports separately, so optionally add it back
+        let accounted = if self.config.vram_reserved {
+            used + reserved
+        } else {
+            used
+        };
+
+        let mut text = format!(
+            "{}MiB / {}MiB",
+            accounted.to_formatted_string(&Locale::en),
+            total.to_formatted_string(&Locale::en)
+        );
+
+        if reserved > 0 {
+            let (sign, other) = if self.config.vram_reserved {
+                ('-', used)
+            } else {
+                ('+', used + reserved)
+            };
+
+            text.push_str(&format!(
+                " ({}{}MiB rsvd = {}MiB)",
+                sign,
+                reserved.to_formatted_string(&Locale::en),
+                other.to_formatted_string(&Locale::en)
+            ));
+        }
+
+        let label = Span::styled(
+            text,
+            Style::new().bold().fg(Color::Gray).bg(Color::Reset),
+        );
+
+        // the enclosing box draws the bottom border
+        let g1 = Gauge::default()
+            .block(
+                Block::default()
+                    .borders(Borders::TOP | Borders::LEFT | Borders::RIGHT)
+                    .title(format!("{} Memory", self.gpu_label(i))),
+            )
             .gauge_style(Color::Yellow)
-            .ratio((used as f64 / total as f64).clamp(0.0, 1.0))
+            .ratio((accounted as f64 / total as f64).clamp(0.0, 1.0))
             .label(label);
 
         frame.render_widget(g1, area);
     }
 
-    fn render_temps_table(&self, frame: &mut Frame, area: Rect) {
-        let ctl1 = format!("{:.1}", self.tctl.last().unwrap().1);
-        let ctl2 = format!("{:.1}", self.tctl_mm.0);
-        let ctl3 = format!("{:.1}", self.tctl_mm.1);
-
-        let ccd1 = format!("{:.1}", self.tccd1);
-        let ccd2 = format!("{:.1}", self.tccd1_mm.0);
-        let ccd3 = format!("{:.1}", self.tccd1_mm.1);
-
-        let cool1_1 = format!("{:.1}", self.coolant1.last().unwrap().1);
-        let cool1_2 = format!("{:.1}", self.coolant1_mm.0);
-        let cool1_3 = format!("{:.1}", self.coolant1_mm.1);
-
-        let cool2_1 = format!("{:.1}", self.coolant2);
-        let cool2_2 = format!("{:.1}", self.coolant2_mm.0);
-        let cool2_3 = format!("{:.1}", self.coolant2_mm.1);
-
-        let gpu1 = format!("{:.1}", self.gpu_temp.last().unwrap().1);
-        let gpu2 = format!("{:.1}", self.gpu_temp_mm.0);
-        let gpu3 = format!("{:.1}", self.gpu_temp_mm.1);
-
-        let rows = [
-            Row::new(vec![CPU_CTL_LABEL, &ctl1, &ctl2, &ctl3]),
-            Row::new(vec![CPU_CCD_LABEL, &ccd1, &ccd2, &ccd3]),
-            Row::new(vec![COOLANT_1_LABEL, &cool1_1, &cool1_2, &cool1_3]),
-            Row::new(vec![COOLANT_2_LABEL, &cool2_1, &cool2_2, &cool2_3]),
-            Row::new(vec![GPU_LABEL, &gpu1, &gpu2, &gpu3]),
-        ];
+    /// Header and rows of the temps table
+    fn temps_table(&self) -> (Vec<&'static str>, Vec<(Vec<String>, RowKind)>) {
+        let show_base = self.baseline.is_some();
 
-        let widths = [
-            Constraint::Fill(1),
-            Constraint::Length(5),
-            Constraint::Length(5),
-            Constraint::Length(5),
+        // delta of the current reading from the idle baseline, blank
+        // until it's been captured
+        let base = |label: &str| -> String {
+            self.baseline
+                .as_ref()
+                .zip(self.reading(label))
+                .and_then(|(b, v)| b.delta(label, v))
+                .map(|d| format!("{d:+.1}"))
+                .unwrap_or_else(|| "-".to_string())
+        };
+
+        let row = |label: &str, curr: f64| {
+            let (min, max) = match self.min_max(label) {
+                Some(mm) => {
+                    (format!("{:.1}", mm.min), format!("{:.1}", mm.max))
+                }
+                None => ("-".to_string(), "-".to_string()),
+            };
+            let mut cells = vec![
+                label.to_string(),
+                format!("{curr:.1}"),
+                min,
+                max,
+                self.window_avg(label),
+            ];
+            if show_base {
+                cells.push(base(label));
+            }
+            (cells, RowKind::Sensor)
+        };
+
+        let hw = &self.config.hardware;
+        let mut rows = vec![
+            row(&hw.cpu.label, self.tctl.last()),
+            row(&hw.ccd.label, self.tccd1),
+            row(&hw.coolant1.label, self.coolant1.last()),
+            row(&hw.coolant2.label, self.coolant2),
         ];
+        // most setups have no ambient probe, so it only gets a row when
+        // there is one
+        if is_present(self.ambient.last()) {
+            rows.push(row(&hw.ambient.label, self.ambient.last()));
+        }
+        // GPUs that throttled get a badge with the reasons, the current
+        // ones while it's still going on
+        for (i, gpu) in self.gpus.iter().enumerate() {
+            let now = gpu.last.throttle;
+            let (badge, kind) = if now.any() {
+                (now, RowKind::Throttling)
+            } else {
+                (gpu.throttle_seen, RowKind::Sensor)
+            };
+
+            let (mut cells, _) = row(self.gpu_label(i), gpu.temp.last());
+            if badge.any() {
+                cells[0] = format!("{} [{}]", cells[0], badge.describe());
+            }
+            rows.push((cells, kind));
+        }
+
+        for v in &self.virtual_sensors {
+            rows.push(row(&v.label, v.value));
+        }
+
+        // loop summaries show the hottest point in the loop, with the
+        // delta to ambient next to the name when there's an ambient probe
+        let loops_mm = self.view_stats.get(&self.view).map(|s| &s.loops);
+        for l in &self.config.loops {
+            let mm = loops_mm.and_then(|mm| mm.get(&l.name));
+            let (Some(hottest), Some(mm)) = (self.loop_hottest(&l.sensors), mm)
+            else {
+                continue;
+            };
+
+            let ambient = self.ambient.last();
+            let name = if is_present(ambient) {
+                format!("{} \u{0394}{:.1}", l.name, hottest - ambient)
+            } else {
+                l.name.clone()
+            };
+
+            rows.push((
+                vec![
+                    name,
+                    format!("{hottest:.1}"),
+                    format!("{:.1}", mm.min),
+                    format!("{:.1}", mm.max),
+                    String::new(),
+                ],
+                RowKind::Summary,
+            ));
+        }
+
+        let mut header = vec!["Sensor", "Curr", "Min", "Max", "Avg"];
+        if show_base {
+            header.push("Base");
+        }
+
+        (header, rows)
+    }
+
+    fn render_temps_table(&self, frame: &mut Frame, area: Rect) {
+        let (header, rows) = self.temps_table();
+
+        let widths = header.iter().enumerate().map(|(i, _)| match i {
+            0 => Constraint::Fill(1),
+            _ => Constraint::Length(5),
+        });
+        let rows = rows.into_iter().map(|(cells, kind)| {
+            let row = Row::new(cells);
+            match kind {
+                RowKind::Sensor => row,
+                RowKind::Summary => {
+                    row.style(Style::new().bold().fg(Color::Cyan))
+                }
+                RowKind::Throttling => {
+                    row.style(Style::new().bold().fg(Color::Red))
+                }
+            }
+        });
 
         let table = Table::new(rows, widths)
             .column_spacing(1)
-            .header(
-                Row::new(vec!["Sensor", "Curr", "Min", "Max"])
-                    .style(Style::new().bold()),
-            )
+            .header(Row::new(header).style(Style::new().bold()))
             .block(Block::bordered());
 
         frame.render_widget(table, area);
     }
 
-    fn render_temps_chart(&self, frame: &mut Frame, area: Rect) {
-        let datasets = vec![
-            Dataset::default()
-                .name(format!(
-                    "{CPU_CTL_LABEL} ({:.1})",
-                    self.tctl.last().unwrap().1
-                ))
-                .marker(symbols::Marker::Braille)
-                .graph_type(GraphType::Line)
-                .style(Style::default().fg(Color::Red))
-                .data(&self.tctl),
-            Dataset::default()
-                .name(format!(
-                    "{COOLANT_1_LABEL} ({:.1})",
-                    self.coolant1.last().unwrap().1
-                ))
-                .marker(symbols::Marker::Braille)
-                .graph_type(GraphType::Line)
-                .style(Style::default().fg(Color::Blue))
-                .data(&self.coolant1),
-            Dataset::default()
-                .name(format!(
-                    "{GPU_LABEL} ({:.1})",
-                    self.gpu_temp.last().unwrap().1
-                ))
-                .marker(symbols::Marker::Braille)
-                .graph_type(GraphType::Line)
-                .style(Style::default().fg(Color::Green))
-                .data(&self.gpu_temp),
-        ];
+    /// The temps table as a Markdown table, for pasting into forum posts
+    /// and issues. Loop summaries are in bold
+    fn temps_markdown(&self) -> String {
+        let (header, rows) = self.temps_table();
+
+        let mut out = format!("| {} |\n", header.join(" | "));
+        let align: Vec<&str> = header
+            .iter()
+            .enumerate()
+            .map(|(i, _)| if i == 0 { "---" } else { "---:" })
+            .collect();
+        out.push_str(&format!("| {} |\n", align.join(" | ")));
+
+        for (mut cells, kind) in rows {
+            for cell in &mut cells {
+                *cell = cell.replace('|', "\\|");
+            }
+            if kind == RowKind::Summary {
+                cells[0] = format!("**{}**", cells[0]);
+            }
+            out.push_str(&format!("| {} |\n", cells.join(" | ")));
+        }
+
+        out
+    }
+
+    /// Gridlines for the chart: vertical ones on wall clock minute
+    /// boundaries, so they scroll with the data, and horizontal ones at
+    /// the inner y labels
+    fn grid_lines(&self) -> Vec<[(f64, f64); 2]> {
+        let [x_min, x_max] = self.window;
+        let [y_min, y_max] = self.y_bounds;
+        let mut lines = Vec::new();
+
+        let step = GRID_STEPS
+            .into_iter()
+            .find(|m| self.config.window / (m * 60) <= MAX_GRID_LINES)
+            .unwrap_or(60)
+            * 60;
+
+        // seconds are converted to x by the number of ticks they span
+        let ticks_per_sec = 1000.0 / self.config.interval as f64;
+        let mut x = x_max - (history::now() % step) as f64 * ticks_per_sec;
+        while x >= x_min {
+            lines.push([(x, y_min), (x, y_max)]);
+            x -= step as f64 * ticks_per_sec;
+        }
+
+        let steps = self.config.chart.y_labels - 1;
+        for i in 1..steps {
+            let y = y_min + i as f64 * ((y_max - y_min) / steps as f64);
+            lines.push([(x_min, y), (x_max, y)]);
+        }
+
+        lines
+    }
+
+    fn legend_name(&self, line: &ChartLine) -> String {
+        let label = line.label;
+        let fmt = |v: f64| match line.scale {
+            Some(scale) => scale.unit.format(v),
+            None => format!("{v:.1}"),
+        };
+        let curr = fmt(line.series.last());
+        // a scaled series says what the top of the plot is for it
+        let of = line
+            .scale
+            .map(|s| format!(" of {}", s.unit.format(s.top)))
+            .unwrap_or_default();
+
+        let mm = self.min_max(label).filter(|_| self.config.legend_min_max);
+        if let Some(mm) = mm {
+            let (min, max) = (fmt(mm.min), fmt(mm.max));
+            format!("{label} ({curr} | {min}\u{2013}{max}{of})")
+        } else {
+            format!("{label} ({curr}{of})")
+        }
+    }
+
+    /// Where `val` of `line` is drawn on the y axis
+    fn plot_y(&self, line: &ChartLine, val: f64) -> f64 {
+        let Some(scale) = line.scale else {
+            return val;
+        };
+        let [y_min, y_max] = self.y_bounds;
+        let top = scale.top.max(f64::EPSILON);
+        y_min + (val / top).clamp(0.0, 1.0) * (y_max - y_min)
+    }
 
-        let x_labels = vec![
-            Span::styled(
-                "5m ago",
-                Style::default().add_modifier(Modifier::BOLD),
+    /// The y axis labels, bottom first. They're in degrees unless every
+    /// shown series is scaled the same way, then they're in its unit
+    fn y_labels(&self, series: &[ChartLine]) -> Vec<String> {
+        let [y_min, y_max] = self.y_bounds;
+        let steps = self.config.chart.y_labels - 1;
+        let precision = self.config.chart.y_precision as usize;
+
+        let mut shown = series.iter().filter(|l| l.shown).map(|l| l.scale);
+        let scale = match shown.next() {
+            Some(Some(first)) if shown.all(|s| s == Some(first)) => Some(first),
+            _ => None,
+        };
+
+        (0..=steps)
+            .map(|i| {
+                let frac = i as f64 / steps as f64;
+                match scale {
+                    Some(s) => s.unit.format(frac * s.top),
+                    None => {
+                        let val = y_min + frac * (y_max - y_min);
+                        format!("{val:.precision$}")
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Every series the chart can plot
+    fn chart_lines(&self) -> Vec<ChartLine<'_>> {
+        let hw = &self.config.hardware;
+        let line = |shown, label, series, color| ChartLine {
+            shown,
+            label,
+            series,
+            color,
+            scale: None,
+        };
+
+        let mut series = vec![
+            line(
+                self.shows("cpu"),
+                hw.cpu.label.as_str(),
+                &self.tctl,
+                hw.cpu.color,
             ),
-            Span::styled(
-                "2m30s ago",
-                Style::default().add_modifier(Modifier::BOLD),
+            line(
+                self.shows("coolant"),
+                &hw.coolant1.label,
+                &self.coolant1,
+                hw.coolant1.color,
+            ),
+            line(
+                self.shows("ambient"),
+                &hw.ambient.label,
+                &self.ambient,
+                hw.ambient.color,
             ),
-            Span::styled("now", Style::default().add_modifier(Modifier::BOLD)),
         ];
+        for (i, gpu) in self.gpus.iter().enumerate() {
+            series.push(line(
+                self.shows("gpu"),
+                self.gpu_label(i),
+                &gpu.temp,
+                self.gpu_color(i),
+            ));
+        }
+        for v in &self.virtual_sensors {
+            if let Some((color, s)) = &v.chart {
+                series.push(line(
+                    !v.drive || self.shows("drives"),
+                    &v.label,
+                    s,
+                    *color,
+                ));
+            }
+        }
+        for (name, label, s, color) in [
+            ("psi_cpu", "CPU pressure %", &self.psi_cpu, Color::Yellow),
+            ("psi_io", "IO pressure %", &self.psi_io, Color::Magenta),
+            (
+                "psi_memory",
+                "Memory pressure %",
+                &self.psi_memory,
+                Color::Cyan,
+            ),
+        ] {
+            series.push(line(self.shows(name), label, s, color));
+        }
+        // watts are drawn against a shared scale
+        let watts = Scale {
+            top: self.watts_scale(),
+            unit: Unit::Watts,
+        };
+        if let Some(s) = &self.cpu_power {
+            series.push(ChartLine {
+                scale: Some(watts),
+                ..line(self.shows("cpu_power"), "CPU power", s, Color::LightRed)
+            });
+        }
+        if let Some(s) = &self.battery_power {
+            series.push(ChartLine {
+                scale: Some(watts),
+                ..line(self.shows("battery"), "Battery", s, Color::Green)
+            });
+        }
+        // throughput is drawn against the interface's gauge maximum
+        let network = self.shows("network");
+        for (n, link) in self.config.network.iter().zip(&self.links) {
+            if let Some((rx, tx)) = n.chart {
+                for (label, s, color) in [
+                    (&link.rx_label, &link.rx, rx),
+                    (&link.tx_label, &link.tx, tx),
+                ] {
+                    series.push(ChartLine {
+                        scale: Some(Scale {
+                            top: n.max,
+                            unit: Unit::MegabytesPerSec,
+                        }),
+                        ..line(network, label, s, color)
+                    });
+                }
+            }
+        }
+        series
+    }
+
+    /// The x axis labels of the chart, oldest first
+    fn x_labels(&self) -> [String; 3] {
+        [
+            format!("{} ago", fmt_duration(self.config.window)),
+            format!("{} ago", fmt_duration(self.config.window / 2)),
+            "now".to_string(),
+        ]
+    }
 
-        let y_min = self
-            .tctl
+    /// Each shown series' data as plotted, smoothed when the chart is
+    /// and scaled for rates
+    fn plotted<'a>(
+        &self,
+        series: &[ChartLine<'a>],
+    ) -> Vec<Cow<'a, [(f64, f64)]>> {
+        let smooth = self.config.chart.smooth as usize;
+        series
             .iter()
-            .zip(self.coolant1.iter())
-            .zip(self.gpu_temp.iter())
-            .map(|v| v.0 .0 .1.min(v.0 .1 .1).min(v.1 .1))
-            .filter(|v| *v >= 0.01)
-            .min_by(|a, b| {
-                if a <= b {
-                    return Ordering::Less;
+            .map(|line| {
+                let data = match line.scale {
+                    // only what was sampled, the padding isn't a zero
+                    Some(_) if line.shown => {
+                        let since = line.series.since();
+                        Cow::Owned(
+                            line.series
+                                .data()
+                                .iter()
+                                .filter(|p| p.0 > since)
+                                .map(|&(x, v)| (x, self.plot_y(line, v)))
+                                .collect(),
+                        )
+                    }
+                    _ => Cow::Borrowed(line.series.data()),
+                };
+                match smooth {
+                    n if line.shown && n > 1 => {
+                        Cow::Owned(model::smoothed(&data, n))
+                    }
+                    _ => data,
                 }
-                Ordering::Greater
             })
-            .map(|v| (v - BOUNDS_PADDING).max(BOUNDS_MIN))
-            .unwrap_or(BOUNDS_MIN);
+            .collect()
+    }
+
+    /// Write the chart as it is now to an SVG file at `path`, or to a
+    /// timestamped file in the working directory
+    fn write_chart(&self, path: Option<PathBuf>) -> Result<PathBuf> {
+        let path = path.unwrap_or_else(|| {
+            PathBuf::from(format!("sensors-mon-{}.svg", history::now()))
+        });
 
-        let y_max = self
-            .tctl
+        let series = self.chart_lines();
+        let plotted = self.plotted(&series);
+        let lines = series
             .iter()
-            .zip(self.coolant1.iter())
-            .zip(self.gpu_temp.iter())
-            .map(|v| v.0 .0 .1.max(v.0 .1 .1).max(v.1 .1))
-            .filter(|v| *v >= 0.01)
-            .max_by(|a, b| {
-                if a <= b {
-                    return Ordering::Less;
+            .zip(&plotted)
+            .filter(|(line, _)| line.shown)
+            .map(|(line, data)| {
+                // what dropped ticks pushed past the window is off the
+                // chart on screen too
+                let start = data.partition_point(|p| p.0 < self.window[0]);
+                svg::Line {
+                    label: self.legend_name(line),
+                    color: line.color,
+                    segments: segments(&data[start..]),
                 }
-                Ordering::Greater
             })
-            .map(|v| (v + BOUNDS_PADDING).min(BOUNDS_MAX))
-            .unwrap_or(BOUNDS_MAX);
+            .collect();
 
-        let labels = (0..6).map(|i| {
-            let val = y_min + i as f64 * ((y_max - y_min) / 5.0);
-            format!("{:.0}", val).bold()
-        });
+        let title = match self.view.and_then(|i| self.config.views.get(i)) {
+            Some(view) => format!("sensors-mon: {}", view.name),
+            None => "sensors-mon".to_string(),
+        };
+        let chart = svg::Chart {
+            title,
+            lines,
+            x_bounds: self.window,
+            y_bounds: self.y_bounds,
+            x_labels: self.x_labels().to_vec(),
+            y_labels: self.y_labels(&series),
+            markers: self.annotations.clone(),
+        };
+
+        std::fs::write(&path, chart.render())
+            .with_context(|| format!("writing {}", path.display()))?;
+
+        Ok(path)
+    }
+
+    fn render_temps_chart(&self, frame: &mut Frame, area: Rect) {
+        // nothing at all to plot until a provider has read something
+        let placeholder = if self.last_tick.is_none() {
+            Some("waiting for data")
+        } else if self.polled.is_empty() && self.startup.is_none() {
+            Some("no sensors found")
+        } else {
+            None
+        };
+        if let Some(placeholder) = placeholder {
+            let block = Block::bordered();
+            let inner = block.inner(area);
+            frame.render_widget(block, area);
+
+            let [_, middle, _] = Layout::vertical([
+                Constraint::Fill(1),
+                Constraint::Length(1),
+                Constraint::Fill(1),
+            ])
+            .areas(inner);
+            frame.render_widget(
+                Line::from(placeholder.fg(Color::DarkGray)).centered(),
+                middle,
+            );
+            return;
+        }
+
+        let [y_min, y_max] = self.y_bounds;
+        let hw = &self.config.hardware;
+
+        // faint lines at each series' current value, drawn first so the
+        // series themselves end up on top
+        let series = self.chart_lines();
+        // as (where it's drawn, value, color)
+        let guides: Vec<(f64, f64, Color)> = series
+            .iter()
+            .filter(|line| self.config.chart.guides && line.shown)
+            .map(|line| {
+                let cur = line.series.last();
+                (self.plot_y(line, cur), cur, line.color)
+            })
+            .filter(|(_, cur, _)| is_present(*cur))
+            .collect();
+        let guide_lines: Vec<[(f64, f64); 2]> = guides
+            .iter()
+            .map(|(y, ..)| [(self.window[0], *y), (self.window[1], *y)])
+            .collect();
+
+        // status series (throttling, alerts, no flow) get a row each
+        // along the bottom of the plot, only the ones that happened
+        // within the window
+        let mut status = vec![
+            ("Alert".to_string(), &self.alerting, Color::Yellow),
+            ("No flow".to_string(), &self.no_flow, Color::Red),
+            (
+                "CPU throttling".to_string(),
+                &self.cpu_throttled,
+                hw.cpu.color,
+            ),
+        ];
+        for (i, gpu) in self.gpus.iter().enumerate() {
+            let name = match self.gpus.len() {
+                1 => "GPU throttling".to_string(),
+                _ => format!("{} throttling", self.gpu_label(i)),
+            };
+            status.push((name, &gpu.throttled, self.gpu_color(i)));
+        }
+        status.retain(|(_, xs, _)| !xs.is_empty());
+
+        // the plot is the area inside the border minus the two rows of
+        // x axis, each strip is one row of it
+        let row = (y_max - y_min) / area.height.saturating_sub(4).max(1) as f64;
+        let strips: Vec<(String, Vec<_>, Color)> = status
+            .into_iter()
+            .enumerate()
+            .map(|(i, (name, xs, color))| {
+                let y = y_min + (i as f64 + 0.5) * row;
+                (name, xs.iter().map(|x| (*x, y)).collect(), color)
+            })
+            .collect();
+
+        let grid = if self.config.chart.grid {
+            self.grid_lines()
+        } else {
+            Vec::new()
+        };
+
+        let mut datasets = Vec::new();
+
+        for line in &grid {
+            datasets.push(
+                Dataset::default()
+                    .marker(self.marker)
+                    .graph_type(GraphType::Line)
+                    .style(Style::default().fg(Color::DarkGray))
+                    .data(line),
+            );
+        }
+
+        // dropped ticks are shaded with full height grey bars under
+        // everything else
+        let dropped_shade: Vec<(f64, f64)> =
+            self.dropped.iter().map(|x| (*x, y_max)).collect();
+        if !dropped_shade.is_empty() {
+            datasets.push(
+                Dataset::default()
+                    .name("No data")
+                    .marker(self.marker)
+                    .graph_type(GraphType::Bar)
+                    .style(
+                        Style::default()
+                            .fg(Color::DarkGray)
+                            .add_modifier(Modifier::DIM),
+                    )
+                    .data(&dropped_shade),
+            );
+        }
+
+        // whole cells, so a strip reads as a solid band
+        for (name, strip, color) in &strips {
+            datasets.push(
+                Dataset::default()
+                    .name(name.clone())
+                    .marker(symbols::Marker::Block)
+                    .graph_type(GraphType::Scatter)
+                    .style(Style::default().fg(*color))
+                    .data(strip),
+            );
+        }
+
+        for ((.., color), line) in guides.iter().zip(&guide_lines) {
+            datasets.push(
+                Dataset::default()
+                    .marker(self.marker)
+                    .graph_type(GraphType::Line)
+                    .style(
+                        Style::default().fg(*color).add_modifier(Modifier::DIM),
+                    )
+                    .data(line),
+            );
+        }
+
+        let plotted = self.plotted(&series);
+
+        for (line, data) in series.iter().zip(&plotted) {
+            if !line.shown {
+                continue;
+            }
+
+            let label = line.label;
+            let chart = &self.config.chart;
+            let gradient = line.scale.is_none()
+                && chart.gradient.iter().any(|l| l == label);
+            let pieces: Vec<(Color, &[(f64, f64)])> = if gradient {
+                let crit = self
+                    .config
+                    .alerts
+                    .iter()
+                    .find(|a| a.sensor == label)
+                    .and_then(|a| a.crit)
+                    .filter(|c| *c > chart.gradient_min);
+                let hi = crit.unwrap_or(chart.gradient_max);
+                segments(data)
+                    .into_iter()
+                    .flat_map(|s| gradient_runs(s, chart.gradient_min, hi))
+                    .collect()
+            } else {
+                segments(data)
+                    .into_iter()
+                    .map(|s| (line.color, s))
+                    .collect()
+            };
+
+            // only the first piece is named so the legend has one entry
+            // per series
+            for (i, (color, piece)) in pieces.into_iter().enumerate() {
+                let mut dataset = Dataset::default()
+                    .marker(self.marker)
+                    .graph_type(GraphType::Line)
+                    .style(Style::default().fg(color))
+                    .data(piece);
+                if i == 0 {
+                    dataset = dataset.name(self.legend_name(line));
+                }
+                datasets.push(dataset);
+            }
+        }
+
+        let x_labels: Vec<Span> = self
+            .x_labels()
+            .into_iter()
+            .map(|l| {
+                Span::styled(l, Style::default().add_modifier(Modifier::BOLD))
+            })
+            .collect();
+
+        let labels = self.y_labels(&series).into_iter().map(Stylize::bold);
+
+        let markers: Vec<[(f64, f64); 2]> = self
+            .annotations
+            .iter()
+            .map(|(x, _)| [(*x, y_min), (*x, y_max)])
+            .collect();
+
+        for ((_, text), marker) in self.annotations.iter().zip(&markers) {
+            datasets.push(
+                Dataset::default()
+                    .name(text.clone())
+                    .marker(self.marker)
+                    .graph_type(GraphType::Line)
+                    .style(Style::default().fg(Color::DarkGray))
+                    .data(marker),
+            );
+        }
 
         let chart = Chart::new(datasets)
             // always show the legend (first constraint will always return true)
@@ -655,5 +4943,27 @@ impl App {
             );
 
         frame.render_widget(chart, area);
+
+        // value of each guide at the right edge of the plot. the plot
+        // is the area inside the border minus the two rows of x axis
+        let plot_top = area.y + 1;
+        let plot_height = area.height.saturating_sub(4);
+        if plot_height < 2 || y_max <= y_min {
+            return;
+        }
+
+        for (y, cur, color) in &guides {
+            let frac = (y_max - y) / (y_max - y_min);
+            let row = plot_top
+                + (frac.clamp(0.0, 1.0) * (plot_height - 1) as f64).round()
+                    as u16;
+
+            let label = Line::from(format!(" {cur:.1} "))
+                .style(Style::new().fg(*color).bold())
+                .right_aligned();
+            let label_area = Rect::new(area.x + 1, row, area.width - 2, 1);
+
+            frame.render_widget(label, label_area);
+        }
     }
 }
@@ -0,0 +1,38 @@
+//! System memory and swap usage from /proc/meminfo
+
+use std::fs::read_to_string;
+
+const MEMINFO: &str = "/proc/meminfo";
+
+/// Usage in MiB. Used memory is what isn't available, so page cache the
+/// kernel would hand back doesn't count. Swap is zero without any
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Memory {
+    pub used: u64,
+    pub total: u64,
+    pub swap_used: u64,
+    pub swap_total: u64,
+}
+
+pub fn read() -> Memory {
+    let Ok(text) = read_to_string(MEMINFO) else {
+        return Memory::default();
+    };
+
+    // every line is `Key:   value kB`
+    let field = |key: &str| {
+        text.lines()
+            .find_map(|l| l.strip_prefix(key)?.strip_prefix(':'))
+            .and_then(|v| v.split_whitespace().next()?.parse::<u64>().ok())
+            .map_or(0, |kib| kib / 1024)
+    };
+
+    let total = field("MemTotal");
+    let swap_total = field("SwapTotal");
+    Memory {
+        used: total.saturating_sub(field("MemAvailable")),
+        total,
+        swap_used: swap_total.saturating_sub(field("SwapFree")),
+        swap_total,
+    }
+}
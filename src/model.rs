@@ -0,0 +1,251 @@
+//! The sampling data model: chart series, min/max tracking, summary
+//! stats and sensor fusion. This only uses `core` and `alloc` so it can
+//! be copied as-is into a `no_std` project (like a microcontroller
+//! driven companion display) without pulling in the TUI or sensor
+//! libraries
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+/// Readings below this are treated as missing. That's what we store
+/// when a sensor couldn't be read
+pub const MISSING_BELOW: f64 = 0.01;
+
+pub fn is_present(val: f64) -> bool {
+    val >= MISSING_BELOW
+}
+
+/// Lowest and highest value seen
+#[derive(Debug, Clone, Copy)]
+pub struct MinMax {
+    pub min: f64,
+    pub max: f64,
+}
+
+impl MinMax {
+    pub fn new(val: f64) -> Self {
+        Self { min: val, max: val }
+    }
+
+    /// Take `val` into account. Missing readings are skipped, and a
+    /// MinMax that started out missing begins at the first real one
+    pub fn update(&mut self, val: f64) {
+        if !is_present(val) {
+            return;
+        }
+        if !is_present(self.max) {
+            *self = Self::new(val);
+            return;
+        }
+
+        self.min = self.min.min(val);
+        self.max = self.max.max(val);
+    }
+}
+
+/// A fixed-length series of `(x, value)` points, where x counts ticks
+#[derive(Debug, Clone)]
+pub struct Series {
+    data: Vec<(f64, f64)>,
+    /// x of the point the series was created with, the ones before it
+    /// are padding
+    since: f64,
+}
+
+impl Series {
+    /// `size` points ending at x = `size - 1` with value `val`, padded
+    /// at the start with missing readings
+    pub fn new(size: usize, val: f64) -> Self {
+        Self::ending(size, size.saturating_sub(1) as f64, val)
+    }
+
+    /// `size` points ending at x = `end` with value `val`, for a series
+    /// that starts partway through a session
+    pub fn ending(size: usize, end: f64, val: f64) -> Self {
+        let mut data = Vec::with_capacity(size);
+        for i in (1..size).rev() {
+            data.push((end - i as f64, 0.0));
+        }
+        data.push((end, val));
+
+        Self {
+            data,
+            since: end,
+        }
+    }
+
+    pub fn data(&self) -> &[(f64, f64)] {
+        &self.data
+    }
+
+    /// x of the point the series was created with. For series where a
+    /// zero is a reading rather than a missing one, the points up to it
+    /// weren't sampled
+    pub fn since(&self) -> f64 {
+        self.since
+    }
+
+    /// Most recent value
+    pub fn last(&self) -> f64 {
+        self.data.last().map(|p| p.1).unwrap_or(0.0)
+    }
+
+    /// Drop the oldest point and append `val` at `x`
+    pub fn push(&mut self, x: f64, val: f64) {
+        if !self.data.is_empty() {
+            self.data.remove(0);
+        }
+        self.data.push((x, val));
+    }
+
+    /// Change the number of points. Growing pads the start with missing
+    /// readings
+    pub fn resize(&mut self, size: usize, end: f64) {
+        if self.data.len() > size {
+            self.data.drain(..self.data.len() - size);
+        }
+
+        while self.data.len() < size {
+            let x = self.data.first().map(|p| p.0).unwrap_or(end) - 1.0;
+            self.data.insert(0, (x, 0.0));
+        }
+    }
+}
+
+/// Centered moving average over `width` ticks, for drawing. Sensors
+/// report in steps (0.1 or 0.125 degrees) and a slow drift between two
+/// steps is otherwise drawn as a flat line with a jump of a whole dot
+/// row. Averaging spreads the jump over the neighbouring ticks so it's
+/// drawn as a ramp. Missing readings stay missing and aren't averaged
+/// in, and nothing is averaged across a gap in x
+pub fn smoothed(data: &[(f64, f64)], width: usize) -> Vec<(f64, f64)> {
+    let half = width / 2;
+
+    data.iter()
+        .enumerate()
+        .map(|(i, &(x, v))| {
+            if !is_present(v) {
+                return (x, v);
+            }
+
+            // points are one tick apart except across gaps, so the
+            // neighbours within `half` ticks are at most `half` away
+            let lo = i.saturating_sub(half);
+            let hi = (i + half + 1).min(data.len());
+            let (sum, n) = data[lo..hi]
+                .iter()
+                .filter(|(px, pv)| {
+                    (px - x).abs() <= half as f64 && is_present(*pv)
+                })
+                .fold((0.0, 0), |(sum, n), (_, pv)| (sum + pv, n + 1));
+            (x, sum / n as f64)
+        })
+        .collect()
+}
+
+/// Average, max and 95th percentile of a set of readings
+#[derive(Debug, Clone, Copy)]
+pub struct Stats {
+    pub avg: f64,
+    pub max: f64,
+    pub p95: f64,
+}
+
+impl Stats {
+    pub fn of(mut values: Vec<f64>) -> Option<Self> {
+        if values.is_empty() {
+            return None;
+        }
+
+        values.sort_by(f64::total_cmp);
+
+        // ceil(len * 0.95) - 1 without needing float rounding, which
+        // isn't in core
+        let p95_idx = (values.len() * 95).div_ceil(100).saturating_sub(1);
+
+        Some(Self {
+            avg: values.iter().sum::<f64>() / values.len() as f64,
+            max: values[values.len() - 1],
+            p95: values[p95_idx],
+        })
+    }
+}
+
+/// Strategy for combining several redundant sensors that measure the
+/// same thing into a single value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fusion {
+    Min,
+    Max,
+    Avg,
+}
+
+impl Fusion {
+    pub fn name(self) -> &'static str {
+        match self {
+            Fusion::Min => "min",
+            Fusion::Max => "max",
+            Fusion::Avg => "avg",
+        }
+    }
+
+    /// Combine the given readings. Missing readings are skipped; if
+    /// every reading is missing the result is zero
+    pub fn apply(self, vals: &[f64]) -> f64 {
+        let mut present = vals.iter().copied().filter(|v| is_present(*v));
+
+        let first = match present.next() {
+            Some(v) => v,
+            None => return 0.0,
+        };
+
+        match self {
+            Fusion::Min => present.fold(first, f64::min),
+            Fusion::Max => present.fold(first, f64::max),
+            Fusion::Avg => {
+                let (sum, n) =
+                    present.fold((first, 1), |(sum, n), v| (sum + v, n + 1));
+                sum / n as f64
+            }
+        }
+    }
+}
+
+/// First two bytes of every display frame
+pub const FRAME_MAGIC: [u8; 2] = [0xa5, 0x5a];
+
+/// Value sent for a sensor that couldn't be read
+pub const FRAME_MISSING: i16 = i16::MIN;
+
+/// Encode readings as a compact frame for a companion display:
+///
+/// - [`FRAME_MAGIC`]
+/// - count of values (u8, extra values past 255 are dropped)
+/// - each value as a little-endian i16 in hundredths, or
+///   [`FRAME_MISSING`] for `None`
+/// - wrapping sum of every byte after the magic (u8)
+pub fn encode_frame(values: &[Option<f64>]) -> Vec<u8> {
+    let values = &values[..values.len().min(u8::MAX as usize)];
+
+    let mut frame = Vec::with_capacity(4 + values.len() * 2);
+    frame.extend_from_slice(&FRAME_MAGIC);
+    frame.push(values.len() as u8);
+
+    for val in values {
+        let val = match val {
+            // `as` saturates, so out of range readings clamp instead of
+            // wrapping around (or turning into the missing marker)
+            Some(v) => ((*v * 100.0) as i16).max(FRAME_MISSING + 1),
+            None => FRAME_MISSING,
+        };
+        frame.extend_from_slice(&val.to_le_bytes());
+    }
+
+    let sum = frame[FRAME_MAGIC.len()..]
+        .iter()
+        .fold(0u8, |sum, b| sum.wrapping_add(*b));
+    frame.push(sum);
+
+    frame
+}
@@ -0,0 +1,83 @@
+//! Network interface throughput from /proc/net/dev, for `[[network]]`
+//! entries in the config. The kernel only keeps byte counters, so like
+//! CPU utilization the rates are how far they moved since the last read
+
+use std::{fs::read_to_string, time::Instant};
+
+const NET_DEV: &str = "/proc/net/dev";
+
+/// Receive and transmit rates in MB/s
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Throughput {
+    pub rx: f64,
+    pub tx: f64,
+}
+
+/// Keeps the previous counters and when they were read
+#[derive(Debug, Default)]
+pub struct NetStat {
+    read_at: Option<Instant>,
+    /// Received and sent bytes by interface
+    last: Vec<(String, u64, u64)>,
+}
+
+/// Received and sent bytes of every interface, from lines like
+/// `  eth0: 1234 56 0 0 0 0 0 0 7890 12 ...` where the ninth counter is
+/// the first transmit one
+fn counters() -> Vec<(String, u64, u64)> {
+    let text = read_to_string(NET_DEV).unwrap_or_default();
+
+    text.lines()
+        .filter_map(|line| {
+            let (name, rest) = line.split_once(':')?;
+            let fields: Vec<u64> = rest
+                .split_whitespace()
+                .filter_map(|f| f.parse().ok())
+                .collect();
+            Some((name.trim().to_string(), *fields.first()?, *fields.get(8)?))
+        })
+        .collect()
+}
+
+/// Every interface the kernel lists, for `sensors-mon list`
+pub fn interfaces() -> Vec<String> {
+    counters().into_iter().map(|(name, ..)| name).collect()
+}
+
+impl NetStat {
+    /// Rates of every interface since the last read, by name. The first
+    /// call only primes the counters and reports none. A counter that
+    /// went backwards (the interface was recreated) reads as zero
+    pub fn read(&mut self) -> Vec<(String, Throughput)> {
+        let now = Instant::now();
+        let counters = counters();
+
+        let mut ret = Vec::new();
+        if let Some(then) = self.read_at {
+            let secs = now.duration_since(then).as_secs_f64();
+            if secs > 0.0 {
+                for (name, rx, tx) in &counters {
+                    let Some((_, last_rx, last_tx)) =
+                        self.last.iter().find(|(n, ..)| n == name)
+                    else {
+                        continue;
+                    };
+                    let rate = |now: u64, last: u64| {
+                        now.saturating_sub(last) as f64 / secs / 1e6
+                    };
+                    ret.push((
+                        name.clone(),
+                        Throughput {
+                            rx: rate(*rx, *last_rx),
+                            tx: rate(*tx, *last_tx),
+                        },
+                    ));
+                }
+            }
+        }
+
+        self.read_at = Some(now);
+        self.last = counters;
+        ret
+    }
+}
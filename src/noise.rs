@@ -0,0 +1,71 @@
+//! A rough estimate of how loud the fans are. Each fan's level comes
+//! from its `noise` curve in `[[fans]]`, or the fan laws from a typical
+//! 120mm fan when it has none, the levels are added up the way sound
+//! adds up, and the total is averaged over the last few ticks so it
+//! doesn't jump with every RPM wobble. It's only good for comparing fan
+//! settings against each other, not as a measurement
+
+/// A typical 120mm fan's level in dB(A) at 1000 RPM, for fans without a
+/// curve
+const REFERENCE: (f64, f64) = (1000.0, 25.0);
+
+/// Weight of the newest total in the moving average
+const SMOOTHING: f64 = 0.2;
+
+/// The level `db` at `rpm` moved to `target` RPM. Fan noise goes up by
+/// about 50 log10 of the speed ratio
+fn fan_law((rpm, db): (f64, f64), target: f64) -> f64 {
+    db + 50.0 * (target / rpm).log10()
+}
+
+/// A fan's level in dB(A) at `rpm`, None when it's stopped. `curve` is
+/// (RPM, dB(A)) points sorted by RPM and is interpolated between them
+/// and extended past its ends with the fan laws
+pub fn level(rpm: f64, curve: &[(f64, f64)]) -> Option<f64> {
+    if rpm < 1.0 {
+        return None;
+    }
+
+    let (Some(first), Some(last)) = (curve.first(), curve.last()) else {
+        return Some(fan_law(REFERENCE, rpm));
+    };
+    if rpm <= first.0 {
+        return Some(fan_law(*first, rpm));
+    }
+    if rpm >= last.0 {
+        return Some(fan_law(*last, rpm));
+    }
+
+    let i = curve.partition_point(|(r, _)| *r <= rpm);
+    let ((r0, d0), (r1, d1)) = (curve[i - 1], curve[i]);
+    Some(d0 + (d1 - d0) * (rpm - r0) / (r1 - r0))
+}
+
+/// Several sources together: their sound powers add, so two equally
+/// loud fans are 3 dB louder than one
+pub fn combine(levels: impl IntoIterator<Item = f64>) -> Option<f64> {
+    let power: f64 = levels.into_iter().map(|l| 10f64.powf(l / 10.0)).sum();
+    (power > 0.0).then(|| 10.0 * power.log10())
+}
+
+/// Exponentially weighted moving average of the total level
+#[derive(Debug, Default)]
+pub struct Average {
+    value: Option<f64>,
+}
+
+impl Average {
+    /// Add a tick's total, None when every fan is stopped, and return
+    /// the average. Stopping every fan clears it straight away
+    pub fn update(&mut self, total: Option<f64>) -> Option<f64> {
+        self.value = match (self.value, total) {
+            (Some(avg), Some(t)) => Some(avg + SMOOTHING * (t - avg)),
+            (_, t) => t,
+        };
+        self.value
+    }
+
+    pub fn value(&self) -> Option<f64> {
+        self.value
+    }
+}
@@ -0,0 +1,258 @@
+//! GPU readings through NVML
+
+use crate::{
+    procs::GpuProcess,
+    provider::{NvmlValues, Reading, SensorProvider, Throttle},
+};
+use anyhow::Result;
+use nvml_wrapper::{
+    bitmasks::device::ThrottleReasons,
+    enum_wrappers::device::{Clock, TemperatureSensor},
+    enums::device::{SampleValue, UsedGpuMemory},
+    structs::device::FieldId,
+    sys_exports::field_id::NVML_FI_DEV_MEMORY_TEMP,
+    Device, Nvml,
+};
+use std::rc::Rc;
+
+const B_TO_MIB: u64 = 1024 * 1024;
+
+/// Throttle reasons by what they're shown as
+const THERMAL_REASONS: ThrottleReasons = ThrottleReasons::SW_THERMAL_SLOWDOWN
+    .union(ThrottleReasons::HW_THERMAL_SLOWDOWN);
+const POWER_REASONS: ThrottleReasons = ThrottleReasons::HW_SLOWDOWN
+    .union(ThrottleReasons::HW_POWER_BRAKE_SLOWDOWN);
+
+pub struct Gpu(Nvml);
+
+/// Memory junction temperature. NVML has no call for it, only a field
+/// value that most cards fail to read
+fn mem_temp(device: &Device) -> Option<f64> {
+    let mut samples = device
+        .field_values_for(&[FieldId(NVML_FI_DEV_MEMORY_TEMP)])
+        .ok()?;
+
+    match samples.pop()?.ok()?.value.ok()? {
+        SampleValue::U32(c) => Some(c as f64),
+        SampleValue::U64(c) => Some(c as f64),
+        SampleValue::I64(c) => Some(c as f64),
+        SampleValue::F64(c) => Some(c),
+    }
+    .filter(|&c| c > 0.0)
+}
+
+pub fn init() -> Result<Gpu> {
+    Ok(Gpu(Nvml::init()?))
+}
+
+impl Gpu {
+    /// Name of each device, in index order
+    pub fn names(&self) -> Vec<String> {
+        let count = self.0.device_count().unwrap_or(0);
+
+        (0..count)
+            .map(|i| {
+                self.0
+                    .device_by_index(i)
+                    .and_then(|d| d.name())
+                    .unwrap_or_default()
+            })
+            .collect()
+    }
+
+    /// One line per device for `sensors-mon list`, with its index and
+    /// current temperature and power
+    pub fn describe(&self) -> Vec<String> {
+        let count = self.0.device_count().unwrap_or(0);
+
+        (0..count)
+            .filter_map(|i| {
+                let device = self.0.device_by_index(i).ok()?;
+                let name = device.name().unwrap_or_default();
+                let temp = device
+                    .temperature(TemperatureSensor::Gpu)
+                    .map(|c| format!("{c}C"))
+                    .unwrap_or_else(|_| "-".to_string());
+                let watts = device
+                    .power_usage()
+                    .map(|mw| format!("{:.1}W", mw as f64 / 1000.0))
+                    .unwrap_or_else(|_| "-".to_string());
+
+                Some(format!("{i:<4} {name:<40} {temp:>6} {watts:>8}"))
+            })
+            .collect()
+    }
+
+    /// Readings of every device, in index order. A device that can't
+    /// be opened reads zero rather than shifting the ones after it
+    pub fn values(&self) -> Vec<NvmlValues> {
+        let count = self.0.device_count().unwrap_or(0);
+        (0..count).map(|i| self.device_values(i)).collect()
+    }
+
+    /// Every process on any of the devices with the memory it holds,
+    /// compute and graphics alike, and its SM utilization on cards that
+    /// keep per-process samples (Maxwell and newer). A process with
+    /// several contexts is listed once, with the most memory any of them
+    /// holds
+    pub fn processes(&self) -> Vec<GpuProcess> {
+        let count = self.0.device_count().unwrap_or(0);
+        let mut ret = Vec::new();
+
+        for i in 0..count {
+            let Ok(device) = self.0.device_by_index(i) else {
+                continue;
+            };
+            let samples =
+                device.process_utilization_stats(None).unwrap_or_default();
+            let running = device
+                .running_compute_processes()
+                .unwrap_or_default()
+                .into_iter()
+                .chain(device.running_graphics_processes().unwrap_or_default());
+
+            for p in running {
+                let mem_mib = match p.used_gpu_memory {
+                    UsedGpuMemory::Used(b) => b / B_TO_MIB,
+                    UsedGpuMemory::Unavailable => 0,
+                };
+                // the driver buffers a few seconds of samples, the
+                // newest is what's happening now
+                let util = samples
+                    .iter()
+                    .filter(|s| s.pid == p.pid)
+                    .max_by_key(|s| s.timestamp)
+                    .map(|s| s.sm_util);
+
+                match ret.iter_mut().find(|g: &&mut GpuProcess| g.pid == p.pid)
+                {
+                    Some(g) => g.mem_mib = g.mem_mib.max(mem_mib),
+                    None => ret.push(GpuProcess {
+                        pid: p.pid,
+                        util,
+                        mem_mib,
+                    }),
+                }
+            }
+        }
+
+        ret
+    }
+
+    fn device_values(&self, index: u32) -> NvmlValues {
+        let mut temp: f64 = 0.0;
+        let mut watts: f64 = 0.0;
+        let mut mem_used: u64 = 0;
+        let mut mem_reserved: u64 = 0;
+        let mut mem_total: u64 = 0;
+        let mut gpu_busy: u32 = 0;
+        let mut mem_busy: u32 = 0;
+        let mut clock_mhz: u32 = 0;
+        let mut mem_clock_mhz: u32 = 0;
+        let mut throttle = Throttle::default();
+        let mut fan_pct = None;
+        let mut mem_temp = None;
+
+        if let Ok(device) = self.0.device_by_index(index) {
+            if let Ok(c) = device.temperature(TemperatureSensor::Gpu) {
+                temp = c as f64;
+            }
+
+            if let Ok(mw) = device.power_usage() {
+                watts = mw as f64 / 1000.0;
+            }
+
+            if let Ok(mem_info) = device.memory_info() {
+                mem_used = mem_info.used / B_TO_MIB;
+                mem_reserved = mem_info.reserved / B_TO_MIB;
+                mem_total = mem_info.total / B_TO_MIB;
+            }
+
+            // share of time the memory controller was busy, as opposed
+            // to how much of the memory is allocated
+            if let Ok(util) = device.utilization_rates() {
+                gpu_busy = util.gpu;
+                mem_busy = util.memory;
+            }
+
+            if let Ok(mhz) = device.clock_info(Clock::Graphics) {
+                clock_mhz = mhz;
+            }
+
+            if let Ok(mhz) = device.clock_info(Clock::Memory) {
+                mem_clock_mhz = mhz;
+            }
+
+            fan_pct = device.fan_speed(0).ok();
+            mem_temp = self::mem_temp(&device);
+
+            if let Ok(reasons) = device.current_throttle_reasons() {
+                throttle = Throttle {
+                    thermal: reasons.intersects(THERMAL_REASONS),
+                    power: reasons.intersects(POWER_REASONS),
+                    sw_cap: reasons.contains(ThrottleReasons::SW_POWER_CAP),
+                };
+            }
+        }
+
+        NvmlValues {
+            temp,
+            watts,
+            mem_used,
+            mem_reserved,
+            mem_total,
+            gpu_busy,
+            mem_busy,
+            clock_mhz,
+            mem_clock_mhz,
+            throttle,
+            fan_pct,
+            mem_temp,
+        }
+    }
+
+    /// Temperature and power of every device as `tempN` and `powerN` of
+    /// an `nvml` chip, numbered from 1 and labelled with the device name
+    pub fn readings(&self) -> Vec<Reading> {
+        let count = self.0.device_count().unwrap_or(0);
+        let mut ret = Vec::new();
+
+        for i in 0..count {
+            let Ok(device) = self.0.device_by_index(i) else {
+                continue;
+            };
+            let label = device.name().ok();
+
+            let temp = device
+                .temperature(TemperatureSensor::Gpu)
+                .map(|c| ("temp", c as f64));
+            let power =
+                device.power_usage().map(|mw| ("power", mw as f64 / 1000.0));
+
+            for (kind, value) in [temp, power].into_iter().flatten() {
+                ret.push(Reading {
+                    chip: "nvml".to_string(),
+                    serial: None,
+                    feature: format!("{kind}{}", i + 1),
+                    label: label.clone(),
+                    value,
+                });
+            }
+        }
+
+        ret
+    }
+}
+
+impl SensorProvider for Gpu {
+    fn poll(&mut self) -> Vec<Reading> {
+        self.readings()
+    }
+}
+
+/// For a handle that's also read for the gauges
+impl SensorProvider for Rc<Gpu> {
+    fn poll(&mut self) -> Vec<Reading> {
+        self.readings()
+    }
+}
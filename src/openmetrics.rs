@@ -0,0 +1,93 @@
+//! One-shot OpenMetrics text snapshots of the current readings
+
+use std::fmt::Write;
+
+/// Escape a label value: backslash, double quote and newline
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// What a reading measures, which picks the metric family it's written
+/// under
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Temperature,
+    /// The coolant-to-ambient difference
+    CoolantDelta,
+    /// A `[[commands]]` reading, in whatever the command measures
+    Command,
+}
+
+impl Kind {
+    const ALL: [Kind; 3] =
+        [Kind::Temperature, Kind::CoolantDelta, Kind::Command];
+
+    /// Metric name and unit. A command's unit isn't known, so its
+    /// family has none
+    fn metric(self) -> (&'static str, &'static str) {
+        match self {
+            Kind::Temperature => ("sensors_mon_temperature_celsius", "celsius"),
+            Kind::CoolantDelta => {
+                ("sensors_mon_coolant_delta_celsius", "celsius")
+            }
+            Kind::Command => ("sensors_mon_command_value", ""),
+        }
+    }
+}
+
+/// Collects metric families and renders them in OpenMetrics text format
+#[derive(Default)]
+pub struct Snapshot {
+    out: String,
+}
+
+impl Snapshot {
+    /// Add a gauge family with one sample per `(sensor, value)`. An empty
+    /// sensor name means the sample has no label, an empty unit that the
+    /// family has none
+    pub fn gauge(&mut self, name: &str, unit: &str, samples: &[(&str, f64)]) {
+        if samples.is_empty() {
+            return;
+        }
+
+        // writing to a String can't fail
+        let _ = writeln!(self.out, "# TYPE {name} gauge");
+        if !unit.is_empty() {
+            let _ = writeln!(self.out, "# UNIT {name} {unit}");
+        }
+
+        for (sensor, value) in samples {
+            if sensor.is_empty() {
+                let _ = writeln!(self.out, "{name} {value}");
+            } else {
+                let _ = writeln!(
+                    self.out,
+                    "{name}{{sensor=\"{}\"}} {value}",
+                    escape(sensor)
+                );
+            }
+        }
+    }
+
+    /// Add `readings` as a gauge family per kind, `kinds` giving the
+    /// kind of each reading
+    pub fn readings(&mut self, readings: &[(String, f64)], kinds: &[Kind]) {
+        for kind in Kind::ALL {
+            let samples: Vec<(&str, f64)> = readings
+                .iter()
+                .zip(kinds)
+                .filter(|(_, k)| **k == kind)
+                .map(|((l, v), _)| (l.as_str(), *v))
+                .collect();
+            let (name, unit) = kind.metric();
+            self.gauge(name, unit, &samples);
+        }
+    }
+
+    pub fn finish(mut self) -> String {
+        self.out.push_str("# EOF\n");
+        self.out
+    }
+}
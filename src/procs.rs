@@ -0,0 +1,142 @@
+//! The processes working the machine hardest, so a temperature spike
+//! can be pinned on something without switching to htop. CPU time comes
+//! from /proc/<pid>/stat, which only has counters, so like utilization
+//! it's how far they moved since the last read. GPU use comes from NVML
+//! and is merged in by the caller
+
+use std::{
+    collections::HashMap,
+    fs::{read_dir, read_to_string},
+    time::Instant,
+};
+
+const PROC_ROOT: &str = "/proc";
+
+/// Clock ticks per second of the /proc counters, which the kernel keeps
+/// at 100 for userspace on every architecture
+const USER_HZ: f64 = 100.0;
+
+/// What one process was doing since the last read
+#[derive(Debug, Default, Clone)]
+pub struct Process {
+    pub pid: u32,
+    pub name: String,
+    /// Share of one core, like `top`, so it goes past 100 for threaded
+    /// programs
+    pub cpu: f64,
+    /// SM utilization in percent, None when NVML doesn't report it
+    pub gpu: Option<u32>,
+    /// GPU memory in MiB, 0 for processes not on the GPU
+    pub gpu_mem: u64,
+}
+
+/// A process NVML lists as running on a GPU
+#[derive(Debug, Clone, Copy)]
+pub struct GpuProcess {
+    pub pid: u32,
+    pub util: Option<u32>,
+    pub mem_mib: u64,
+}
+
+/// Keeps each process's CPU time from the last read
+#[derive(Debug, Default)]
+pub struct ProcStat {
+    read_at: Option<Instant>,
+    last: HashMap<u32, u64>,
+}
+
+/// `comm` of a process, the executable name the kernel truncates to 15
+/// characters
+pub fn name(pid: u32) -> String {
+    read_to_string(format!("{PROC_ROOT}/{pid}/comm"))
+        .map(|s| s.trim().to_string())
+        .unwrap_or_else(|_| "?".to_string())
+}
+
+/// User plus system ticks of one process. The name in the second field
+/// can hold spaces and parentheses, so the fields are counted from the
+/// last `)`
+fn cpu_ticks(pid: u32) -> Option<u64> {
+    let stat = read_to_string(format!("{PROC_ROOT}/{pid}/stat")).ok()?;
+    let rest = &stat[stat.rfind(')')? + 1..];
+    let fields: Vec<&str> = rest.split_whitespace().collect();
+
+    // utime and stime are the 14th and 15th fields, the state (3rd) is
+    // the first after the name
+    let utime: u64 = fields.get(11)?.parse().ok()?;
+    let stime: u64 = fields.get(12)?.parse().ok()?;
+    Some(utime + stime)
+}
+
+impl ProcStat {
+    /// Every process that used any CPU since the last read. The first
+    /// call only primes the counters and reports none
+    pub fn read(&mut self) -> Vec<Process> {
+        let now = Instant::now();
+        let secs = self
+            .read_at
+            .map(|then| now.duration_since(then).as_secs_f64());
+
+        let ticks: HashMap<u32, u64> = read_dir(PROC_ROOT)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|e| e.file_name().to_str()?.parse::<u32>().ok())
+            .filter_map(|pid| Some((pid, cpu_ticks(pid)?)))
+            .collect();
+
+        let mut ret = Vec::new();
+        if let Some(secs) = secs.filter(|s| *s > 0.0) {
+            for (pid, now) in &ticks {
+                let Some(last) = self.last.get(pid) else {
+                    continue;
+                };
+                let used = now.saturating_sub(*last);
+                if used == 0 {
+                    continue;
+                }
+                ret.push(Process {
+                    pid: *pid,
+                    name: name(*pid),
+                    cpu: used as f64 / USER_HZ / secs * 100.0,
+                    ..Process::default()
+                });
+            }
+        }
+
+        self.read_at = Some(now);
+        self.last = ticks;
+        ret
+    }
+}
+
+/// Merge `gpu` into `procs` and keep the `count` busiest, by CPU and GPU
+/// use added up
+pub fn top(
+    mut procs: Vec<Process>,
+    gpu: &[GpuProcess],
+    count: usize,
+) -> Vec<Process> {
+    for g in gpu {
+        match procs.iter_mut().find(|p| p.pid == g.pid) {
+            Some(p) => {
+                p.gpu = g.util;
+                p.gpu_mem = g.mem_mib;
+            }
+            None => procs.push(Process {
+                pid: g.pid,
+                name: name(g.pid),
+                cpu: 0.0,
+                gpu: g.util,
+                gpu_mem: g.mem_mib,
+            }),
+        }
+    }
+
+    let load = |p: &Process| p.cpu + p.gpu.unwrap_or(0) as f64;
+    procs.sort_by(|a, b| {
+        load(b).total_cmp(&load(a)).then(b.gpu_mem.cmp(&a.gpu_mem))
+    });
+    procs.truncate(count);
+    procs
+}
@@ -0,0 +1,74 @@
+//! One-line text prompt for session notes
+
+use ratatui::{
+    crossterm::event::KeyCode,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Color, Stylize},
+    text::Line,
+    widgets::{Block, Clear, Paragraph},
+    Frame,
+};
+
+/// Notes longer than this are cut off, they're one line in the history
+/// file and the reports
+const MAX_LEN: usize = 200;
+
+/// What the app should do after the prompt handled a key
+pub enum Action {
+    None,
+    Submit(String),
+    Cancel,
+}
+
+#[derive(Debug, Default)]
+pub struct NotePrompt {
+    text: String,
+}
+
+impl NotePrompt {
+    pub fn handle_key(&mut self, key: KeyCode) -> Action {
+        match key {
+            KeyCode::Esc => return Action::Cancel,
+            KeyCode::Enter => {
+                let text = self.text.trim();
+                if text.is_empty() {
+                    return Action::Cancel;
+                }
+                return Action::Submit(text.to_string());
+            }
+            KeyCode::Backspace => {
+                self.text.pop();
+            }
+            KeyCode::Char(c) if self.text.chars().count() < MAX_LEN => {
+                self.text.push(c)
+            }
+            _ => {}
+        }
+
+        Action::None
+    }
+
+    pub fn render(&self, frame: &mut Frame, area: Rect) {
+        let [area] = Layout::horizontal([Constraint::Length(60)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [area] = Layout::vertical([Constraint::Length(3)])
+            .flex(Flex::Center)
+            .areas(area);
+
+        // keep the end of a long note in view
+        let width = area.width.saturating_sub(3) as usize;
+        let skip = self.text.chars().count().saturating_sub(width);
+        let visible: String = self.text.chars().skip(skip).collect();
+
+        let footer = Line::from(" enter save  esc cancel ");
+        let text = Paragraph::new(format!("{visible}_")).block(
+            Block::bordered()
+                .title(" Session note ")
+                .title_bottom(footer.fg(Color::Gray)),
+        );
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(text, area);
+    }
+}
@@ -0,0 +1,325 @@
+//! Sources of readings, polled once a tick. The dashboard finds all of
+//! its sensors (the `[hardware]` roles, `[[sensors]]` rules, virtual
+//! sensors and the hwmon GPU fallback) in what the providers return, so
+//! a new kind of source only has to implement `SensorProvider`. The
+//! sensor backend and NVML are the built-in providers
+
+#[cfg(feature = "hwmon-sysfs")]
+use crate::hwmon;
+#[cfg(feature = "libsensors")]
+use crate::lmsensors;
+use anyhow::{bail, Result};
+use std::{collections::BTreeMap, fs::read_to_string, path::Path};
+
+/// What identifies a sensor from one poll to the next. The serial pins
+/// it to one USB device when there are several identical ones
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SensorId {
+    pub chip: String,
+    pub serial: Option<String>,
+    pub feature: String,
+}
+
+/// One temperature (`tempN`, degrees C), power (`powerN`, watts), fan
+/// (`fanN`, RPM) or voltage (`inN`, volts) feature as found by a provider
+#[derive(Debug, Clone)]
+pub struct Reading {
+    pub chip: String,
+    pub serial: Option<String>,
+    pub feature: String,
+    pub label: Option<String>,
+    pub value: f64,
+}
+
+impl Reading {
+    pub fn id(&self) -> SensorId {
+        SensorId {
+            chip: self.chip.clone(),
+            serial: self.serial.clone(),
+            feature: self.feature.clone(),
+        }
+    }
+
+    pub fn is_temp(&self) -> bool {
+        self.feature.starts_with("temp")
+    }
+
+    /// A fan or pump speed. Flow sensors are `fanN` features too, but
+    /// aren't counted
+    pub fn is_fan(&self) -> bool {
+        self.feature.starts_with("fan") && !self.is_flow()
+    }
+
+    /// A flow sensor, which Aquacomputer devices report as a `fanN`
+    /// feature labelled `Flow...` in dL/h
+    pub fn is_flow(&self) -> bool {
+        self.feature.starts_with("fan")
+            && self.label.as_deref().is_some_and(|l| l.starts_with("Flow"))
+    }
+
+    pub fn is_voltage(&self) -> bool {
+        self.feature.starts_with("in")
+    }
+
+    /// Whether this is `feature`, by name (`temp1`) or by the label the
+    /// driver gives it (`Tctl`)
+    pub fn is(&self, feature: &str) -> bool {
+        self.feature == feature || self.label.as_deref() == Some(feature)
+    }
+}
+
+pub trait SensorProvider {
+    /// Every reading that currently has a value
+    fn poll(&mut self) -> Vec<Reading>;
+}
+
+/// Poll every provider in turn. NaN and infinite values (which some
+/// drivers report for a disconnected probe) are dropped here, so they
+/// read as missing everywhere rather than each consumer having to check
+pub fn poll_all(providers: &mut [Box<dyn SensorProvider>]) -> Vec<Reading> {
+    providers
+        .iter_mut()
+        .flat_map(|p| p.poll())
+        .filter(|r| r.value.is_finite())
+        .collect()
+}
+
+/// Polls another provider several times in a row and reports the median
+/// of each reading, which throws out the odd wild value some cheap HID
+/// sensors return. `samples` gives the number of reads for a reading,
+/// where 1 leaves it as it is
+pub struct Median<P> {
+    inner: P,
+    samples: Box<dyn Fn(&Reading) -> usize>,
+}
+
+impl<P: SensorProvider> Median<P> {
+    pub fn new(
+        inner: P,
+        samples: impl Fn(&Reading) -> usize + 'static,
+    ) -> Self {
+        Self {
+            inner,
+            samples: Box::new(samples),
+        }
+    }
+}
+
+fn median(values: &mut [f64]) -> f64 {
+    values.sort_by(f64::total_cmp);
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    }
+}
+
+impl<P: SensorProvider> SensorProvider for Median<P> {
+    fn poll(&mut self) -> Vec<Reading> {
+        let mut first = self.inner.poll();
+        let most = first.iter().map(|r| (self.samples)(r)).max().unwrap_or(1);
+        if most <= 1 {
+            return first;
+        }
+
+        let extra: Vec<BTreeMap<SensorId, f64>> = (1..most)
+            .map(|_| {
+                let poll = self.inner.poll();
+                poll.into_iter().map(|r| (r.id(), r.value)).collect()
+            })
+            .collect();
+
+        for reading in &mut first {
+            let k = (self.samples)(reading);
+            if k <= 1 {
+                continue;
+            }
+
+            // a read where the sensor dropped out just leaves fewer
+            // values to take the median of
+            let id = reading.id();
+            let mut values: Vec<f64> = std::iter::once(reading.value)
+                .chain(
+                    extra[..k - 1]
+                        .iter()
+                        .filter_map(|poll| poll.get(&id).copied()),
+                )
+                .collect();
+            reading.value = median(&mut values);
+        }
+
+        first
+    }
+}
+
+/// Any readable value (temperature, voltage, fan speed...) as found by
+/// a backend, for `sensors-mon list`
+#[derive(Debug)]
+pub struct SubFeatureReading {
+    pub chip: String,
+    pub serial: Option<String>,
+    pub feature: String,
+    pub label: Option<String>,
+    pub sub_feature: String,
+    pub value: f64,
+}
+
+/// GPU readings, from whichever GPU provider is in use. Readings the
+/// provider doesn't have are zero, and the ones only some cards have
+/// are None
+#[derive(Debug, Default, Clone)]
+pub struct NvmlValues {
+    pub temp: f64,
+    pub watts: f64,
+    pub mem_used: u64,
+    pub mem_reserved: u64,
+    pub mem_total: u64,
+
+    /// Share of time a kernel was running, in percent
+    pub gpu_busy: u32,
+    /// Memory bandwidth utilization in percent
+    pub mem_busy: u32,
+    /// Graphics clock, 0 when the provider doesn't report it
+    pub clock_mhz: u32,
+    pub mem_clock_mhz: u32,
+    pub throttle: Throttle,
+
+    /// Speed the first fan is asked to run at, in percent. None on
+    /// passively cooled cards
+    pub fan_pct: Option<u32>,
+    /// Memory junction temperature, which only HBM and GDDR6X cards
+    /// report
+    pub mem_temp: Option<f64>,
+}
+
+impl NvmlValues {
+    /// With NaN and infinite readings taken out, as zero (missing) for
+    /// the temperature and power and None for the memory temperature
+    pub fn sanitized(mut self) -> Self {
+        let finite = |v: f64| if v.is_finite() { v } else { 0.0 };
+        self.temp = finite(self.temp);
+        self.watts = finite(self.watts);
+        self.mem_temp = self.mem_temp.filter(|t| t.is_finite());
+        self
+    }
+}
+
+/// Why a GPU is losing clocks. Idling and application clock caps
+/// aren't counted
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Throttle {
+    /// Thermal slowdown, by the driver or the card itself
+    pub thermal: bool,
+    /// Hardware slowdown or power brake, usually the PSU or power
+    /// connector
+    pub power: bool,
+    /// The software power limit
+    pub sw_cap: bool,
+}
+
+impl Throttle {
+    pub fn any(&self) -> bool {
+        self.thermal || self.power || self.sw_cap
+    }
+
+    /// Reasons in both
+    pub fn union(self, other: Self) -> Self {
+        Self {
+            thermal: self.thermal || other.thermal,
+            power: self.power || other.power,
+            sw_cap: self.sw_cap || other.sw_cap,
+        }
+    }
+
+    /// The reasons joined with `/`, like `thermal/sw cap`
+    pub fn describe(&self) -> String {
+        [
+            (self.thermal, "thermal"),
+            (self.power, "power"),
+            (self.sw_cap, "sw cap"),
+        ]
+        .into_iter()
+        .filter_map(|(on, name)| on.then_some(name))
+        .collect::<Vec<_>>()
+        .join("/")
+    }
+}
+
+/// Serial number of the USB device behind the hwmon device at `path`,
+/// if it's on USB and reports one. Unlike the chip name this doesn't
+/// change when the device lands on a different bus address
+pub fn device_serial(path: &Path) -> Option<String> {
+    let device = path.join("device").canonicalize().ok()?;
+
+    device
+        .ancestors()
+        .take_while(|p| p.starts_with("/sys/devices/"))
+        .find_map(|p| {
+            let serial = read_to_string(p.join("serial")).ok()?;
+            let serial = serial.trim();
+            (!serial.is_empty()).then(|| serial.to_string())
+        })
+}
+
+/// Whichever sensor backend could be initialized at runtime
+pub enum Sensors {
+    #[cfg(feature = "libsensors")]
+    LmSensors(lmsensors::Sensors),
+    #[cfg(feature = "hwmon-sysfs")]
+    Hwmon(hwmon::Sensors),
+}
+
+impl Sensors {
+    /// Try each compiled-in backend in turn. libsensors goes first since
+    /// it applies the labels and compute rules from sensors3.conf
+    pub fn init() -> Result<Self> {
+        let mut errors: Vec<String> = Vec::new();
+
+        #[cfg(feature = "libsensors")]
+        match lmsensors::init() {
+            Ok(s) => return Ok(Self::LmSensors(s)),
+            Err(e) => errors.push(format!("libsensors: {e}")),
+        }
+
+        #[cfg(feature = "hwmon-sysfs")]
+        match hwmon::init() {
+            Ok(s) => return Ok(Self::Hwmon(s)),
+            Err(e) => errors.push(format!("hwmon: {e}")),
+        }
+
+        bail!("no sensor backend available ({})", errors.join(", "))
+    }
+
+    /// Whether any chip reports a temperature. Without one the thermal
+    /// zones stand in for the chips
+    pub fn has_temps(&self) -> bool {
+        let readings = match self {
+            #[cfg(feature = "libsensors")]
+            Self::LmSensors(s) => s.readings(),
+            #[cfg(feature = "hwmon-sysfs")]
+            Self::Hwmon(s) => s.readings(),
+        };
+        readings.iter().any(Reading::is_temp)
+    }
+
+    pub fn sub_features(&self) -> Vec<SubFeatureReading> {
+        match self {
+            #[cfg(feature = "libsensors")]
+            Self::LmSensors(s) => s.sub_features(),
+            #[cfg(feature = "hwmon-sysfs")]
+            Self::Hwmon(s) => s.sub_features(),
+        }
+    }
+}
+
+impl SensorProvider for Sensors {
+    fn poll(&mut self) -> Vec<Reading> {
+        match self {
+            #[cfg(feature = "libsensors")]
+            Self::LmSensors(s) => s.readings(),
+            #[cfg(feature = "hwmon-sysfs")]
+            Self::Hwmon(s) => s.readings(),
+        }
+    }
+}
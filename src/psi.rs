@@ -0,0 +1,38 @@
+//! Pressure stall information from /proc/pressure: the share of time
+//! tasks were stalled waiting on CPU, IO or memory. Plotting these next
+//! to the temperatures shows whether a temperature spike lined up with
+//! the machine being starved of something
+
+use std::fs::read_to_string;
+
+const PRESSURE_ROOT: &str = "/proc/pressure";
+
+/// Stall percentages averaged over the last 10 seconds. Zero when the
+/// kernel doesn't have PSI (it needs CONFIG_PSI and can be turned off
+/// with psi=0 on the command line)
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Pressure {
+    pub cpu: f64,
+    pub io: f64,
+    pub memory: f64,
+}
+
+/// The `avg10` of the `some` line of one resource's file, the share of
+/// time at least one task was stalled on it
+fn some_avg10(resource: &str) -> Option<f64> {
+    let text = read_to_string(format!("{PRESSURE_ROOT}/{resource}")).ok()?;
+    let line = text.lines().find(|l| l.starts_with("some "))?;
+
+    line.split_whitespace()
+        .find_map(|f| f.strip_prefix("avg10="))?
+        .parse()
+        .ok()
+}
+
+pub fn read() -> Pressure {
+    Pressure {
+        cpu: some_avg10("cpu").unwrap_or(0.0),
+        io: some_avg10("io").unwrap_or(0.0),
+        memory: some_avg10("memory").unwrap_or(0.0),
+    }
+}
@@ -0,0 +1,119 @@
+//! CPU package power from the RAPL energy counters in powercap. AMD
+//! CPUs show up under the same `intel-rapl` names. The counters only
+//! count up, so power is how far they moved since the last poll. Since
+//! kernel 5.10 they're only readable by root
+
+use crate::provider::{Reading, SensorProvider};
+use anyhow::{bail, Result};
+use std::{
+    fs::{read_dir, read_to_string},
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+const POWERCAP_ROOT: &str = "/sys/class/powercap";
+
+struct Domain {
+    /// `package-0`, `package-1`...
+    name: String,
+    energy: PathBuf,
+
+    /// Value the counter wraps at, in microjoules
+    max: u64,
+    last: Option<(Instant, u64)>,
+}
+
+pub struct Rapl {
+    domains: Vec<Domain>,
+}
+
+fn read_u64(path: &Path) -> Option<u64> {
+    read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// The package domains (`intel-rapl:N`, not their `intel-rapl:N:M`
+/// subzones) whose counters can be read
+pub fn init() -> Result<Rapl> {
+    let mut domains: Vec<Domain> = read_dir(POWERCAP_ROOT)?
+        .flatten()
+        .filter_map(|e| {
+            let dir = e.file_name().into_string().ok()?;
+            let index = dir.strip_prefix("intel-rapl:")?;
+            if index.contains(':') {
+                return None;
+            }
+
+            let path = e.path();
+            let name = read_to_string(path.join("name")).ok()?;
+            let name = name.trim();
+            if !name.starts_with("package") {
+                return None;
+            }
+
+            let energy = path.join("energy_uj");
+            read_u64(&energy)?;
+
+            Some(Domain {
+                name: name.to_string(),
+                max: read_u64(&path.join("max_energy_range_uj"))
+                    .unwrap_or(u64::MAX),
+                energy,
+                last: None,
+            })
+        })
+        .collect();
+    domains.sort_by(|a, b| a.name.cmp(&b.name));
+
+    if domains.is_empty() {
+        bail!("no readable RAPL package domain in {POWERCAP_ROOT}");
+    }
+
+    Ok(Rapl { domains })
+}
+
+impl Domain {
+    /// Average watts since the last call. The first call only primes
+    /// the counter
+    fn watts(&mut self) -> Option<f64> {
+        let now = Instant::now();
+        let uj = read_u64(&self.energy)?;
+        let (then, prev) = self.last.replace((now, uj))?;
+
+        let secs = now.duration_since(then).as_secs_f64();
+        if secs <= 0.0 {
+            return None;
+        }
+
+        let delta = if uj >= prev {
+            uj - prev
+        } else {
+            self.max - prev + uj
+        };
+
+        Some(delta as f64 / 1_000_000.0 / secs)
+    }
+}
+
+impl SensorProvider for Rapl {
+    /// Power of every package as `powerN` of a `rapl` chip, numbered from
+    /// 1 and labelled with the domain name
+    fn poll(&mut self) -> Vec<Reading> {
+        let mut ret = Vec::new();
+
+        for (i, domain) in self.domains.iter_mut().enumerate() {
+            let Some(value) = domain.watts() else {
+                continue;
+            };
+
+            ret.push(Reading {
+                chip: "rapl".to_string(),
+                serial: None,
+                feature: format!("power{}", i + 1),
+                label: Some(domain.name.clone()),
+                value,
+            });
+        }
+
+        ret
+    }
+}
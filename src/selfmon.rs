@@ -0,0 +1,105 @@
+//! The monitor's own footprint: memory, CPU use and how long ticks and
+//! redraws take, shown in the debug pane so regressions are easy to spot
+
+use ratatui::{
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Color, Stylize},
+    text::Line,
+    widgets::{Block, Clear, Paragraph},
+    Frame,
+};
+use std::{
+    fs::read_to_string,
+    time::{Duration, Instant},
+};
+
+/// Units of utime/stime in /proc/self/stat. This is USER_HZ, which the
+/// kernel fixes at 100 on every architecture we run on
+const CLOCK_TICKS: f64 = 100.0;
+
+/// Resident set size in KiB
+fn rss_kib() -> Option<u64> {
+    let status = read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|l| l.starts_with("VmRSS:"))?;
+    line.split_whitespace().nth(1)?.parse().ok()
+}
+
+/// User plus system CPU time used so far, in seconds
+fn cpu_secs() -> Option<f64> {
+    let stat = read_to_string("/proc/self/stat").ok()?;
+    // the command name is in parens and may contain spaces, so count
+    // fields from after it. utime and stime are fields 14 and 15
+    let rest = &stat[stat.rfind(')')? + 2..];
+    let mut fields = rest.split_whitespace().skip(11);
+    let utime: f64 = fields.next()?.parse().ok()?;
+    let stime: f64 = fields.next()?.parse().ok()?;
+    Some((utime + stime) / CLOCK_TICKS)
+}
+
+pub struct SelfStats {
+    pub tick: Duration,
+    pub draw: Duration,
+    rss_kib: Option<u64>,
+
+    /// CPU use since the previous sample, as a percentage of one core
+    cpu_percent: Option<f64>,
+    last_cpu: Option<(Instant, f64)>,
+}
+
+impl SelfStats {
+    pub fn new() -> Self {
+        Self {
+            tick: Duration::ZERO,
+            draw: Duration::ZERO,
+            rss_kib: None,
+            cpu_percent: None,
+            last_cpu: cpu_secs().map(|c| (Instant::now(), c)),
+        }
+    }
+
+    /// Refresh the memory and CPU figures. Called once per tick
+    pub fn sample(&mut self) {
+        self.rss_kib = rss_kib();
+
+        let now = Instant::now();
+        let cpu = cpu_secs();
+        if let (Some((then, before)), Some(cpu)) = (self.last_cpu, cpu) {
+            let elapsed = now.duration_since(then).as_secs_f64();
+            if elapsed > 0.0 {
+                self.cpu_percent = Some((cpu - before) / elapsed * 100.0);
+            }
+        }
+        self.last_cpu = cpu.map(|c| (now, c));
+    }
+
+    /// Draw the pane in the top right corner of `area`
+    pub fn render(&self, frame: &mut Frame, area: Rect) {
+        let [area] = Layout::horizontal([Constraint::Length(24)])
+            .flex(Flex::End)
+            .areas(area);
+        let [area] = Layout::vertical([Constraint::Length(6)]).areas(area);
+
+        let ms = |d: Duration| format!("{:.2}ms", d.as_secs_f64() * 1000.0);
+        let rss = match self.rss_kib {
+            Some(kib) => format!("{:.1}MiB", kib as f64 / 1024.0),
+            None => "?".to_string(),
+        };
+        let cpu = match self.cpu_percent {
+            Some(p) => format!("{p:.1}%"),
+            None => "?".to_string(),
+        };
+
+        let lines = vec![
+            Line::from(format!("RSS   {rss:>14}")),
+            Line::from(format!("CPU   {cpu:>14}")),
+            Line::from(format!("tick  {:>14}", ms(self.tick))),
+            Line::from(format!("draw  {:>14}", ms(self.draw))),
+        ];
+
+        let pane = Paragraph::new(lines)
+            .block(Block::bordered().title(" Debug ").fg(Color::Gray));
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(pane, area);
+    }
+}
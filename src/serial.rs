@@ -0,0 +1,57 @@
+//! Output to a microcontroller-driven desk display over a serial port.
+//! The port isn't configured here, set it up with something like
+//! `stty -F /dev/ttyUSB0 115200 raw` first. Frames are written on a
+//! thread of their own, so a slow baud rate or a display that stopped
+//! reading doesn't hold up a tick
+
+use crate::{config::SerialConfig, model::encode_frame};
+use anyhow::{Context, Result};
+use std::{
+    fs::{File, OpenOptions},
+    io::Write,
+    sync::mpsc::{sync_channel, Receiver, SyncSender},
+    thread,
+};
+
+pub struct Display {
+    tx: SyncSender<Vec<u8>>,
+    sensors: Vec<String>,
+}
+
+fn run(mut port: File, rx: Receiver<Vec<u8>>) {
+    while let Ok(frame) = rx.recv() {
+        // an unplugged display shouldn't take down the UI
+        let _ = port.write_all(&frame);
+    }
+}
+
+impl Display {
+    /// Open the port and start the writer thread, which ends when the
+    /// display is dropped
+    pub fn open(config: &SerialConfig) -> Result<Self> {
+        let port = OpenOptions::new()
+            .write(true)
+            .open(&config.device)
+            .with_context(|| format!("opening {}", config.device.display()))?;
+
+        // room for one frame, written as soon as the last is done
+        let (tx, rx) = sync_channel(1);
+        thread::spawn(move || run(port, rx));
+
+        Ok(Self {
+            tx,
+            sensors: config.sensors.clone(),
+        })
+    }
+
+    /// Labels of the sensors to send, in frame order
+    pub fn sensors(&self) -> &[String] {
+        &self.sensors
+    }
+
+    /// Queue a frame. It's dropped when the port is still busy with the
+    /// one before, the display catches up on the next tick
+    pub fn send(&self, values: &[Option<f64>]) {
+        let _ = self.tx.try_send(encode_frame(values));
+    }
+}
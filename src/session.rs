@@ -0,0 +1,108 @@
+//! The summary printed on quit with `exit_summary`: how long the session
+//! ran, the lowest, average and highest reading of every sensor, how many
+//! alerts fired and the energy the GPUs used, so a quick look at the
+//! machine leaves a record in the scrollback
+
+use sensors_mon::model::MinMax;
+use std::{fmt::Write, time::Instant};
+
+struct Stats {
+    mm: MinMax,
+    sum: f64,
+    count: u64,
+}
+
+pub struct Session {
+    start: Instant,
+    /// By label, in the order the sensors first showed up
+    stats: Vec<(String, Stats)>,
+}
+
+/// `1h02m05s`, `3m10s`, `12s`
+fn fmt_elapsed(secs: u64) -> String {
+    match (secs / 3600, secs / 60 % 60, secs % 60) {
+        (0, 0, s) => format!("{s}s"),
+        (0, m, s) => format!("{m}m{s:02}s"),
+        (h, m, s) => format!("{h}h{m:02}m{s:02}s"),
+    }
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            stats: Vec::new(),
+        }
+    }
+
+    /// Add one tick of labelled readings
+    pub fn record(&mut self, readings: &[(String, f64)]) {
+        for (label, value) in readings {
+            match self.stats.iter_mut().find(|(l, _)| l == label) {
+                Some((_, s)) => {
+                    s.mm.update(*value);
+                    s.sum += value;
+                    s.count += 1;
+                }
+                None => self.stats.push((
+                    label.clone(),
+                    Stats {
+                        mm: MinMax::new(*value),
+                        sum: *value,
+                        count: 1,
+                    },
+                )),
+            }
+        }
+    }
+
+    /// The summary, given the alerts fired and the measured energy in
+    /// Wh. With an electricity `rate` the energy's cost is shown too
+    pub fn report(
+        &self,
+        alerts: usize,
+        energy_wh: f64,
+        rate: Option<f64>,
+    ) -> String {
+        let mut out = String::new();
+
+        let _ = write!(
+            out,
+            "sensors-mon ran for {}, {alerts} alert{}, energy {energy_wh:.1}Wh",
+            fmt_elapsed(self.start.elapsed().as_secs()),
+            if alerts == 1 { "" } else { "s" },
+        );
+        if let Some(rate) = rate {
+            let _ = write!(out, " ({:.3})", energy_wh / 1000.0 * rate);
+        }
+        let _ = writeln!(out);
+
+        if self.stats.is_empty() {
+            return out;
+        }
+
+        let width = self
+            .stats
+            .iter()
+            .map(|(l, _)| l.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max("SENSOR".len());
+        let _ = writeln!(
+            out,
+            "{:<width$} {:>7} {:>7} {:>7}",
+            "SENSOR", "MIN", "AVG", "MAX"
+        );
+        for (label, s) in &self.stats {
+            let _ = writeln!(
+                out,
+                "{label:<width$} {:>7.1} {:>7.1} {:>7.1}",
+                s.mm.min,
+                s.sum / s.count as f64,
+                s.mm.max
+            );
+        }
+
+        out
+    }
+}
@@ -0,0 +1,247 @@
+//! In-app settings menu for the most common config options
+
+use crate::config::{Config, Origin};
+use ratatui::{
+    crossterm::event::KeyCode,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::Line,
+    widgets::{Block, Clear, Row, Table, TableState},
+    Frame,
+};
+use std::path::Path;
+
+const INTERVALS: [u64; 6] = [500, 1000, 2000, 3000, 5000, 10000];
+const WINDOWS: [u64; 7] = [60, 120, 300, 600, 900, 1800, 3600];
+
+#[derive(Debug, Clone, Copy)]
+enum Item {
+    Interval,
+    Window,
+    LegendMinMax,
+    VramReserved,
+    ChartCpu,
+    ChartCoolant,
+    ChartGpu,
+    ChartGuides,
+    ChartGrid,
+}
+
+const ITEMS: [Item; 9] = [
+    Item::Interval,
+    Item::Window,
+    Item::LegendMinMax,
+    Item::VramReserved,
+    Item::ChartCpu,
+    Item::ChartCoolant,
+    Item::ChartGpu,
+    Item::ChartGuides,
+    Item::ChartGrid,
+];
+
+/// Move to the next or previous value of `steps`, starting from the
+/// closest one to `val` so hand-edited values still step sensibly
+fn step(steps: &[u64], val: u64, forward: bool) -> u64 {
+    let idx = steps.iter().position(|s| *s >= val).unwrap_or(steps.len());
+
+    if forward {
+        let exact = steps.get(idx).is_some_and(|s| *s == val);
+        let next = if exact { idx + 1 } else { idx };
+        steps[next.min(steps.len() - 1)]
+    } else {
+        steps[idx.saturating_sub(1)]
+    }
+}
+
+fn on_off(v: bool) -> String {
+    if v { "on" } else { "off" }.to_string()
+}
+
+impl Item {
+    fn label(self) -> &'static str {
+        match self {
+            Item::Interval => "Interval",
+            Item::Window => "Chart window",
+            Item::LegendMinMax => "Legend min/max",
+            Item::VramReserved => "Count reserved VRAM",
+            Item::ChartCpu => "Chart CPU",
+            Item::ChartCoolant => "Chart coolant",
+            Item::ChartGpu => "Chart GPU",
+            Item::ChartGuides => "Value guides",
+            Item::ChartGrid => "Gridlines",
+        }
+    }
+
+    /// Where the setting lives in the config file
+    fn key(self) -> &'static str {
+        match self {
+            Item::Interval => "interval",
+            Item::Window => "window",
+            Item::LegendMinMax => "legend_min_max",
+            Item::VramReserved => "vram_reserved",
+            Item::ChartCpu => "chart.cpu",
+            Item::ChartCoolant => "chart.coolant",
+            Item::ChartGpu => "chart.gpu",
+            Item::ChartGuides => "chart.guides",
+            Item::ChartGrid => "chart.grid",
+        }
+    }
+
+    fn value(self, config: &Config) -> String {
+        match self {
+            Item::Interval => format!("{}ms", config.interval),
+            Item::Window => crate::fmt_duration(config.window),
+            Item::LegendMinMax => on_off(config.legend_min_max),
+            Item::VramReserved => on_off(config.vram_reserved),
+            Item::ChartCpu => on_off(config.chart.cpu),
+            Item::ChartCoolant => on_off(config.chart.coolant),
+            Item::ChartGpu => on_off(config.chart.gpu),
+            Item::ChartGuides => on_off(config.chart.guides),
+            Item::ChartGrid => on_off(config.chart.grid),
+        }
+    }
+
+    fn adjust(self, config: &mut Config, forward: bool) {
+        match self {
+            Item::Interval => {
+                config.interval = step(&INTERVALS, config.interval, forward)
+            }
+            Item::Window => {
+                config.window = step(&WINDOWS, config.window, forward)
+            }
+            Item::LegendMinMax => {
+                config.legend_min_max = !config.legend_min_max
+            }
+            Item::VramReserved => config.vram_reserved = !config.vram_reserved,
+            Item::ChartCpu => config.chart.cpu = !config.chart.cpu,
+            Item::ChartCoolant => config.chart.coolant = !config.chart.coolant,
+            Item::ChartGpu => config.chart.gpu = !config.chart.gpu,
+            Item::ChartGuides => config.chart.guides = !config.chart.guides,
+            Item::ChartGrid => config.chart.grid = !config.chart.grid,
+        }
+    }
+}
+
+/// What the app should do after the menu handled a key
+pub enum Action {
+    None,
+    Changed,
+    Save,
+    Close,
+}
+
+#[derive(Debug, Default)]
+pub struct SettingsMenu {
+    selected: usize,
+
+    /// Result of the last save, shown at the bottom of the menu
+    pub message: Option<String>,
+
+    /// Config keys of the settings changed since the last save. Only
+    /// these are written, so nothing that came from the system-wide file
+    /// or the command line ends up in the user's file
+    pub changed: Vec<&'static str>,
+}
+
+impl SettingsMenu {
+    pub fn handle_key(&mut self, key: KeyCode, config: &mut Config) -> Action {
+        match key {
+            KeyCode::Esc | KeyCode::Char('o' | 'q') => return Action::Close,
+            KeyCode::Char('w') => return Action::Save,
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.selected = self.selected.saturating_sub(1)
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected = (self.selected + 1).min(ITEMS.len() - 1)
+            }
+            KeyCode::Left | KeyCode::Char('h') => {
+                self.adjust(config, false);
+                return Action::Changed;
+            }
+            KeyCode::Right
+            | KeyCode::Char('l')
+            | KeyCode::Enter
+            | KeyCode::Char(' ') => {
+                self.adjust(config, true);
+                return Action::Changed;
+            }
+            _ => {}
+        }
+
+        Action::None
+    }
+
+    fn adjust(&mut self, config: &mut Config, forward: bool) {
+        let item = ITEMS[self.selected];
+        item.adjust(config, forward);
+        if !self.changed.contains(&item.key()) {
+            self.changed.push(item.key());
+        }
+    }
+
+    /// Where the value of `item` comes from, so it's clear what saving
+    /// would change. Blank for the user's file at `user`
+    fn origin(&self, item: Item, config: &Config, user: &Path) -> &str {
+        if self.changed.contains(&item.key()) {
+            return "unsaved";
+        }
+        match config.origins.get(item.key()) {
+            None => "default",
+            Some(Origin::CommandLine) => "flag",
+            Some(Origin::File(path)) if path == user => "",
+            Some(Origin::File(_)) => "system",
+        }
+    }
+
+    pub fn render(
+        &self,
+        config: &Config,
+        user: &Path,
+        frame: &mut Frame,
+        area: Rect,
+    ) {
+        let [area] = Layout::horizontal([Constraint::Length(48)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [area] =
+            Layout::vertical([Constraint::Length(ITEMS.len() as u16 + 4)])
+                .flex(Flex::Center)
+                .areas(area);
+
+        let rows = ITEMS.iter().map(|item| {
+            Row::new(vec![
+                item.label().into(),
+                item.value(config).into(),
+                Line::from(self.origin(*item, config, user).to_string())
+                    .fg(Color::DarkGray),
+            ])
+        });
+
+        let footer = match &self.message {
+            Some(m) => Line::from(m.as_str()),
+            None => Line::from(" \u{2190}\u{2192} change  w save  o close "),
+        };
+
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Fill(1),
+                Constraint::Length(8),
+                Constraint::Length(7),
+            ],
+        )
+        .row_highlight_style(
+            Style::new().bold().fg(Color::Black).bg(Color::Gray),
+        )
+        .block(
+            Block::bordered()
+                .title(" Settings ")
+                .title_bottom(footer.fg(Color::Gray)),
+        );
+
+        let mut state = TableState::default().with_selected(self.selected);
+
+        frame.render_widget(Clear, area);
+        frame.render_stateful_widget(table, area, &mut state);
+    }
+}
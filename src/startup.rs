@@ -0,0 +1,106 @@
+//! The providers that are slow to come up, each probed on a thread of
+//! its own so the dashboard is drawn and sampling right away. Loading
+//! NVML, asking the BMC and opening USB devices can each take a second
+//! or more, which used to be a blank terminal. A provider joins in as
+//! soon as its probe is done. libsensors and the thermal zones can't
+//! leave the UI thread, so those aren't probed here
+
+#[cfg(feature = "nvml")]
+use sensors_mon::nvml;
+#[cfg(feature = "usb-hid")]
+use sensors_mon::usbhid;
+use sensors_mon::{
+    battery,
+    config::{Config, GpuProvider},
+    drives, exec, intel, ipmi,
+    provider::SensorProvider,
+    rapl,
+};
+use std::{
+    sync::mpsc::{channel, Receiver, Sender},
+    thread,
+    time::Duration,
+};
+
+/// Providers are polled in this order however quickly their probes
+/// finish, the sensor backend first
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Rank {
+    Backend,
+    #[cfg(feature = "nvml")]
+    Nvml,
+    Rapl,
+    Battery,
+    Ipmi,
+    Exec,
+    #[cfg(feature = "usb-hid")]
+    UsbHid,
+    Drives,
+}
+
+/// Something a probe found
+pub enum Found {
+    Provider(Rank, Box<dyn SensorProvider + Send>),
+    /// Read for the gauges and polled for `[[sensors]]` rules through
+    /// the same handle
+    #[cfg(feature = "nvml")]
+    Nvml(Box<nvml::Gpu>),
+    Intel(intel::Gpu),
+}
+
+fn probe(
+    tx: &Sender<Found>,
+    find: impl FnOnce() -> Option<Found> + Send + 'static,
+) {
+    let tx = tx.clone();
+    thread::spawn(move || {
+        if let Some(found) = find() {
+            let _ = tx.send(found);
+        }
+    });
+}
+
+fn provider<P: SensorProvider + Send + 'static>(
+    rank: Rank,
+    provider: anyhow::Result<P>,
+) -> Option<Found> {
+    provider
+        .ok()
+        .map(|p| Found::Provider(rank, Box::new(p) as _))
+}
+
+/// Start the probes. What they find is sent as it's found, and the
+/// channel disconnects once every probe is done
+pub fn spawn(config: &Config, no_gpu: bool) -> Receiver<Found> {
+    let (tx, rx) = channel();
+
+    // the GPU is optional, the gauges just read zero without one
+    let gpu = config.hardware.gpu_provider;
+    #[cfg(feature = "nvml")]
+    if !no_gpu && gpu.allows(GpuProvider::Nvml) {
+        probe(&tx, || {
+            nvml::init().ok().map(|gpu| Found::Nvml(Box::new(gpu)))
+        });
+    }
+    if !no_gpu && gpu.allows(GpuProvider::Intel) {
+        probe(&tx, || intel::init().ok().map(Found::Intel));
+    }
+    probe(&tx, || provider(Rank::Rapl, rapl::init()));
+    probe(&tx, || provider(Rank::Battery, battery::init()));
+    probe(&tx, || provider(Rank::Ipmi, ipmi::init()));
+    if !config.commands.is_empty() {
+        let commands = config.commands.clone();
+        let tick = Duration::from_millis(config.interval);
+        probe(&tx, move || {
+            provider(Rank::Exec, Ok(exec::spawn(&commands, tick)))
+        });
+    }
+    #[cfg(feature = "usb-hid")]
+    probe(&tx, || provider(Rank::UsbHid, usbhid::init()));
+    if config.drives.enabled {
+        let smart = config.drives.smart;
+        probe(&tx, move || provider(Rank::Drives, Ok(drives::init(smart))));
+    }
+
+    rx
+}
@@ -0,0 +1,231 @@
+//! The chart as an SVG file, drawn from the series themselves rather
+//! than from terminal cells, so it stays sharp in documentation and
+//! forum posts. It's dark like a terminal so the series colors read the
+//! same as on screen
+
+use ratatui::style::Color;
+use std::fmt::Write;
+
+const WIDTH: f64 = 960.0;
+const PLOT_LEFT: f64 = 64.0;
+const PLOT_RIGHT: f64 = WIDTH - 24.0;
+const PLOT_TOP: f64 = 40.0;
+const PLOT_BOTTOM: f64 = 400.0;
+/// Height of a legend entry, the legend goes under the x axis
+const LEGEND_ROW: f64 = 20.0;
+
+const BACKGROUND: &str = "#1c1c1c";
+const FOREGROUND: &str = "#d0d0d0";
+const GRID: &str = "#3a3a3a";
+
+/// One series, split where ticks were dropped so the gaps stay gaps
+pub struct Line<'a> {
+    pub label: String,
+    pub color: Color,
+    pub segments: Vec<&'a [(f64, f64)]>,
+}
+
+pub struct Chart<'a> {
+    pub title: String,
+    pub lines: Vec<Line<'a>>,
+    pub x_bounds: [f64; 2],
+    pub y_bounds: [f64; 2],
+    /// Spread evenly along the x axis, oldest first
+    pub x_labels: Vec<String>,
+    /// Spread evenly along the y axis, bottom first
+    pub y_labels: Vec<String>,
+    /// Annotations as (x, text)
+    pub markers: Vec<(f64, String)>,
+}
+
+/// The xterm default for the 16 named colors, and the 256 color palette
+/// for indexed ones
+pub fn css_color(color: Color) -> String {
+    const NAMED: [(u8, u8, u8); 16] = [
+        (0x00, 0x00, 0x00),
+        (0xcd, 0x00, 0x00),
+        (0x00, 0xcd, 0x00),
+        (0xcd, 0xcd, 0x00),
+        (0x00, 0x00, 0xee),
+        (0xcd, 0x00, 0xcd),
+        (0x00, 0xcd, 0xcd),
+        (0xe5, 0xe5, 0xe5),
+        (0x7f, 0x7f, 0x7f),
+        (0xff, 0x00, 0x00),
+        (0x00, 0xff, 0x00),
+        (0xff, 0xff, 0x00),
+        (0x5c, 0x5c, 0xff),
+        (0xff, 0x00, 0xff),
+        (0x00, 0xff, 0xff),
+        (0xff, 0xff, 0xff),
+    ];
+
+    let index = match color {
+        Color::Rgb(r, g, b) => return format!("#{r:02x}{g:02x}{b:02x}"),
+        Color::Reset => return FOREGROUND.to_string(),
+        Color::Black => 0,
+        Color::Red => 1,
+        Color::Green => 2,
+        Color::Yellow => 3,
+        Color::Blue => 4,
+        Color::Magenta => 5,
+        Color::Cyan => 6,
+        Color::Gray => 7,
+        Color::DarkGray => 8,
+        Color::LightRed => 9,
+        Color::LightGreen => 10,
+        Color::LightYellow => 11,
+        Color::LightBlue => 12,
+        Color::LightMagenta => 13,
+        Color::LightCyan => 14,
+        Color::White => 15,
+        Color::Indexed(i) => i,
+    };
+
+    let (r, g, b) = match index {
+        0..=15 => NAMED[index as usize],
+        16..=231 => {
+            let i = index - 16;
+            let level = |v: u8| if v == 0 { 0 } else { 55 + v * 40 };
+            (level(i / 36), level(i / 6 % 6), level(i % 6))
+        }
+        _ => {
+            let v = 8 + (index - 232) * 10;
+            (v, v, v)
+        }
+    };
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+impl Chart<'_> {
+    /// Page coordinates of a data point, clamped to the plot
+    fn point(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        let [x0, x1] = self.x_bounds;
+        let [y0, y1] = self.y_bounds;
+        let fx = ((x - x0) / (x1 - x0).max(f64::EPSILON)).clamp(0.0, 1.0);
+        let fy = ((y - y0) / (y1 - y0).max(f64::EPSILON)).clamp(0.0, 1.0);
+        (
+            PLOT_LEFT + fx * (PLOT_RIGHT - PLOT_LEFT),
+            PLOT_BOTTOM - fy * (PLOT_BOTTOM - PLOT_TOP),
+        )
+    }
+
+    pub fn render(&self) -> String {
+        let height = PLOT_BOTTOM + 48.0 + LEGEND_ROW * self.lines.len() as f64;
+        let [y0, y1] = self.y_bounds;
+        let mut out = String::new();
+
+        // writing to a String can't fail
+        let _ = writeln!(
+            out,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{WIDTH}" height="{height}" viewBox="0 0 {WIDTH} {height}" font-family="monospace" font-size="12">"#
+        );
+        let _ = writeln!(
+            out,
+            r#"<rect width="100%" height="100%" fill="{BACKGROUND}"/>"#
+        );
+        let _ = writeln!(
+            out,
+            r#"<text x="{PLOT_LEFT}" y="24" fill="{FOREGROUND}" font-size="14" font-weight="bold">{}</text>"#,
+            escape(&self.title)
+        );
+
+        // horizontal grid lines with the y labels
+        let steps = self.y_labels.len().saturating_sub(1).max(1);
+        for (i, label) in self.y_labels.iter().enumerate() {
+            let val = y0 + i as f64 * (y1 - y0) / steps as f64;
+            let (_, y) = self.point((self.x_bounds[0], val));
+            let _ = writeln!(
+                out,
+                r#"<line x1="{PLOT_LEFT}" y1="{y:.1}" x2="{PLOT_RIGHT}" y2="{y:.1}" stroke="{GRID}"/>"#
+            );
+            let _ = writeln!(
+                out,
+                r#"<text x="{}" y="{:.1}" fill="{FOREGROUND}" text-anchor="end">{}</text>"#,
+                PLOT_LEFT - 8.0,
+                y + 4.0,
+                escape(label)
+            );
+        }
+
+        // the axes themselves
+        let _ = writeln!(
+            out,
+            r#"<polyline points="{PLOT_LEFT},{PLOT_TOP} {PLOT_LEFT},{PLOT_BOTTOM} {PLOT_RIGHT},{PLOT_BOTTOM}" fill="none" stroke="{FOREGROUND}"/>"#
+        );
+        let last = self.x_labels.len().saturating_sub(1).max(1);
+        for (i, label) in self.x_labels.iter().enumerate() {
+            let x =
+                PLOT_LEFT + i as f64 / last as f64 * (PLOT_RIGHT - PLOT_LEFT);
+            let anchor = match i {
+                0 => "start",
+                i if i == last => "end",
+                _ => "middle",
+            };
+            let _ = writeln!(
+                out,
+                r#"<text x="{x:.1}" y="{}" fill="{FOREGROUND}" text-anchor="{anchor}">{}</text>"#,
+                PLOT_BOTTOM + 18.0,
+                escape(label)
+            );
+        }
+
+        for (x, text) in &self.markers {
+            let (x, _) = self.point((*x, y0));
+            let _ = writeln!(
+                out,
+                r#"<line x1="{x:.1}" y1="{PLOT_TOP}" x2="{x:.1}" y2="{PLOT_BOTTOM}" stroke="{}" stroke-dasharray="4 3"/>"#,
+                css_color(Color::DarkGray)
+            );
+            let _ = writeln!(
+                out,
+                r#"<text x="{:.1}" y="{}" fill="{}">{}</text>"#,
+                x + 4.0,
+                PLOT_TOP + 12.0,
+                css_color(Color::DarkGray),
+                escape(text)
+            );
+        }
+
+        for (i, line) in self.lines.iter().enumerate() {
+            let color = css_color(line.color);
+            for segment in &line.segments {
+                let points: Vec<String> = segment
+                    .iter()
+                    .map(|p| {
+                        let (x, y) = self.point(*p);
+                        format!("{x:.1},{y:.1}")
+                    })
+                    .collect();
+                let _ = writeln!(
+                    out,
+                    r#"<polyline points="{}" fill="none" stroke="{color}" stroke-width="1.5" stroke-linejoin="round"/>"#,
+                    points.join(" ")
+                );
+            }
+
+            let y = PLOT_BOTTOM + 40.0 + i as f64 * LEGEND_ROW;
+            let _ = writeln!(
+                out,
+                r#"<rect x="{PLOT_LEFT}" y="{:.1}" width="16" height="4" fill="{color}"/>"#,
+                y - 6.0
+            );
+            let _ = writeln!(
+                out,
+                r#"<text x="{}" y="{y:.1}" fill="{FOREGROUND}">{}</text>"#,
+                PLOT_LEFT + 24.0,
+                escape(&line.label)
+            );
+        }
+
+        out.push_str("</svg>\n");
+        out
+    }
+}
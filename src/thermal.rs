@@ -0,0 +1,141 @@
+//! Temperatures from the kernel's thermal zones in /sys/class/thermal.
+//! ARM boards like the Raspberry Pi and some laptops have no hwmon chip
+//! that reports a temperature, but anything with thermal management has
+//! zones, so they stand in for the chips there
+
+use crate::provider::{Reading, SensorProvider};
+use anyhow::{bail, Result};
+use std::{
+    fs::{read_dir, read_to_string},
+    path::{Path, PathBuf},
+};
+
+const THERMAL_ROOT: &str = "/sys/class/thermal";
+
+/// Chip name the zones are reported under
+pub const CHIP: &str = "thermal";
+
+/// Prefixes of zone types and the labels they're shown with. Types that
+/// aren't listed keep their own name
+const LABELS: [(&str, &str); 12] = [
+    ("cpu", "CPU"),
+    ("x86_pkg_temp", "CPU"),
+    ("TCPU", "CPU"),
+    ("B0D4", "CPU"),
+    ("soc", "SoC"),
+    ("gpu", "GPU"),
+    ("ddr", "Memory"),
+    ("pch_", "PCH"),
+    ("acpitz", "ACPI"),
+    ("iwlwifi", "Wi-Fi"),
+    ("battery", "Battery"),
+    ("nvme", "NVMe"),
+];
+
+struct Zone {
+    label: String,
+    temp: PathBuf,
+}
+
+pub struct Thermal {
+    zones: Vec<Zone>,
+}
+
+/// Label for a zone of type `kind`
+pub fn label(kind: &str) -> &str {
+    LABELS
+        .iter()
+        .find(|(prefix, _)| kind.starts_with(prefix))
+        .map_or(kind, |(_, label)| label)
+}
+
+/// Temperature in degrees, zones report millidegrees
+fn read_temp(path: &Path) -> Option<f64> {
+    let v: i64 = read_to_string(path).ok()?.trim().parse().ok()?;
+    Some(v as f64 / 1000.0)
+}
+
+/// Every zone whose temperature can be read, in the kernel's order.
+/// Zones that map to the same label are numbered from the second one
+/// (`ACPI`, `ACPI 2`...)
+pub fn init() -> Result<Thermal> {
+    let mut zones: Vec<(u32, String, PathBuf)> = read_dir(THERMAL_ROOT)?
+        .flatten()
+        .filter_map(|e| {
+            let dir = e.file_name().into_string().ok()?;
+            let index = dir.strip_prefix("thermal_zone")?.parse().ok()?;
+
+            let path = e.path();
+            let kind = read_to_string(path.join("type")).ok()?;
+            let temp = path.join("temp");
+            read_temp(&temp)?;
+
+            Some((index, label(kind.trim()).to_string(), temp))
+        })
+        .collect();
+    zones.sort_by_key(|(index, ..)| *index);
+
+    if zones.is_empty() {
+        bail!("no readable thermal zone in {THERMAL_ROOT}");
+    }
+
+    let mut ret: Vec<Zone> = Vec::new();
+    for (_, label, temp) in zones {
+        let n = ret
+            .iter()
+            .filter(|z| z.label.split(' ').next() == Some(&label))
+            .count();
+        let label = if n == 0 {
+            label
+        } else {
+            format!("{label} {}", n + 1)
+        };
+        ret.push(Zone { label, temp });
+    }
+
+    Ok(Thermal { zones: ret })
+}
+
+/// The CPU zone's temperature among `readings`, for the CPU role when no
+/// chip matches it
+pub fn cpu(readings: &[Reading]) -> Option<f64> {
+    readings
+        .iter()
+        .find(|r| r.chip == CHIP && r.is("CPU"))
+        .map(|r| r.value)
+}
+
+impl Thermal {
+    /// One line per zone for `sensors-mon list`, as feature, label and
+    /// temperature
+    pub fn describe(&self) -> Vec<String> {
+        self.zones
+            .iter()
+            .enumerate()
+            .filter_map(|(i, z)| {
+                let value = read_temp(&z.temp)?;
+                Some(format!("temp{:<4} {:<20} {value:>10.2}", i + 1, z.label))
+            })
+            .collect()
+    }
+}
+
+impl SensorProvider for Thermal {
+    /// Every zone as `tempN` of a `thermal` chip, numbered from 1 and
+    /// labelled after its type
+    fn poll(&mut self) -> Vec<Reading> {
+        self.zones
+            .iter()
+            .enumerate()
+            .filter_map(|(i, zone)| {
+                Some(Reading {
+                    chip: CHIP.to_string(),
+                    serial: None,
+                    feature: format!("temp{}", i + 1),
+                    label: Some(zone.label.clone()),
+                    value: read_temp(&zone.temp)?,
+                })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,69 @@
+//! Large block-glyph number tiles for reading values from across the
+//! room
+
+use ratatui::{
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{Block, Paragraph},
+    Frame,
+};
+
+pub const TILE_HEIGHT: u16 = 7;
+
+const GLYPH_HEIGHT: usize = 5;
+
+fn glyph(c: char) -> [&'static str; GLYPH_HEIGHT] {
+    match c {
+        '0' => ["███", "█ █", "█ █", "█ █", "███"],
+        '1' => [" █ ", "██ ", " █ ", " █ ", "███"],
+        '2' => ["███", "  █", "███", "█  ", "███"],
+        '3' => ["███", "  █", "███", "  █", "███"],
+        '4' => ["█ █", "█ █", "███", "  █", "  █"],
+        '5' => ["███", "█  ", "███", "  █", "███"],
+        '6' => ["███", "█  ", "███", "█ █", "███"],
+        '7' => ["███", "  █", "  █", "  █", "  █"],
+        '8' => ["███", "█ █", "███", "█ █", "███"],
+        '9' => ["███", "█ █", "███", "  █", "███"],
+        '.' => [" ", " ", " ", " ", "█"],
+        '-' => ["   ", "   ", "███", "   ", "   "],
+        _ => ["   ", "   ", "   ", "   ", "   "],
+    }
+}
+
+/// Render `text` as five lines of block glyphs with one column of space
+/// between characters
+fn big_lines(text: &str) -> Vec<Line<'static>> {
+    (0..GLYPH_HEIGHT)
+        .map(|row| {
+            let line: Vec<&str> = text.chars().map(|c| glyph(c)[row]).collect();
+            Line::from(line.join(" "))
+        })
+        .collect()
+}
+
+/// Draw one tile per `(label, value)` pair, side by side across `area`
+pub fn render(tiles: &[(String, Option<f64>)], frame: &mut Frame, area: Rect) {
+    let areas = Layout::horizontal(tiles.iter().map(|_| Constraint::Fill(1)))
+        .split(area);
+
+    for ((label, value), area) in tiles.iter().zip(areas.iter()) {
+        let block = Block::bordered().title(label.as_str());
+        let inner = block.inner(*area);
+        frame.render_widget(block, *area);
+
+        let text = match value {
+            Some(v) => big_lines(&format!("{v:.1}")),
+            None => vec![Line::from("no reading").dark_gray()],
+        };
+
+        let [inner] = Layout::vertical([Constraint::Length(text.len() as u16)])
+            .flex(Flex::Center)
+            .areas(inner);
+
+        frame.render_widget(
+            Paragraph::new(text).style(Style::new().bold()).centered(),
+            inner,
+        );
+    }
+}
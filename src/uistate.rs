@@ -0,0 +1,64 @@
+//! UI state kept between runs: the chart view, full-screen chart, legend
+//! min/max and the cores, GPU detail and debug panels. It's written on quit and read back at
+//! startup, so toggles don't have to be redone every launch. Unlike the
+//! config this is never edited by hand, and a missing or broken file
+//! leaves everything as the config has it
+
+use crate::config::toml_str;
+use anyhow::{Context, Result};
+use std::{
+    fmt::Write as _,
+    fs::{create_dir_all, read_to_string, write},
+    path::{Path, PathBuf},
+};
+use toml_edit::DocumentMut;
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct UiState {
+    /// Name of the selected `[[views]]` entry. A view that's since been
+    /// removed from the config falls back to the default
+    pub view: Option<String>,
+    pub fullscreen: bool,
+    pub legend_min_max: bool,
+    pub cores: bool,
+    pub gpu_detail: bool,
+    pub debug: bool,
+}
+
+/// `$XDG_STATE_HOME/sensors-mon/ui.toml`
+pub fn default_path() -> PathBuf {
+    crate::state_dir().join("ui.toml")
+}
+
+impl UiState {
+    pub fn load(path: &Path) -> Option<Self> {
+        let doc = read_to_string(path).ok()?.parse::<DocumentMut>().ok()?;
+
+        let flag = |key: &str| doc.get(key).and_then(|v| v.as_bool());
+        Some(Self {
+            view: doc.get("view").and_then(|v| v.as_str()).map(String::from),
+            fullscreen: flag("fullscreen").unwrap_or(false),
+            legend_min_max: flag("legend_min_max").unwrap_or(false),
+            cores: flag("cores").unwrap_or(false),
+            gpu_detail: flag("gpu_detail").unwrap_or(false),
+            debug: flag("debug").unwrap_or(false),
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut out = String::new();
+        if let Some(view) = &self.view {
+            let _ = writeln!(out, "view = {}", toml_str(view));
+        }
+        let _ = writeln!(out, "fullscreen = {}", self.fullscreen);
+        let _ = writeln!(out, "legend_min_max = {}", self.legend_min_max);
+        let _ = writeln!(out, "cores = {}", self.cores);
+        let _ = writeln!(out, "gpu_detail = {}", self.gpu_detail);
+        let _ = writeln!(out, "debug = {}", self.debug);
+
+        if let Some(dir) = path.parent() {
+            create_dir_all(dir)?;
+        }
+        write(path, out).with_context(|| format!("writing {}", path.display()))
+    }
+}
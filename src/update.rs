@@ -0,0 +1,145 @@
+//! Optional check for a newer release on GitHub, and the changelog popup
+//! shown for it. The feed is fetched with `curl` on a background thread
+//! so there's no HTTP stack compiled in and a slow network can't hold up
+//! the UI. Any failure just means no update note is shown
+
+use ratatui::{
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Color, Stylize},
+    text::Line,
+    widgets::{Block, Clear, Paragraph, Wrap},
+    Frame,
+};
+use std::{
+    process::{Command, Stdio},
+    sync::mpsc::{channel, Receiver},
+};
+
+const FEED_URL: &str =
+    "https://api.github.com/repos/jayden-chan/sensors-mon/releases/latest";
+
+/// Give up on the request after this many seconds
+const TIMEOUT_SECS: &str = "10";
+
+#[derive(Debug, Clone)]
+pub struct Release {
+    pub version: String,
+    pub notes: String,
+}
+
+/// Read the JSON string value of `key`. Only handles what the GitHub
+/// release API sends, which is all we need
+fn json_string(json: &str, key: &str) -> Option<String> {
+    let start = json.find(&format!("\"{key}\""))? + key.len() + 2;
+    let rest = json[start..].trim_start().strip_prefix(':')?;
+    let rest = rest.trim_start().strip_prefix('"')?;
+
+    let mut out = String::new();
+    let mut chars = rest.chars();
+    loop {
+        match chars.next()? {
+            '"' => return Some(out),
+            '\\' => match chars.next()? {
+                'n' => out.push('\n'),
+                't' => out.push('\t'),
+                'r' => {}
+                'u' => {
+                    let hex: String = chars.by_ref().take(4).collect();
+                    let c = u32::from_str_radix(&hex, 16).ok()?;
+                    // surrogate pairs (emoji) are dropped rather than
+                    // decoded, they don't render in most terminals anyway
+                    out.extend(char::from_u32(c));
+                }
+                c => out.push(c),
+            },
+            c => out.push(c),
+        }
+    }
+}
+
+/// Whether `latest` is a higher dotted version than `current`. A leading
+/// `v` is ignored
+fn is_newer(latest: &str, current: &str) -> bool {
+    let parse = |v: &str| -> Vec<u64> {
+        v.trim_start_matches('v')
+            .split('.')
+            .map(|p| p.parse().unwrap_or(0))
+            .collect()
+    };
+
+    parse(latest) > parse(current)
+}
+
+fn fetch() -> Option<Release> {
+    let output = Command::new("curl")
+        .args(["-fsSL", "--max-time", TIMEOUT_SECS, FEED_URL])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    let json = String::from_utf8_lossy(&output.stdout);
+    let version = json_string(&json, "tag_name")?;
+    let notes = json_string(&json, "body").unwrap_or_default();
+
+    is_newer(&version, env!("CARGO_PKG_VERSION"))
+        .then_some(Release { version, notes })
+}
+
+pub struct Checker {
+    rx: Receiver<Release>,
+    pub release: Option<Release>,
+}
+
+impl Checker {
+    /// Start checking in the background
+    pub fn spawn() -> Self {
+        let (tx, rx) = channel();
+        std::thread::spawn(move || {
+            if let Some(release) = fetch() {
+                let _ = tx.send(release);
+            }
+        });
+
+        Self { rx, release: None }
+    }
+
+    /// Pick up the result once the check has finished
+    pub fn poll(&mut self) {
+        if let Ok(release) = self.rx.try_recv() {
+            self.release = Some(release);
+        }
+    }
+}
+
+pub fn render_changelog(release: &Release, frame: &mut Frame, area: Rect) {
+    let [area] = Layout::horizontal([Constraint::Percentage(60)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [area] = Layout::vertical([Constraint::Percentage(70)])
+        .flex(Flex::Center)
+        .areas(area);
+
+    let notes = if release.notes.trim().is_empty() {
+        "No release notes."
+    } else {
+        release.notes.trim()
+    };
+
+    let popup = Paragraph::new(notes).wrap(Wrap { trim: false }).block(
+        Block::bordered()
+            .title(format!(
+                " sensors-mon {} (running {}) ",
+                release.version,
+                env!("CARGO_PKG_VERSION")
+            ))
+            .title_bottom(Line::from(" c close ").fg(Color::Gray)),
+    );
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup, area);
+}
@@ -0,0 +1,363 @@
+//! AIO coolers, fan controllers and thermometers read straight from
+//! their USB HID interface in /dev/hidraw, the way liquidctl does, for
+//! kernels whose hwmon drivers don't cover them: NZXT Kraken X2
+//! (X42/52/62/72), X3 (X53/63/73) and Z3 (Z53/63/73), the Corsair
+//! Commander Pro and PCsensor TEMPer USB thermometers. A device a kernel
+//! driver already shows through hwmon is left to it. The hidraw nodes
+//! are root only unless a udev rule opens them up
+//!
+//! Krakens send a status report on their own every second or so, the
+//! Commander Pro and TEMPers have to be asked. Either way each device is
+//! read on a thread of its own, so a slow or unplugged one doesn't hold
+//! up a tick
+
+use crate::provider::{device_serial, Reading, SensorProvider};
+use anyhow::{bail, Result};
+use std::{
+    fs::{read_dir, read_to_string, File, OpenOptions},
+    io::{Read, Write},
+    path::{Path, PathBuf},
+    sync::mpsc::{channel, Receiver, Sender},
+    thread,
+    time::Duration,
+};
+
+const HIDRAW_ROOT: &str = "/sys/class/hidraw";
+
+/// How often the Commander Pro and TEMPers are asked
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy)]
+enum Kind {
+    KrakenX2,
+    KrakenX3,
+    CommanderPro,
+    /// The original TEMPer and TEMPer1, reading in 1/256 degrees
+    Temper,
+    /// TEMPerGold and later TEMPer2s, reading in 1/100 degrees
+    TemperGold,
+}
+
+/// USB vendor and product IDs, the kind of device and the chip name its
+/// readings are reported under (followed by the hidraw node)
+const DEVICES: [(u32, u32, Kind, &str); 7] = [
+    (0x1e71, 0x170e, Kind::KrakenX2, "kraken-x2"),
+    (0x1e71, 0x2007, Kind::KrakenX3, "kraken-x3"),
+    (0x1e71, 0x2014, Kind::KrakenX3, "kraken-x3"),
+    (0x1e71, 0x3008, Kind::KrakenX3, "kraken-z3"),
+    (0x1b1c, 0x0c10, Kind::CommanderPro, "commander-pro"),
+    (0x0c45, 0x7401, Kind::Temper, "temper"),
+    (0x413d, 0x2107, Kind::TemperGold, "temper"),
+];
+
+/// Commander Pro commands, answered with a 16 byte report whose first
+/// byte is a status
+const CPRO_TEMP_CONFIG: u8 = 0x10;
+const CPRO_TEMP: u8 = 0x11;
+const CPRO_FAN_MODES: u8 = 0x20;
+const CPRO_FAN_RPM: u8 = 0x21;
+
+/// Asks a TEMPer for its temperature, answered with an 8 byte report
+/// that has it in the third and fourth bytes
+const TEMPER_READ: [u8; 9] = [0, 0x01, 0x80, 0x33, 0x01, 0, 0, 0, 0];
+
+struct Device {
+    kind: Kind,
+    chip: String,
+    serial: Option<String>,
+    node: PathBuf,
+}
+
+pub struct UsbHid {
+    rx: Receiver<(usize, Vec<Reading>)>,
+    /// Last readings of each device
+    last: Vec<Vec<Reading>>,
+}
+
+/// Vendor and product from the `HID_ID=0003:00001E71:00002007` line of
+/// a hidraw device's uevent
+fn hid_id(uevent: &str) -> Option<(u32, u32)> {
+    let id = uevent.lines().find_map(|l| l.strip_prefix("HID_ID="))?;
+    let mut parts = id.split(':').skip(1);
+    let vendor = u32::from_str_radix(parts.next()?, 16).ok()?;
+    let product = u32::from_str_radix(parts.next()?, 16).ok()?;
+    Some((vendor, product))
+}
+
+/// Every supported device that no kernel driver has claimed, sorted by
+/// hidraw node
+fn devices() -> Vec<Device> {
+    let mut ret: Vec<Device> = read_dir(HIDRAW_ROOT)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|e| {
+            let name = e.file_name().into_string().ok()?;
+            let path = e.path();
+            let uevent = read_to_string(path.join("device/uevent")).ok()?;
+            let (vendor, product) = hid_id(&uevent)?;
+            let (_, _, kind, chip) = DEVICES
+                .iter()
+                .find(|(v, p, ..)| *v == vendor && *p == product)?;
+
+            if path.join("device/hwmon").exists() {
+                return None;
+            }
+            // TEMPers also show up as a keyboard (for typing readings
+            // into a spreadsheet), the sensor is the second interface
+            let temper = matches!(kind, Kind::Temper | Kind::TemperGold);
+            let phys = uevent.lines().find_map(|l| l.strip_prefix("HID_PHYS="));
+            if temper && !phys.is_some_and(|p| p.ends_with("/input1")) {
+                return None;
+            }
+
+            Some(Device {
+                kind: *kind,
+                chip: format!("{chip}-{name}"),
+                serial: device_serial(&path),
+                node: Path::new("/dev").join(&name),
+            })
+        })
+        .collect();
+
+    ret.sort_by(|a, b| a.node.cmp(&b.node));
+    ret
+}
+
+impl Device {
+    fn reading(&self, feature: &str, label: &str, value: f64) -> Reading {
+        Reading {
+            chip: self.chip.clone(),
+            serial: self.serial.clone(),
+            feature: feature.to_string(),
+            label: Some(label.to_string()),
+            value,
+        }
+    }
+
+    /// Liquid temperature and pump (and on the X2, fan) speed from one
+    /// status report, or None for other reports
+    fn parse_kraken(&self, msg: &[u8]) -> Option<Vec<Reading>> {
+        let mut ret = Vec::new();
+
+        match self.kind {
+            Kind::KrakenX2 => {
+                if msg.len() < 7 {
+                    return None;
+                }
+                let temp = msg[1] as f64 + msg[2] as f64 / 10.0;
+                let fan = u16::from_be_bytes([msg[3], msg[4]]);
+                let pump = u16::from_be_bytes([msg[5], msg[6]]);
+                ret.push(self.reading("temp1", "Liquid", temp));
+                ret.push(self.reading("fan1", "Pump", pump as f64));
+                ret.push(self.reading("fan2", "Fan", fan as f64));
+            }
+            Kind::KrakenX3 => {
+                if msg.len() < 20 || msg[..2] != [0x75, 0x02] {
+                    return None;
+                }
+                // 0xffff until the sensor has settled after power on
+                if msg[15..17] != [0xff, 0xff] {
+                    let temp = msg[15] as f64 + msg[16] as f64 / 10.0;
+                    ret.push(self.reading("temp1", "Liquid", temp));
+                }
+                let pump = u16::from_le_bytes([msg[17], msg[18]]);
+                ret.push(self.reading("fan1", "Pump", pump as f64));
+            }
+            _ => return None,
+        }
+
+        Some(ret)
+    }
+
+    /// Read status reports as they come
+    fn run_kraken(
+        &self,
+        mut file: File,
+        index: usize,
+        tx: &Sender<(usize, Vec<Reading>)>,
+    ) {
+        let mut buf = [0; 64];
+
+        while let Ok(n) = file.read(&mut buf) {
+            if n == 0 {
+                return;
+            }
+            let Some(readings) = self.parse_kraken(&buf[..n]) else {
+                continue;
+            };
+            if tx.send((index, readings)).is_err() {
+                return;
+            }
+        }
+    }
+
+    /// Ask the Commander Pro which probes and fans are connected, then
+    /// for their readings every [`POLL_INTERVAL`]
+    fn run_commander_pro(
+        &self,
+        mut file: File,
+        index: usize,
+        tx: &Sender<(usize, Vec<Reading>)>,
+    ) {
+        // reports are unnumbered, so a zero report ID goes first
+        let mut command = |cmd: u8, arg: u8| -> Option<[u8; 16]> {
+            let mut out = [0; 65];
+            out[1] = cmd;
+            out[2] = arg;
+            file.write_all(&out).ok()?;
+
+            let mut res = [0; 16];
+            file.read_exact(&mut res).ok()?;
+            (res[0] == 0).then_some(res)
+        };
+
+        let Some(temps) = command(CPRO_TEMP_CONFIG, 0) else {
+            return;
+        };
+        let Some(fans) = command(CPRO_FAN_MODES, 0) else {
+            return;
+        };
+
+        loop {
+            let mut ret = Vec::new();
+
+            for ch in (0..4).filter(|ch| temps[1 + *ch as usize] == 1) {
+                let Some(res) = command(CPRO_TEMP, ch) else {
+                    return;
+                };
+                let temp = u16::from_be_bytes([res[1], res[2]]) as f64 / 100.0;
+                ret.push(self.reading(
+                    &format!("temp{}", ch + 1),
+                    &format!("Temp {}", ch + 1),
+                    temp,
+                ));
+            }
+            for ch in (0..6).filter(|ch| fans[1 + *ch as usize] != 0) {
+                let Some(res) = command(CPRO_FAN_RPM, ch) else {
+                    return;
+                };
+                let rpm = u16::from_be_bytes([res[1], res[2]]);
+                ret.push(self.reading(
+                    &format!("fan{}", ch + 1),
+                    &format!("Fan {}", ch + 1),
+                    rpm as f64,
+                ));
+            }
+
+            if tx.send((index, ret)).is_err() {
+                return;
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    /// Ask the TEMPer for its temperature every [`POLL_INTERVAL`]
+    fn run_temper(
+        &self,
+        mut file: File,
+        index: usize,
+        tx: &Sender<(usize, Vec<Reading>)>,
+    ) {
+        let divisor = match self.kind {
+            Kind::Temper => 256.0,
+            _ => 100.0,
+        };
+
+        loop {
+            let mut res = [0; 8];
+            if file.write_all(&TEMPER_READ).is_err()
+                || file.read_exact(&mut res).is_err()
+            {
+                return;
+            }
+
+            let temp = i16::from_be_bytes([res[2], res[3]]) as f64 / divisor;
+            let readings = vec![self.reading("temp1", "Ambient", temp)];
+            if tx.send((index, readings)).is_err() {
+                return;
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
+
+/// Start reading every supported device that can be opened. Fails when
+/// there are none
+pub fn init() -> Result<UsbHid> {
+    let (tx, rx) = channel();
+    let mut count = 0;
+
+    for device in devices() {
+        let file = match device.kind {
+            Kind::KrakenX2 | Kind::KrakenX3 => File::open(&device.node),
+            _ => OpenOptions::new().read(true).write(true).open(&device.node),
+        };
+        let Ok(file) = file else {
+            continue;
+        };
+
+        let tx = tx.clone();
+        let index = count;
+        thread::spawn(move || {
+            match device.kind {
+                Kind::CommanderPro => {
+                    device.run_commander_pro(file, index, &tx)
+                }
+                Kind::Temper | Kind::TemperGold => {
+                    device.run_temper(file, index, &tx)
+                }
+                _ => device.run_kraken(file, index, &tx),
+            }
+            // unplugged or stopped answering, its readings drop out
+            let _ = tx.send((index, Vec::new()));
+        });
+        count += 1;
+    }
+
+    if count == 0 {
+        bail!("no supported USB device that can be opened in /dev/hidraw*");
+    }
+
+    Ok(UsbHid {
+        rx,
+        last: vec![Vec::new(); count],
+    })
+}
+
+/// One line per supported device for `sensors-mon list`, as chip name
+/// and hidraw node
+pub fn describe() -> Vec<String> {
+    devices()
+        .iter()
+        .map(|d| format!("{:<32} {}", d.chip, d.node.display()))
+        .collect()
+}
+
+/// A Kraken's liquid temperature among `readings`, for the coolant
+/// gauge when no chip matches the coolant role
+pub fn coolant(readings: &[Reading]) -> Option<f64> {
+    readings
+        .iter()
+        .find(|r| r.chip.starts_with("kraken-") && r.is("Liquid"))
+        .map(|r| r.value)
+}
+
+/// A TEMPer's temperature among `readings`, for the ambient role when no
+/// chip matches it
+pub fn ambient(readings: &[Reading]) -> Option<f64> {
+    readings
+        .iter()
+        .find(|r| r.chip.starts_with("temper-") && r.is("Ambient"))
+        .map(|r| r.value)
+}
+
+impl SensorProvider for UsbHid {
+    /// Whatever each device reported last
+    fn poll(&mut self) -> Vec<Reading> {
+        while let Ok((i, readings)) = self.rx.try_recv() {
+            self.last[i] = readings;
+        }
+        self.last.concat()
+    }
+}